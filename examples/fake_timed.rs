@@ -0,0 +1,23 @@
+//! Run the in-memory fake Timed API for local development
+//!
+//! ```bash
+//! cargo run --example fake_timed --features fake-server -- 127.0.0.1:8000
+//! ```
+
+use libtimed::fake::{FakeTimed, FAKE_TOKEN};
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8000".to_string());
+
+    let server = FakeTimed::bind(addr.as_str()).await?;
+    server.seed_demo_data();
+
+    println!("Fake Timed API listening on {}/api/v1", server.url());
+    println!("Any bearer token is accepted, e.g. {FAKE_TOKEN}");
+    println!("Press Ctrl+C to stop");
+
+    tokio::signal::ctrl_c().await
+}
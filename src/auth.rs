@@ -1,7 +1,9 @@
 use anyhow::Result;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
-use reqwest::{Client, ClientBuilder};
+use futures_util::future::BoxFuture;
+use libtimed::{token::TokenProvider, TimedError};
+use reqwest::Client;
 use serde::Deserialize;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
@@ -60,7 +62,6 @@ struct TokenResponse {
 #[derive(Debug, serde::Deserialize)]
 struct TokenClaims {
     exp: u64,
-    #[allow(dead_code)]
     sub: String,
     #[allow(dead_code)]
     aud: String,
@@ -72,13 +73,11 @@ pub struct AuthClient {
 }
 
 impl AuthClient {
-    pub fn new(config: TimedConfig) -> Self {
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .unwrap_or_else(|_| Client::new());
+    pub fn new(config: TimedConfig) -> Result<Self> {
+        // Talk to the SSO provider through the same proxy and certificates
+        let client = config.client_builder()?.build_http_client()?;
 
-        Self { client, config }
+        Ok(Self { client, config })
     }
 
     /// Get the OpenID Connect configuration from the discovery URL
@@ -293,6 +292,11 @@ impl AuthClient {
         Ok(claims)
     }
 
+    /// Get the subject of a token, the SSO account it was issued to
+    pub fn token_subject(&self, token: &str) -> Option<String> {
+        self.decode_token(token).ok().map(|claims| claims.sub)
+    }
+
     /// Check if the token is expired
     pub fn is_token_expired(&self, token: &str) -> bool {
         match self.decode_token(token) {
@@ -404,7 +408,7 @@ impl AuthClient {
             ("refresh_token", refresh_token),
         ];
 
-        let client = reqwest::Client::new();
+        let client = &self.client;
         let response = client
             .post(&token_url)
             .form(&params)
@@ -489,6 +493,21 @@ impl AuthClient {
         Ok(token)
     }
 
+    /// Get a new token, preferring the refresh token over a new login
+    pub async fn renew_token(&self) -> Result<String, AuthError> {
+        if let Ok(refresh_token) = self.config.get_refresh_token() {
+            match self.refresh_token(&refresh_token).await {
+                Ok(token) => return Ok(token),
+                Err(e) => warn!(
+                    "Failed to refresh token: {}, starting new authentication flow",
+                    e
+                ),
+            }
+        }
+
+        self.authenticate().await
+    }
+
     /// Force renewal of the authentication token
     pub async fn force_renew_token(&self) -> Result<String, AuthError> {
         info!("Forcing token renewal");
@@ -501,6 +520,24 @@ impl AuthClient {
     }
 }
 
+impl TokenProvider for AuthClient {
+    fn token(&self) -> BoxFuture<'_, libtimed::Result<String>> {
+        Box::pin(async move {
+            self.ensure_valid_token()
+                .await
+                .map_err(|e| TimedError::Token(e.to_string()))
+        })
+    }
+
+    fn refresh(&self) -> BoxFuture<'_, libtimed::Result<String>> {
+        Box::pin(async move {
+            self.renew_token()
+                .await
+                .map_err(|e| TimedError::Token(e.to_string()))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -514,7 +551,7 @@ mod tests {
             ..TimedConfig::default()
         };
 
-        let auth_client = AuthClient::new(config);
+        let auth_client = AuthClient::new(config).unwrap();
 
         // This is an invalid token, so it should be considered expired
         let invalid_token = "invalid.token.format";
@@ -0,0 +1,276 @@
+//! Response cache for read-mostly resources of the Timed API
+//!
+//! Responses are kept in memory for the lifetime of the client and
+//! persisted as JSON files so that subsequent invocations can reuse them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cached::{Cached, TimedCache};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::ApiResource;
+
+/// Default time to live per cached resource
+const DEFAULT_TTLS: &[(ApiResource, Duration)] = &[
+    (ApiResource::Users, Duration::from_secs(60 * 60)),
+    (ApiResource::Customers, Duration::from_secs(24 * 60 * 60)),
+    (ApiResource::Projects, Duration::from_secs(24 * 60 * 60)),
+    (ApiResource::Tasks, Duration::from_secs(6 * 60 * 60)),
+];
+
+/// Get the default directory of the on-disk cache
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("timedctl"))
+}
+
+/// Get the directory of the on-disk cache of one user
+///
+/// Responses like `users/me` differ per user, so every user gets a directory
+/// of their own below [`default_cache_dir`].
+pub fn user_cache_dir(user: &str) -> Option<PathBuf> {
+    let name: String = user
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.@".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    default_cache_dir().map(|dir| dir.join("users").join(name))
+}
+
+/// A cached response as stored on disk
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    stored_at: u64,
+    value: serde_json::Value,
+}
+
+/// Two-level cache for GET responses, keyed by URL and query
+pub struct ResponseCache {
+    ttls: HashMap<&'static str, Duration>,
+    memory: Mutex<HashMap<&'static str, TimedCache<String, serde_json::Value>>>,
+    dir: Option<PathBuf>,
+}
+
+impl ResponseCache {
+    /// Create a cache that only lives in memory
+    pub fn in_memory() -> Self {
+        Self {
+            ttls: DEFAULT_TTLS
+                .iter()
+                .map(|(resource, ttl)| (resource.as_path(), *ttl))
+                .collect(),
+            memory: Mutex::new(HashMap::new()),
+            dir: None,
+        }
+    }
+
+    /// Create a cache that is also persisted to `dir`
+    pub fn persistent(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            ..Self::in_memory()
+        }
+    }
+
+    /// Override the time to live of a resource, or enable caching for it
+    pub fn with_ttl(mut self, resource: ApiResource, ttl: Duration) -> Self {
+        self.ttls.insert(resource.as_path(), ttl);
+        self
+    }
+
+    /// Get the directory of the on-disk cache, if any
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Get the cached resource an endpoint belongs to, if it is cached at all
+    fn resource_of(&self, endpoint: &str) -> Option<(&'static str, Duration)> {
+        let path = endpoint.split(['/', '?']).next()?;
+        self.ttls
+            .get_key_value(path)
+            .map(|(resource, ttl)| (*resource, *ttl))
+    }
+
+    /// Check whether responses of an endpoint are cached
+    pub fn is_cached(&self, endpoint: &str) -> bool {
+        self.resource_of(endpoint).is_some()
+    }
+
+    /// Look up a response
+    pub fn get(&self, endpoint: &str, key: &str) -> Option<serde_json::Value> {
+        let (resource, ttl) = self.resource_of(endpoint)?;
+
+        let mut memory = self.memory.lock().unwrap();
+        let store = memory
+            .entry(resource)
+            .or_insert_with(|| TimedCache::with_lifespan(ttl));
+        if let Some(value) = store.cache_get(key) {
+            debug!("Memory cache hit for {}", key);
+            return Some(value.clone());
+        }
+
+        let entry = self.read_entry(resource, key)?;
+        let age = now().saturating_sub(entry.stored_at);
+        if entry.key != key || age >= ttl.as_secs() {
+            return None;
+        }
+
+        debug!("Disk cache hit for {}", key);
+        store.cache_set(key.to_string(), entry.value.clone());
+        Some(entry.value)
+    }
+
+    /// Store a response
+    pub fn insert(&self, endpoint: &str, key: &str, value: &serde_json::Value) {
+        let Some((resource, ttl)) = self.resource_of(endpoint) else {
+            return;
+        };
+
+        self.memory
+            .lock()
+            .unwrap()
+            .entry(resource)
+            .or_insert_with(|| TimedCache::with_lifespan(ttl))
+            .cache_set(key.to_string(), value.clone());
+
+        let entry = CacheEntry {
+            key: key.to_string(),
+            stored_at: now(),
+            value: value.clone(),
+        };
+        if let Err(e) = self.write_entry(resource, &entry) {
+            warn!("Failed to write cache entry for {}: {}", key, e);
+        }
+    }
+
+    /// Drop all cached responses of the resource an endpoint belongs to
+    pub fn invalidate(&self, endpoint: &str) {
+        let Some((resource, _)) = self.resource_of(endpoint) else {
+            return;
+        };
+
+        debug!("Invalidating cached {}", resource);
+        self.memory.lock().unwrap().remove(resource);
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir.join(resource));
+        }
+    }
+
+    /// Drop every cached response, in memory and on disk
+    pub fn clear(&self) -> std::io::Result<()> {
+        self.memory.lock().unwrap().clear();
+        match &self.dir {
+            Some(dir) if dir.exists() => fs::remove_dir_all(dir),
+            _ => Ok(()),
+        }
+    }
+
+    fn entry_path(&self, resource: &str, key: &str) -> Option<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let file = format!("{:016x}.json", hasher.finish());
+        self.dir.as_ref().map(|dir| dir.join(resource).join(file))
+    }
+
+    fn read_entry(&self, resource: &str, key: &str) -> Option<CacheEntry> {
+        let path = self.entry_path(resource, key)?;
+        let text = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn write_entry(&self, resource: &str, entry: &CacheEntry) -> std::io::Result<()> {
+        let Some(path) = self.entry_path(resource, &entry.key) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec(entry)?)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_read_mostly_resources_are_cached() {
+        let cache = ResponseCache::in_memory();
+        assert!(cache.is_cached("users/me"));
+        assert!(cache.is_cached("customers"));
+        assert!(cache.is_cached("tasks/12"));
+        assert!(!cache.is_cached("reports"));
+        assert!(!cache.is_cached("activities"));
+    }
+
+    #[test]
+    fn test_user_cache_dirs_are_separate() {
+        let alice = user_cache_dir("alice-1234").unwrap();
+        let bob = user_cache_dir("bob-1234").unwrap();
+        assert_ne!(alice, bob);
+        assert!(alice.starts_with(default_cache_dir().unwrap()));
+        assert_eq!(
+            user_cache_dir("../../etc").unwrap().file_name().unwrap(),
+            ".._.._etc"
+        );
+    }
+
+    #[test]
+    fn test_persistent_cache_survives_new_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let value = serde_json::json!({"data": {"id": "1"}});
+
+        ResponseCache::persistent(dir.path()).insert("users/me", "key", &value);
+
+        let cache = ResponseCache::persistent(dir.path());
+        assert_eq!(cache.get("users/me", "key"), Some(value));
+        assert_eq!(cache.get("users/me", "other"), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let value = serde_json::json!({"data": []});
+
+        ResponseCache::persistent(dir.path()).insert("tasks", "key", &value);
+
+        let cache =
+            ResponseCache::persistent(dir.path()).with_ttl(ApiResource::Tasks, Duration::ZERO);
+        assert_eq!(cache.get("tasks", "key"), None);
+    }
+
+    #[test]
+    fn test_invalidate_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::persistent(dir.path().join("timedctl"));
+        let value = serde_json::json!({"data": []});
+
+        cache.insert("projects", "projects", &value);
+        cache.insert("customers", "customers", &value);
+        cache.invalidate("projects/3");
+        assert_eq!(cache.get("projects", "projects"), None);
+        assert_eq!(cache.get("customers", "customers"), Some(value));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.get("customers", "customers"), None);
+        assert!(!dir.path().join("timedctl").exists());
+    }
+}
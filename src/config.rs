@@ -1,12 +1,19 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use clap::ValueEnum;
 use config::{Config, File, FileFormat};
 use keyring::Entry;
+use libtimed::{
+    transforms::TimedDuration, TimedClient, TimedClientBuilder, DEFAULT_API_NAMESPACE,
+    DEFAULT_TIMEOUT, DEFAULT_USER_AGENT,
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, info};
 
 const APP_NAME: &str = "timedctl";
 const DEFAULT_TIMED_URL: &str = "https://timed.example.com";
@@ -34,6 +41,154 @@ pub struct TimedConfig {
     pub timed_url: String,
     pub sso_discovery_url: String,
     pub sso_client_id: String,
+    /// API namespace below the Timed URL
+    #[serde(default = "default_api_namespace")]
+    pub api_namespace: String,
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Prefix for the User-Agent header, the timedctl version is always appended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Proxy URL for all requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM files with additional root certificates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certificates: Vec<PathBuf>,
+    /// PEM file with client certificate and private key for mTLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<PathBuf>,
+    /// Rounding of report durations
+    #[serde(default)]
+    pub rounding: RoundingConfig,
+    /// Short names for tasks, mapped to `task`, `project/task` or `customer/project/task`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Direction in which durations are rounded to the increment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    #[default]
+    Nearest,
+    Up,
+    Down,
+    None,
+}
+
+/// Rounding applied to a report duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounding {
+    pub increment_minutes: u32,
+    pub mode: RoundingMode,
+}
+
+impl Rounding {
+    /// Round a duration to a multiple of the increment
+    pub fn apply(self, duration: TimedDuration) -> TimedDuration {
+        let increment = i64::from(self.increment_minutes) * 60;
+        if increment == 0 {
+            return duration;
+        }
+
+        let seconds = duration.num_seconds();
+        let steps = match self.mode {
+            RoundingMode::Nearest => (seconds + increment / 2).div_euclid(increment),
+            RoundingMode::Up => (seconds + increment - 1).div_euclid(increment),
+            RoundingMode::Down => seconds.div_euclid(increment),
+            RoundingMode::None => return duration,
+        };
+        TimedDuration::seconds(steps * increment)
+    }
+}
+
+/// Rounding of report durations, with overrides per customer or project
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoundingConfig {
+    /// Increment in minutes that durations are rounded to
+    #[serde(default = "default_rounding_increment")]
+    pub increment_minutes: u32,
+    #[serde(default)]
+    pub mode: RoundingMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<RoundingOverride>,
+}
+
+/// Rounding for a customer, or for a single project if one is given
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoundingOverride {
+    pub customer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub increment_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<RoundingMode>,
+}
+
+fn default_rounding_increment() -> u32 {
+    15
+}
+
+impl Default for RoundingConfig {
+    fn default() -> Self {
+        Self {
+            increment_minutes: default_rounding_increment(),
+            mode: RoundingMode::default(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl RoundingConfig {
+    /// Rounding that keeps every duration as it is
+    pub fn disabled() -> Self {
+        Self {
+            mode: RoundingMode::None,
+            ..Self::default()
+        }
+    }
+
+    /// The rounding for a task of a customer and project
+    ///
+    /// Settings of a project override take precedence over those of a
+    /// customer override, which take precedence over the global settings.
+    pub fn for_task(&self, customer: &str, project: &str) -> Rounding {
+        let for_customer = self
+            .overrides
+            .iter()
+            .filter(|o| o.customer.eq_ignore_ascii_case(customer));
+        let project_override = for_customer.clone().find(|o| {
+            o.project
+                .as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(project))
+        });
+        let customer_override = for_customer.clone().find(|o| o.project.is_none());
+        let layers = [project_override, customer_override];
+
+        Rounding {
+            increment_minutes: layers
+                .iter()
+                .flatten()
+                .find_map(|o| o.increment_minutes)
+                .unwrap_or(self.increment_minutes),
+            mode: layers
+                .iter()
+                .flatten()
+                .find_map(|o| o.mode)
+                .unwrap_or(self.mode),
+        }
+    }
+}
+
+fn default_api_namespace() -> String {
+    DEFAULT_API_NAMESPACE.to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT.as_secs()
 }
 
 impl Default for TimedConfig {
@@ -43,6 +198,14 @@ impl Default for TimedConfig {
             timed_url: DEFAULT_TIMED_URL.to_string(),
             sso_discovery_url: DEFAULT_SSO_DISCOVERY_URL.to_string(),
             sso_client_id: DEFAULT_SSO_CLIENT_ID.to_string(),
+            api_namespace: default_api_namespace(),
+            timeout_secs: default_timeout_secs(),
+            user_agent: None,
+            proxy: None,
+            ca_certificates: Vec::new(),
+            client_identity: None,
+            rounding: RoundingConfig::default(),
+            aliases: BTreeMap::new(),
         }
     }
 }
@@ -100,6 +263,36 @@ impl TimedConfig {
         Ok(())
     }
 
+    /// The task an alias stands for, or the query itself if it is no alias
+    pub fn task_alias<'a>(&'a self, query: &'a str) -> &'a str {
+        self.aliases.get(query.trim()).map_or(query, String::as_str)
+    }
+
+    /// Create a client builder with the HTTP settings of this configuration
+    pub fn client_builder(&self) -> Result<TimedClientBuilder, ConfigurationError> {
+        let user_agent = match &self.user_agent {
+            Some(prefix) => format!("{prefix} {DEFAULT_USER_AGENT}"),
+            None => DEFAULT_USER_AGENT.to_string(),
+        };
+
+        let mut builder = TimedClient::builder(&self.timed_url)
+            .api_namespace(&self.api_namespace)
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .user_agent(user_agent);
+
+        for path in &self.ca_certificates {
+            builder = builder.add_root_certificates_pem(fs::read(path)?);
+        }
+        if let Some(path) = &self.client_identity {
+            builder = builder.identity_pem(fs::read(path)?);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(builder)
+    }
+
     /// Store a token in the keyring
     pub fn store_token(&self, token: &str) -> Result<(), ConfigurationError> {
         let entry = Entry::new(APP_NAME, &self.username)?;
@@ -232,4 +425,151 @@ mod tests {
         let loaded_config = TimedConfig::load(Some(&config_path)).unwrap();
         assert_eq!(loaded_config.username, "testuser");
     }
+
+    #[test]
+    fn test_http_settings_default_for_older_configs() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+username = "testuser"
+timed_url = "https://timed.example.com"
+sso_discovery_url = "https://sso.example.com/realms/example"
+sso_client_id = "timed-client"
+"#,
+        )
+        .unwrap();
+
+        let config = TimedConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.api_namespace, "api/v1");
+        assert_eq!(config.timeout_secs, 30);
+        assert!(config.proxy.is_none());
+        assert!(config.ca_certificates.is_empty());
+        assert!(config.client_builder().unwrap().build().is_ok());
+        assert_eq!(config.rounding, RoundingConfig::default());
+    }
+
+    #[test]
+    fn test_task_aliases() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+username = "testuser"
+timed_url = "https://timed.example.com"
+sso_discovery_url = "https://sso.example.com/realms/example"
+sso_client_id = "timed-client"
+
+[aliases]
+Standup = "acme/internal/meetings"
+"#,
+        )
+        .unwrap();
+
+        let config = TimedConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.task_alias("Standup"), "acme/internal/meetings");
+        assert_eq!(config.task_alias("website/dev"), "website/dev");
+    }
+
+    #[test]
+    fn test_rounding_overrides() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+username = "testuser"
+timed_url = "https://timed.example.com"
+sso_discovery_url = "https://sso.example.com/realms/example"
+sso_client_id = "timed-client"
+
+[rounding]
+increment_minutes = 30
+
+[[rounding.overrides]]
+customer = "Acme"
+increment_minutes = 6
+mode = "up"
+
+[[rounding.overrides]]
+customer = "acme"
+project = "Support"
+mode = "none"
+"#,
+        )
+        .unwrap();
+
+        let rounding = TimedConfig::load(Some(&config_path)).unwrap().rounding;
+        let rounding_for = |customer, project| {
+            let r = rounding.for_task(customer, project);
+            (r.increment_minutes, r.mode)
+        };
+
+        assert_eq!(
+            rounding_for("Other", "Website"),
+            (30, RoundingMode::Nearest)
+        );
+        assert_eq!(rounding_for("Acme", "Website"), (6, RoundingMode::Up));
+        assert_eq!(rounding_for("ACME", "support"), (6, RoundingMode::None));
+        assert_eq!(
+            RoundingConfig::disabled().for_task("Acme", "Website").mode,
+            RoundingMode::None
+        );
+
+        // The section survives a round trip through the config file
+        let config = TimedConfig::load(Some(&config_path)).unwrap();
+        let toml = toml::to_string_pretty(&config).unwrap();
+        let reloaded: TimedConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(reloaded.rounding, config.rounding);
+    }
+
+    #[test]
+    fn test_missing_ca_certificate_is_an_error() {
+        let config = TimedConfig {
+            ca_certificates: vec![PathBuf::from("/nonexistent/ca.pem")],
+            ..TimedConfig::default()
+        };
+        assert!(matches!(
+            config.client_builder(),
+            Err(ConfigurationError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let round = |increment_minutes, mode, m| {
+            Rounding {
+                increment_minutes,
+                mode,
+            }
+            .apply(TimedDuration::minutes(m))
+            .num_minutes()
+        };
+
+        assert_eq!(round(15, RoundingMode::Nearest, 7), 0);
+        assert_eq!(round(15, RoundingMode::Nearest, 8), 15);
+        assert_eq!(round(15, RoundingMode::Nearest, 52), 45);
+        assert_eq!(round(15, RoundingMode::Nearest, 53), 60);
+        assert_eq!(round(15, RoundingMode::Nearest, 90), 90);
+
+        assert_eq!(round(6, RoundingMode::Up, 50), 54);
+        assert_eq!(round(6, RoundingMode::Up, 54), 54);
+        assert_eq!(round(6, RoundingMode::Down, 59), 54);
+        assert_eq!(round(30, RoundingMode::Nearest, 44), 30);
+        assert_eq!(round(30, RoundingMode::Up, 31), 60);
+        assert_eq!(round(30, RoundingMode::None, 31), 31);
+        assert_eq!(round(0, RoundingMode::Up, 31), 31);
+
+        // Seconds count towards the next increment when rounding up
+        let up = Rounding {
+            increment_minutes: 15,
+            mode: RoundingMode::Up,
+        };
+        assert_eq!(
+            up.apply(TimedDuration::seconds(15 * 60 + 1)).num_minutes(),
+            30
+        );
+    }
 }
@@ -0,0 +1,975 @@
+//! In-memory fake of the Timed JSON:API for tests and local development
+//!
+//! The fake serves users, customers, projects, tasks, reports, activities,
+//! attendances, absences and worktime balances over plain HTTP on a local
+//! port. It supports create, update and delete, the filters timedctl uses,
+//! `ordering`, `include` and page-based pagination. Statistics, CSV exports,
+//! intersections and bulk updates work on the stored reports, like the real
+//! API does.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::transforms::TimedDuration;
+use crate::{TimedClient, DEFAULT_API_NAMESPACE, PAGE_NUMBER_PARAM, PAGE_SIZE_PARAM};
+
+/// Token handed to clients of the fake; any bearer token is accepted
+pub const FAKE_TOKEN: &str = "fake-token";
+
+/// Resource types served by the fake
+const RESOURCES: &[&str] = &[
+    "users",
+    "customers",
+    "projects",
+    "tasks",
+    "reports",
+    "activities",
+    "attendances",
+    "absences",
+    "absence-types",
+    "worktime-balances",
+];
+
+/// Statistics and the relationships they group reports by
+const STATISTICS: &[(&str, &[&str])] = &[
+    ("year-statistics", &["user"]),
+    ("month-statistics", &["user"]),
+    ("task-statistics", &["task"]),
+    ("user-statistics", &["user"]),
+    ("project-statistics", &["project"]),
+    ("customer-statistics", &["customer"]),
+];
+
+/// Resources that belong to the user who created them
+const USER_OWNED: &[&str] = &["reports", "activities", "attendances", "absences"];
+
+/// Attributes that must be present when creating a resource
+const REQUIRED_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("reports", &["date", "duration"]),
+    ("activities", &["date", "from-time"]),
+    ("attendances", &["date", "from-time"]),
+    ("absences", &["date"]),
+];
+
+/// Query parameters that are not filters
+const RESERVED_PARAMS: &[&str] = &[
+    "include",
+    "ordering",
+    "year",
+    "month",
+    PAGE_NUMBER_PARAM,
+    PAGE_SIZE_PARAM,
+];
+
+/// A fake Timed server running on a local port
+pub struct FakeTimed {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    server: JoinHandle<()>,
+}
+
+impl FakeTimed {
+    /// Start a fake server on a free local port
+    pub async fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0").await
+    }
+
+    /// Start a fake server on the given address
+    pub async fn bind(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let store = Arc::new(Mutex::new(Store::new()));
+        let server = tokio::spawn(serve(listener, store.clone()));
+
+        debug!("Fake Timed server listening on {}", addr);
+        Ok(Self {
+            addr,
+            store,
+            server,
+        })
+    }
+
+    /// Get the base URL of the server
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Create a client that talks to this server
+    pub fn client(&self) -> TimedClient {
+        TimedClient::new(
+            &self.url(),
+            DEFAULT_API_NAMESPACE,
+            Some(FAKE_TOKEN.to_string()),
+        )
+        .expect("Failed to build a client for the fake server")
+    }
+
+    /// Get the ID of the user `users/me` resolves to
+    pub fn current_user_id(&self) -> String {
+        self.store.lock().unwrap().current_user.clone()
+    }
+
+    /// Store a resource and return its ID
+    pub fn insert(&self, type_name: &str, attributes: Value, relationships: Value) -> String {
+        self.store
+            .lock()
+            .unwrap()
+            .insert(type_name, attributes, relationships)
+    }
+
+    /// Get a stored resource
+    pub fn get(&self, type_name: &str, id: &str) -> Option<Value> {
+        self.store.lock().unwrap().get(type_name, id).cloned()
+    }
+
+    /// Get all stored resources of a type, ordered by ID
+    pub fn all(&self, type_name: &str) -> Vec<Value> {
+        self.store
+            .lock()
+            .unwrap()
+            .resources
+            .get(type_name)
+            .map(|resources| resources.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the requests served so far, like `GET /api/v1/users/me`
+    pub fn requests(&self) -> Vec<String> {
+        self.store.lock().unwrap().requests.clone()
+    }
+
+    /// Make every request with the method to a path like `activities/3` fail
+    pub fn fail(&self, method: &str, path: &str) {
+        self.store
+            .lock()
+            .unwrap()
+            .failing
+            .push(format!("{method} {path}"));
+    }
+
+    /// Make sure a customer, project and task with the given names exist
+    ///
+    /// Returns the ID of the task.
+    pub fn add_task(&self, customer: &str, project: &str, task: &str) -> String {
+        let mut store = self.store.lock().unwrap();
+        let customer_id = store.find_or_insert("customers", customer, json!({}));
+        let project_id = store.find_or_insert(
+            "projects",
+            project,
+            json!({ "customer": to_one("customers", &customer_id) }),
+        );
+        store.find_or_insert(
+            "tasks",
+            task,
+            json!({ "project": to_one("projects", &project_id) }),
+        )
+    }
+
+    /// Fill the server with a small set of example data
+    pub fn seed_demo_data(&self) {
+        let development = self.add_task("Acme", "Website", "Development");
+        self.add_task("Acme", "Website", "Meetings");
+        self.add_task("Globex", "Support", "Tickets");
+
+        for name in ["Vacation", "Sick", "Military"] {
+            self.insert(
+                "absence-types",
+                json!({ "name": name, "fill-worktime": false }),
+                json!({}),
+            );
+        }
+
+        let user = to_one("users", &self.current_user_id());
+        self.insert(
+            "worktime-balances",
+            json!({
+                "date": chrono::Local::now().date_naive().format("%Y-%m-%d").to_string(),
+                "balance": "-1 day, 22:30:00"
+            }),
+            json!({ "user": user }),
+        );
+        self.insert(
+            "reports",
+            json!({
+                "comment": "Initial setup",
+                "date": chrono::Local::now().date_naive().format("%Y-%m-%d").to_string(),
+                "duration": "01:30:00",
+                "review": false,
+                "not-billable": false,
+                "verified": false
+            }),
+            json!({ "user": user, "task": to_one("tasks", &development) }),
+        );
+    }
+}
+
+impl Drop for FakeTimed {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Build a to-one relationship object
+pub fn to_one(type_name: &str, id: &str) -> Value {
+    json!({ "data": { "type": type_name, "id": id } })
+}
+
+/// State of the fake server
+struct Store {
+    resources: BTreeMap<String, BTreeMap<u64, Value>>,
+    next_id: u64,
+    current_user: String,
+    requests: Vec<String>,
+    /// Requests like `PATCH activities/3` that fail with a server error
+    failing: Vec<String>,
+}
+
+impl Store {
+    fn new() -> Self {
+        let mut store = Self {
+            resources: RESOURCES
+                .iter()
+                .map(|type_name| (type_name.to_string(), BTreeMap::new()))
+                .collect(),
+            next_id: 1,
+            current_user: String::new(),
+            requests: Vec::new(),
+            failing: Vec::new(),
+        };
+        store.current_user = store.insert(
+            "users",
+            json!({
+                "username": "fake",
+                "first-name": "Fake",
+                "last-name": "User",
+                "email": "fake@example.com",
+                "is-active": true
+            }),
+            json!({}),
+        );
+        store
+    }
+
+    fn insert(&mut self, type_name: &str, attributes: Value, relationships: Value) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut relationships = match relationships {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        let has_user = relationships
+            .get("user")
+            .is_some_and(|user| !user["data"].is_null());
+        if USER_OWNED.contains(&type_name) && !has_user {
+            relationships.insert("user".to_string(), to_one("users", &self.current_user));
+        }
+
+        let resource = json!({
+            "id": id.to_string(),
+            "type": type_name,
+            "attributes": attributes,
+            "relationships": relationships,
+        });
+        self.resources
+            .entry(type_name.to_string())
+            .or_default()
+            .insert(id, resource);
+        id.to_string()
+    }
+
+    fn find_or_insert(&mut self, type_name: &str, name: &str, relationships: Value) -> String {
+        let existing = self.resources.get(type_name).and_then(|resources| {
+            resources
+                .values()
+                .find(|r| r["attributes"]["name"] == name && r["relationships"] == relationships)
+                .and_then(|r| r["id"].as_str())
+                .map(String::from)
+        });
+        existing.unwrap_or_else(|| {
+            self.insert(
+                type_name,
+                json!({ "name": name, "archived": false }),
+                relationships,
+            )
+        })
+    }
+
+    fn get(&self, type_name: &str, id: &str) -> Option<&Value> {
+        let id = id.parse::<u64>().ok()?;
+        self.resources.get(type_name)?.get(&id)
+    }
+
+    /// Serve one request
+    fn handle(&mut self, method: &str, target: &str, body: &[u8]) -> Reply {
+        self.requests.push(format!("{method} {target}"));
+
+        let Ok(url) = Url::parse(&format!("http://fake{target}")) else {
+            return error(400, "Malformed request target.", None).into();
+        };
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let prefix = format!("/{DEFAULT_API_NAMESPACE}/");
+        let Some(path) = url.path().strip_prefix(&prefix) else {
+            return error(404, "Not found.", None).into();
+        };
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+        if self
+            .failing
+            .contains(&format!("{method} {}", path.trim_end_matches('/')))
+        {
+            return error(500, "A server error occurred.", None).into();
+        }
+
+        match (method, segments.as_slice()) {
+            ("GET", ["reports", "export"]) => return self.export(&params),
+            ("GET", ["reports", "intersection"]) => self.intersection(&params),
+            ("POST", ["reports", "bulk"]) => self.bulk_update(&params, body),
+            ("GET", ["users", "me"]) => {
+                let id = self.current_user.clone();
+                self.show("users", &id, &params)
+            }
+            ("GET", [type_name]) if self.resources.contains_key(*type_name) => {
+                self.list(type_name, &params)
+            }
+            ("GET", [type_name]) if statistic_groups(type_name).is_some() => {
+                let rows = self.statistics(type_name, &params);
+                self.page(type_name, rows.iter().collect(), &params)
+            }
+            ("GET", [type_name, id]) => self.show(type_name, id, &params),
+            ("POST", [type_name]) if self.resources.contains_key(*type_name) => {
+                self.create(type_name, body)
+            }
+            ("PATCH", [type_name, id]) => self.update(type_name, id, body),
+            ("DELETE", [type_name, id]) => self.destroy(type_name, id),
+            ("GET" | "POST", _) => error(404, "Not found.", None),
+            _ => error(405, &format!("Method \"{method}\" not allowed."), None),
+        }
+        .into()
+    }
+
+    /// Values all matching reports have in common, `null` where they differ
+    fn intersection(&self, params: &[(String, String)]) -> (u16, Option<Value>) {
+        let reports: Vec<&Value> = self.resources["reports"]
+            .values()
+            .filter(|report| self.matches(report, params))
+            .collect();
+
+        let common = |values: Vec<Value>| -> Value {
+            match values.split_first() {
+                Some((first, rest)) if rest.iter().all(|value| value == first) => first.clone(),
+                _ => Value::Null,
+            }
+        };
+
+        let mut attributes = Map::new();
+        for name in ["comment", "not-billable", "review", "verified", "rejected"] {
+            let values = reports
+                .iter()
+                .map(|report| report["attributes"][name].clone())
+                .collect();
+            attributes.insert(name.to_string(), common(values));
+        }
+
+        let mut relationships = Map::new();
+        for name in ["customer", "project", "task", "user"] {
+            let values = reports
+                .iter()
+                .map(|report| json!(self.related_id(report, name, 0)))
+                .collect();
+            let data = match common(values) {
+                Value::String(id) => to_one(&format!("{name}s"), &id),
+                _ => json!({ "data": null }),
+            };
+            relationships.insert(name.to_string(), data);
+        }
+
+        let query: Vec<String> = params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let data = json!({
+            "type": "report-intersections",
+            "id": query.join("&"),
+            "attributes": attributes,
+            "relationships": relationships,
+        });
+        let mut body = json!({ "data": data });
+        if let Some(include) = param(params, "include") {
+            body["included"] = Value::Array(self.included(&[&body["data"]], include));
+        }
+        (200, Some(body))
+    }
+
+    /// Apply attributes and a task to all matching reports
+    fn bulk_update(&mut self, params: &[(String, String)], body: &[u8]) -> (u16, Option<Value>) {
+        let data = match parse_data(body, "report-bulks") {
+            Ok(data) => data,
+            Err(response) => return response,
+        };
+
+        let ids: Vec<u64> = self.resources["reports"]
+            .iter()
+            .filter(|(_, report)| self.matches(report, params))
+            .map(|(id, _)| *id)
+            .collect();
+        let reports = self.resources.get_mut("reports").unwrap();
+        for id in ids {
+            let report = reports.get_mut(&id).unwrap();
+            if let Some(Value::Object(changes)) = data.get("attributes") {
+                for (key, value) in changes {
+                    report["attributes"][key] = value.clone();
+                }
+            }
+            if let Some(task) = data.get("relationships").and_then(|r| r.get("task")) {
+                report["relationships"]["task"] = task.clone();
+            }
+        }
+
+        (204, None)
+    }
+
+    /// Export the matching reports as a CSV file
+    ///
+    /// Spreadsheet formats are rendered by the real server only.
+    fn export(&self, params: &[(String, String)]) -> Reply {
+        match param(params, "file_type") {
+            Some("csv") => {}
+            Some("xlsx" | "ods") => {
+                return error(400, "The fake server only exports CSV.", None).into();
+            }
+            _ => return error(400, "Invalid file type.", None).into(),
+        }
+
+        let filters: Vec<(String, String)> = params
+            .iter()
+            .filter(|(key, _)| key != "file_type")
+            .cloned()
+            .collect();
+        let mut body = String::from("date,duration,customer,project,task,comment\n");
+        for report in self.resources["reports"]
+            .values()
+            .filter(|report| self.matches(report, &filters))
+        {
+            let attributes = &report["attributes"];
+            let name = |type_name: &str, relationship: &str| {
+                self.related_id(report, relationship, 0)
+                    .and_then(|id| self.get(type_name, &id))
+                    .and_then(|resource| resource["attributes"]["name"].as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let fields = [
+                attributes["date"].as_str().unwrap_or_default().to_string(),
+                attributes["duration"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                name("customers", "customer"),
+                name("projects", "project"),
+                name("tasks", "task"),
+                attributes["comment"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            ];
+            let line: Vec<String> = fields
+                .iter()
+                .map(|field| format!("\"{}\"", field.replace('"', "\"\"")))
+                .collect();
+            body.push_str(&line.join(","));
+            body.push('\n');
+        }
+
+        Reply {
+            status: 200,
+            content_type: "text/csv",
+            filename: Some("report.csv".to_string()),
+            body: body.into_bytes(),
+        }
+    }
+
+    fn list(&self, type_name: &str, params: &[(String, String)]) -> (u16, Option<Value>) {
+        let data: Vec<&Value> = self.resources[type_name]
+            .values()
+            .filter(|resource| self.matches(resource, params))
+            .collect();
+        self.page(type_name, data, params)
+    }
+
+    /// Order, paginate and include relationships for a list response
+    fn page(
+        &self,
+        type_name: &str,
+        mut data: Vec<&Value>,
+        params: &[(String, String)],
+    ) -> (u16, Option<Value>) {
+        if let Some(ordering) = param(params, "ordering") {
+            data.sort_by(|a, b| compare_by(a, b, ordering));
+        }
+
+        // Only paginate when asked to, like the real API
+        let count = data.len();
+        let size = param(params, PAGE_SIZE_PARAM)
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&size| size > 0)
+            .unwrap_or(count.max(1));
+        let number = param(params, PAGE_NUMBER_PARAM)
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1);
+        let pages = count.div_ceil(size).max(1);
+        let page: Vec<&Value> = data
+            .into_iter()
+            .skip((number - 1) * size)
+            .take(size)
+            .collect();
+
+        let next = (number < pages).then(|| {
+            format!(
+                "http://fake/{DEFAULT_API_NAMESPACE}/{type_name}?{PAGE_NUMBER_PARAM}={}&{PAGE_SIZE_PARAM}={size}",
+                number + 1
+            )
+        });
+
+        let mut body = json!({
+            "data": page,
+            "links": { "next": next },
+            "meta": { "pagination": { "page": number, "pages": pages, "count": count } },
+        });
+        if let Some(include) = param(params, "include") {
+            body["included"] = Value::Array(self.included(&page, include));
+        }
+        (200, Some(body))
+    }
+
+    /// Sum up the durations of the matching reports per group
+    fn statistics(&self, type_name: &str, params: &[(String, String)]) -> Vec<Value> {
+        let groups = statistic_groups(type_name).unwrap_or_default();
+        let year = param(params, "year");
+        let month = param(params, "month").and_then(|m| m.parse::<u32>().ok());
+        let by_month = type_name == "month-statistics";
+        let by_year = by_month || type_name == "year-statistics";
+
+        let mut totals: BTreeMap<Vec<String>, TimedDuration> = BTreeMap::new();
+        for report in self.resources["reports"].values() {
+            let date = report["attributes"]["date"].as_str().unwrap_or_default();
+            if year.is_some_and(|year| !date.starts_with(year))
+                || month.is_some_and(|month| date.get(5..7) != Some(&format!("{month:02}")))
+                || !self.matches(report, params)
+            {
+                continue;
+            }
+
+            let mut key: Vec<String> = groups
+                .iter()
+                .map(|name| self.related_id(report, name, 0).unwrap_or_default())
+                .collect();
+            if by_year {
+                key.push(date.get(..4).unwrap_or_default().to_string());
+            }
+            if by_month {
+                key.push(date.get(5..7).unwrap_or_default().to_string());
+            }
+
+            let duration = report["attributes"]["duration"]
+                .as_str()
+                .and_then(|d| d.parse::<TimedDuration>().ok())
+                .unwrap_or_default();
+            *totals.entry(key).or_default() += duration;
+        }
+
+        totals
+            .into_iter()
+            .map(|(key, duration)| {
+                let mut attributes = json!({ "duration": duration.to_timedelta_string() });
+                if by_year {
+                    attributes["year"] = json!(key[groups.len()].parse::<i32>().unwrap_or(0));
+                    attributes["total-attendance"] = json!("00:00:00");
+                }
+                if by_month {
+                    attributes["month"] = json!(key[groups.len() + 1].parse::<i32>().unwrap_or(0));
+                }
+                let relationships: Map<String, Value> = groups
+                    .iter()
+                    .zip(&key)
+                    .map(|(name, id)| (name.to_string(), to_one(&format!("{name}s"), id)))
+                    .collect();
+
+                json!({
+                    "type": type_name,
+                    "id": key.join("_"),
+                    "attributes": attributes,
+                    "relationships": relationships,
+                })
+            })
+            .collect()
+    }
+
+    fn show(&self, type_name: &str, id: &str, params: &[(String, String)]) -> (u16, Option<Value>) {
+        let Some(resource) = self.get(type_name, id) else {
+            return error(404, "Not found.", None);
+        };
+
+        let mut body = json!({ "data": resource });
+        if let Some(include) = param(params, "include") {
+            body["included"] = Value::Array(self.included(&[resource], include));
+        }
+        (200, Some(body))
+    }
+
+    fn create(&mut self, type_name: &str, body: &[u8]) -> (u16, Option<Value>) {
+        let data = match parse_data(body, type_name) {
+            Ok(data) => data,
+            Err(response) => return response,
+        };
+
+        let attributes = data.get("attributes").cloned().unwrap_or(json!({}));
+        let missing: Vec<Value> = REQUIRED_ATTRIBUTES
+            .iter()
+            .filter(|(required_type, _)| *required_type == type_name)
+            .flat_map(|(_, fields)| fields.iter())
+            .filter(|field| attributes.get(**field).is_none_or(Value::is_null))
+            .map(|field| {
+                error_object(
+                    400,
+                    "This field is required.",
+                    Some(&format!("/data/attributes/{field}")),
+                )
+            })
+            .collect();
+        if !missing.is_empty() {
+            return (400, Some(json!({ "errors": missing })));
+        }
+
+        let relationships = data.get("relationships").cloned().unwrap_or(json!({}));
+        // Related resources have to exist, like Django's primary key fields
+        if let Value::Object(related) = &relationships {
+            for (name, relationship) in related {
+                let target = &relationship["data"];
+                if let (Some(target_type), Some(target_id)) =
+                    (target["type"].as_str(), target["id"].as_str())
+                {
+                    if self.get(target_type, target_id).is_none() {
+                        return error(
+                            400,
+                            &format!("Invalid pk \"{target_id}\" - object does not exist."),
+                            Some(&format!("/data/relationships/{name}")),
+                        );
+                    }
+                }
+            }
+        }
+
+        let id = self.insert(type_name, attributes, relationships);
+        (201, Some(json!({ "data": self.get(type_name, &id) })))
+    }
+
+    fn update(&mut self, type_name: &str, id: &str, body: &[u8]) -> (u16, Option<Value>) {
+        let data = match parse_data(body, type_name) {
+            Ok(data) => data,
+            Err(response) => return response,
+        };
+        let Some(resource) = id
+            .parse::<u64>()
+            .ok()
+            .and_then(|key| self.resources.get_mut(type_name)?.get_mut(&key))
+        else {
+            return error(404, "Not found.", None);
+        };
+
+        for section in ["attributes", "relationships"] {
+            if let Some(Value::Object(changes)) = data.get(section) {
+                let target = &mut resource[section];
+                if !target.is_object() {
+                    *target = json!({});
+                }
+                for (key, value) in changes {
+                    target[key] = value.clone();
+                }
+            }
+        }
+
+        (200, Some(json!({ "data": resource })))
+    }
+
+    fn destroy(&mut self, type_name: &str, id: &str) -> (u16, Option<Value>) {
+        let removed = id
+            .parse::<u64>()
+            .ok()
+            .and_then(|key| self.resources.get_mut(type_name)?.remove(&key));
+        match removed {
+            Some(_) => (204, None),
+            None => error(404, "Not found.", None),
+        }
+    }
+
+    /// Check a resource against the filter parameters of a request
+    fn matches(&self, resource: &Value, params: &[(String, String)]) -> bool {
+        params
+            .iter()
+            .filter(|(key, _)| !RESERVED_PARAMS.contains(&key.as_str()))
+            .all(|(key, value)| self.matches_filter(resource, key, value))
+    }
+
+    fn matches_filter(&self, resource: &Value, key: &str, value: &str) -> bool {
+        let attributes = &resource["attributes"];
+        let date = attributes["date"].as_str();
+
+        match key {
+            "active" => attributes["to-time"].is_null() == is_truthy(value),
+            "from_date" => date.is_some_and(|date| date >= value),
+            "to_date" => date.is_some_and(|date| date <= value),
+            "day" => date == Some(value),
+            "comment" => attributes["comment"]
+                .as_str()
+                .is_some_and(|comment| comment.to_lowercase().contains(&value.to_lowercase())),
+            _ => {
+                if let Some(id) = self.related_id(resource, key, 0) {
+                    return id == value;
+                }
+                match attributes.get(key.replace('_', "-")) {
+                    Some(Value::Bool(flag)) => *flag == is_truthy(value),
+                    Some(Value::String(text)) => text == value,
+                    Some(Value::Null) => value.is_empty(),
+                    Some(other) => value.parse::<Value>().is_ok_and(|v| v == *other),
+                    // Unknown filters are ignored, like Django does
+                    None => true,
+                }
+            }
+        }
+    }
+
+    /// Follow task → project → customer to find a related resource's ID
+    fn related_id(&self, resource: &Value, name: &str, depth: usize) -> Option<String> {
+        let relationships = &resource["relationships"];
+        if let Some(id) = relationships[name]["data"]["id"].as_str() {
+            return Some(id.to_string());
+        }
+        if depth > 2 {
+            return None;
+        }
+
+        ["task", "project"].iter().find_map(|parent| {
+            let data = &relationships[*parent]["data"];
+            let parent = self.get(data["type"].as_str()?, data["id"].as_str()?)?;
+            self.related_id(parent, name, depth + 1)
+        })
+    }
+
+    /// Collect the resources named by an `include` parameter
+    fn included(&self, data: &[&Value], include: &str) -> Vec<Value> {
+        let mut seen = HashSet::new();
+        let mut included = Vec::new();
+
+        for path in include.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut current: Vec<&Value> = data.to_vec();
+            for relationship in path.split('.') {
+                current = current
+                    .iter()
+                    .filter_map(|resource| {
+                        let target = &resource["relationships"][relationship]["data"];
+                        self.get(target["type"].as_str()?, target["id"].as_str()?)
+                    })
+                    .collect();
+                for resource in &current {
+                    let key = (resource["type"].to_string(), resource["id"].to_string());
+                    if seen.insert(key) {
+                        included.push((*resource).clone());
+                    }
+                }
+            }
+        }
+
+        included
+    }
+}
+
+/// Accept connections until the server is dropped
+async fn serve(listener: TcpListener, store: Arc<Mutex<Store>>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let store = store.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, store).await {
+                debug!("Fake Timed connection failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Read a single HTTP/1.1 request and write the response
+async fn handle_connection(stream: TcpStream, store: Arc<Mutex<Store>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut content_length = 0;
+    let mut authorized = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "authorization" => authorized = value.trim().starts_with("Bearer "),
+                _ => {}
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let reply = if authorized {
+        store.lock().unwrap().handle(&method, &target, &body)
+    } else {
+        error(401, "Authentication credentials were not provided.", None).into()
+    };
+
+    let disposition = reply
+        .filename
+        .map(|name| format!("Content-Disposition: attachment; filename=\"{name}\"\r\n"))
+        .unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\n{disposition}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.status,
+        reason(reply.status),
+        reply.content_type,
+        reply.body.len()
+    );
+
+    let mut stream = reader.into_inner();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&reply.body).await?;
+    stream.shutdown().await
+}
+
+/// A response of the fake server
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    /// Name of an attached file, sent as `Content-Disposition`
+    filename: Option<String>,
+    body: Vec<u8>,
+}
+
+impl From<(u16, Option<Value>)> for Reply {
+    fn from((status, document): (u16, Option<Value>)) -> Self {
+        Self {
+            status,
+            content_type: "application/vnd.api+json",
+            filename: None,
+            body: document
+                .map(|value| value.to_string().into_bytes())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Extract the `data` member of a request body
+fn parse_data(body: &[u8], type_name: &str) -> Result<Value, (u16, Option<Value>)> {
+    let Ok(document) = serde_json::from_slice::<Value>(body) else {
+        return Err(error(400, "Malformed JSON.", None));
+    };
+    let data = document["data"].clone();
+    if data["type"] != type_name {
+        return Err(error(
+            409,
+            &format!("The resource object's type is not the type that constitute the collection represented by the endpoint ({type_name})."),
+            Some("/data"),
+        ));
+    }
+    Ok(data)
+}
+
+fn param<'a>(params: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Relationships a statistic groups by, if the type is a statistic
+fn statistic_groups(type_name: &str) -> Option<&'static [&'static str]> {
+    STATISTICS
+        .iter()
+        .find(|(name, _)| *name == type_name)
+        .map(|(_, groups)| *groups)
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value, "1" | "true" | "True")
+}
+
+/// Compare two resources by a comma-separated `ordering` parameter
+fn compare_by(a: &Value, b: &Value, ordering: &str) -> Ordering {
+    ordering
+        .split(',')
+        .map(|field| match field.strip_prefix('-') {
+            Some(field) => compare_values(&b["attributes"][field], &a["attributes"][field]),
+            None => compare_values(&a["attributes"][field], &b["attributes"][field]),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+fn error_object(status: u16, detail: &str, pointer: Option<&str>) -> Value {
+    let mut object = json!({ "status": status.to_string(), "detail": detail });
+    if let Some(pointer) = pointer {
+        object["source"] = json!({ "pointer": pointer });
+    }
+    object
+}
+
+fn error(status: u16, detail: &str, pointer: Option<&str>) -> (u16, Option<Value>) {
+    (
+        status,
+        Some(json!({ "errors": [error_object(status, detail, pointer)] })),
+    )
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
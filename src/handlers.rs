@@ -1,64 +1,176 @@
+use std::cmp::Ordering;
+
 use anyhow::Result;
-use chrono::{Local, NaiveDate};
+use chrono::{Duration, Local, NaiveDate, NaiveTime};
+use clap::ValueEnum;
+use serde::Serialize;
 
 use libtimed::{
-    models::{FilterParams, User},
+    models::{FilterParams, ResourcesResponse, User, WorktimeBalance},
+    transforms::TimedDuration,
     TimedClient,
 };
 
+use crate::output::{print_item, OutputFormat};
+
 pub mod absence;
 pub mod activity;
 pub mod attendance;
+pub mod cache;
 pub mod config;
 pub mod data;
+pub mod dates;
+pub mod durations;
+pub mod lookup;
 pub mod report;
 pub mod statistics;
 
 /// Parse a date string or return today's date
+///
+/// Relative dates like `yesterday`, `-3d` or `last friday` are accepted too,
+/// see [`dates::parse_relative_date`].
 pub fn parse_date(date_str: Option<&str>) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
     match date_str {
-        Some(s) => {
-            let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
-                .or_else(|_| NaiveDate::parse_from_str(s, "%Y/%m/%d"))
-                .or_else(|_| NaiveDate::parse_from_str(s, "%d.%m.%Y"))
-                .or_else(|_| NaiveDate::parse_from_str(s, "%d-%m-%Y"))?;
-            Ok(date)
-        }
-        None => Ok(Local::now().date_naive()),
+        Some(s) => dates::parse_relative_date(s, today),
+        None => Ok(today),
     }
 }
 
-/// Format a duration in hours and minutes
-#[allow(dead_code)]
-pub fn format_duration(duration_str: &str) -> Result<String> {
-    // Check if it's already in HH:MM format
-    if duration_str.contains(':') {
-        return Ok(duration_str.to_string());
+/// Parse a date string into the `YYYY-MM-DD` form the API expects
+pub fn date_param(date_str: &str) -> Result<String> {
+    Ok(parse_date(Some(date_str))?.format("%Y-%m-%d").to_string())
+}
+
+/// Parse a time of day like 08:30, 08:30:00, 8:30 or just 8
+pub fn parse_time(time_str: &str) -> Result<NaiveTime> {
+    let time_str = time_str.trim();
+    let hour = || {
+        time_str
+            .parse::<u32>()
+            .ok()
+            .and_then(|hour| NaiveTime::from_hms_opt(hour, 0, 0))
+    };
+    NaiveTime::parse_from_str(time_str, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time_str, "%H:%M"))
+        .ok()
+        .or_else(hour)
+        .ok_or_else(|| anyhow::anyhow!("Invalid time: {} (use HH:MM)", time_str))
+}
+
+/// Format a duration in hours and minutes, like `8:30` or `-1:30`
+pub fn format_duration(duration: Duration) -> String {
+    TimedDuration::from(duration).format_clock()
+}
+
+/// Uppercase the first letter of a text
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
+}
 
-    // Parse as decimal hours
-    let hours: f64 = duration_str.parse()?;
-    let total_minutes = (hours * 60.0).round() as i64;
-    let hours = total_minutes / 60;
-    let minutes = total_minutes % 60;
+/// Round a duration to decimal hours with two digits, for structured output
+pub fn hours(duration: TimedDuration) -> f64 {
+    (duration.as_hours() * 100.0).round() / 100.0
+}
+
+/// Order of report and activity listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ListSort {
+    /// Oldest first
+    #[default]
+    Date,
+    /// Longest duration first
+    Duration,
+    /// Alphabetically by customer, project and task
+    Task,
+}
 
-    Ok(format!("{hours:02}:{minutes:02}:00"))
+/// A listed entry that can be ordered with [`ListSort`]
+pub trait Sortable {
+    /// Date and, if known, start time
+    fn when(&self) -> (NaiveDate, Option<NaiveTime>);
+    /// Duration in decimal hours
+    fn hours(&self) -> f64;
+    /// Names of the customer, project and task
+    fn task_path(&self) -> [&str; 3];
+}
+
+impl ListSort {
+    /// Compare two entries, falling back to their dates
+    pub fn compare<T: Sortable>(self, a: &T, b: &T) -> Ordering {
+        let by_date = a.when().cmp(&b.when());
+        match self {
+            ListSort::Date => by_date,
+            ListSort::Duration => b.hours().total_cmp(&a.hours()).then(by_date),
+            ListSort::Task => a.task_path().cmp(&b.task_path()).then(by_date),
+        }
+    }
 }
 
 /// Get the current user
-#[allow(dead_code)]
 pub async fn get_current_user(client: &TimedClient) -> Result<User> {
-    let filter = FilterParams::default();
-    let response = client
-        .get::<serde_json::Value>("users/me", Some(&filter))
-        .await?;
+    let response = client.fetch::<User>("me", None).await?;
+    Ok(response.data)
+}
+
+/// Get the ID of the current user
+pub async fn get_current_user_id(client: &TimedClient) -> Result<String> {
+    get_current_user(client)
+        .await?
+        .id
+        .ok_or_else(|| anyhow::anyhow!("Failed to get user ID"))
+}
 
-    let user = serde_json::from_value::<User>(response["data"].clone())?;
-    Ok(user)
+/// Names of a task and the project and customer it belongs to
+#[derive(Debug, Clone)]
+pub struct TaskPath {
+    pub customer: String,
+    pub project: String,
+    pub task: String,
+}
+
+impl TaskPath {
+    /// Resolve the names of a task from the included resources of a response
+    pub fn resolve<T>(response: &ResourcesResponse<T>, task_id: &str) -> Self {
+        let task = response.find_included("tasks", task_id);
+        let project = task
+            .and_then(|t| t.relationship_id("project"))
+            .and_then(|id| response.find_included("projects", id));
+        let customer = project
+            .and_then(|p| p.relationship_id("customer"))
+            .and_then(|id| response.find_included("customers", id));
+
+        Self {
+            customer: customer
+                .and_then(|c| c.attribute_str("name"))
+                .unwrap_or("Unknown Customer")
+                .to_string(),
+            project: project
+                .and_then(|p| p.attribute_str("name"))
+                .unwrap_or("Unknown Project")
+                .to_string(),
+            task: task
+                .and_then(|t| t.attribute_str("name"))
+                .unwrap_or("Unknown Task")
+                .to_string(),
+        }
+    }
+}
+
+/// Resolve a username from the included resources of a response
+pub fn resolve_username<T>(response: &ResourcesResponse<T>, user_id: &str) -> Option<String> {
+    response
+        .find_included("users", user_id)
+        .and_then(|u| u.attribute_str("username"))
+        .map(String::from)
 }
 
 /// Get overtime for a specific date
-pub async fn get_overtime(client: &TimedClient, date_str: Option<&str>) -> Result<String> {
+pub async fn get_overtime(client: &TimedClient, date_str: Option<&str>) -> Result<TimedDuration> {
     let date = parse_date(date_str)?;
 
     let filter = FilterParams {
@@ -66,21 +178,41 @@ pub async fn get_overtime(client: &TimedClient, date_str: Option<&str>) -> Resul
         ..Default::default()
     };
 
-    let response = client
-        .get::<serde_json::Value>("worktime-balances", Some(&filter))
-        .await?;
+    let response = client.list::<WorktimeBalance>(Some(&filter)).await?;
 
-    if let Some(data) = response["data"].as_array() {
-        if let Some(balance) = data.first() {
-            let balance_str = balance["attributes"]["balance"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Invalid balance format"))?;
+    response
+        .data
+        .first()
+        .map(|balance| TimedDuration::from(balance.attributes.balance))
+        .ok_or_else(|| anyhow::anyhow!("No overtime data found for the specified date"))
+}
 
-            return Ok(balance_str.to_string());
-        }
-    }
+/// Overtime balance as printed by `get overtime`
+#[derive(Debug, Serialize)]
+struct OvertimeRecord {
+    date: NaiveDate,
+    balance: String,
+    hours: f64,
+}
+
+/// Show the overtime balance for a specific date
+pub async fn show_overtime(
+    client: &TimedClient,
+    date_str: Option<&str>,
+    output: OutputFormat,
+) -> Result<()> {
+    let date = parse_date(date_str)?;
+    let balance = get_overtime(client, Some(&date.format("%Y-%m-%d").to_string())).await?;
 
-    Err(anyhow::anyhow!(
-        "No overtime data found for the specified date"
-    ))
+    let record = OvertimeRecord {
+        date,
+        balance: balance.format_signed(),
+        hours: hours(balance),
+    };
+    let text = format!(
+        "Overtime: {} ({} hours)",
+        balance.format_signed(),
+        balance.format_decimal()
+    );
+    print_item(output, &record, &text)
 }
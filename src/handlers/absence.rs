@@ -1,24 +1,48 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect};
+use serde::Serialize;
 use tracing::{debug, info};
 
 use libtimed::{
     models::{
         Absence, AbsenceAttributes, AbsenceRelationships, AbsenceType, FilterParams,
-        RelationshipData, RelationshipResource, ResourceResponse, ResourcesResponse,
+        RelationshipData, RelationshipResource,
     },
     TimedClient,
 };
 
+use super::{date_param, get_current_user_id, lookup::find_by_name, parse_date, resolve_username};
+use crate::output::{print_list, OutputFormat, Table};
+
+/// Options for adding absences
+#[derive(Debug, Clone)]
+pub struct AddAbsenceOptions {
+    pub date: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub absence_type: Option<String>,
+    pub comment: Option<String>,
+    /// Book several days without asking
+    pub yes: bool,
+    pub interactive: bool,
+}
+
 /// List absences for the current user or all users
-#[allow(dead_code)]
 pub async fn list_absences(
     client: &TimedClient,
     date_str: Option<&str>,
     from_str: Option<&str>,
     to_str: Option<&str>,
     all_users: bool,
+    output: OutputFormat,
 ) -> Result<()> {
+    // Relative dates like yesterday are turned into YYYY-MM-DD
+    let date = date_str.map(date_param).transpose()?;
+    let from = from_str.map(date_param).transpose()?;
+    let to = to_str.map(date_param).transpose()?;
+    let (date_str, from_str, to_str) = (date.as_deref(), from.as_deref(), to.as_deref());
+
     // Create filter for absences
     let mut filter = FilterParams::default();
 
@@ -46,127 +70,97 @@ pub async fn list_absences(
 
     // Add user filter unless all_users flag is set
     if !all_users {
-        // Get current user ID from /users/me endpoint
-        let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-
-        if let Some(user_id) = current_user_response["data"]["id"].as_str() {
-            debug!("Filtering for current user: {}", user_id);
-            filter.user = Some(user_id.to_string());
-        }
+        let user_id = get_current_user_id(client).await?;
+        debug!("Filtering for current user: {}", user_id);
+        filter.user = Some(user_id);
     }
 
     // Include related entities for better display
     filter.include = Some("absence-type,user".to_string());
 
-    let response = client
-        .get::<ResourcesResponse<Absence>>("absences", Some(&filter))
-        .await?;
-
-    if response.data.is_empty() {
-        if date_str.is_some() {
-            println!("No absences found for date: {}", date_str.unwrap());
-        } else if from_str.is_some() || to_str.is_some() {
-            let from_msg = from_str.map_or("today", |d| d);
-            let to_msg = to_str.map_or("today", |d| d);
-            println!("No absences found from {from_msg} to {to_msg}");
-        } else {
-            println!("No absences found for today");
-        }
-        return Ok(());
-    }
+    let response = client.list_all::<Absence>(Some(&filter)).await?;
 
-    // Display date range in the header
-    if date_str.is_some() {
-        println!("Absences for {}", date_str.unwrap());
+    // Describe the selected dates for the title
+    let period = if let Some(date) = date_str {
+        format!("for {date}")
     } else if from_str.is_some() || to_str.is_some() {
         let from_msg = from_str.map_or("today", |d| d);
         let to_msg = to_str.map_or("today", |d| d);
-        println!("Absences from {from_msg} to {to_msg}");
+        format!("from {from_msg} to {to_msg}")
     } else {
-        let today = Local::now().date_naive().format("%Y-%m-%d");
-        println!("Absences for {today}");
-    }
-
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each absence
-    for absence in response.data {
-        let date = absence.attributes.date;
-        let comment = absence
-            .attributes
-            .comment
-            .unwrap_or_else(|| "-".to_string());
-
-        // Get absence type info
-        let mut absence_type_name = "Unknown";
-        if let Some(absence_type_rel) = &absence.relationships.absence_type {
-            if let Some(absence_type_res) = &absence_type_rel.data {
-                let absence_type_id = &absence_type_res.id;
-
-                // Find absence type in included data
-                if let Some(absence_type) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "absence-types" && inc.id == *absence_type_id)
-                {
-                    if let Some(name) = absence_type.attributes.get("name").and_then(|n| n.as_str())
-                    {
-                        absence_type_name = name;
-                    }
-                }
-            }
-        }
+        format!("for {}", Local::now().date_naive().format("%Y-%m-%d"))
+    };
 
-        // Get user info if we're showing all users
-        let mut user_prefix = "".to_string();
+    let records: Vec<AbsenceRecord> = response
+        .data
+        .iter()
+        .map(|absence| AbsenceRecord {
+            id: absence.id.clone(),
+            date: absence.attributes.date,
+            user: absence
+                .relationships
+                .user
+                .as_ref()
+                .and_then(|u| u.id())
+                .and_then(|id| resolve_username(&response, id)),
+            absence_type: absence
+                .relationships
+                .absence_type
+                .as_ref()
+                .and_then(|t| t.id())
+                .and_then(|id| response.find_included("absence-types", id))
+                .and_then(|t| t.attribute_str("name"))
+                .unwrap_or("Unknown")
+                .to_string(),
+            comment: absence.attributes.comment.clone(),
+        })
+        .collect();
+
+    let mut header = vec!["ID", "Date", "Type", "Comment"];
+    if all_users {
+        header.insert(0, "User");
+    }
+    let mut table = Table::new(header)
+        .title(format!("Absences {period}"))
+        .empty(format!("No absences found {period}"));
+    for record in &records {
+        let mut cells = vec![
+            record.id.clone().unwrap_or_default(),
+            record.date.to_string(),
+            record.absence_type.clone(),
+            record.comment.clone().unwrap_or_else(|| "-".to_string()),
+        ];
         if all_users {
-            if let Some(user_rel) = &absence.relationships.user {
-                if let Some(user_res) = &user_rel.data {
-                    let user_id = &user_res.id;
-
-                    // Find user in included data
-                    if let Some(user) = included
-                        .iter()
-                        .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                    {
-                        if let Some(username) =
-                            user.attributes.get("username").and_then(|n| n.as_str())
-                        {
-                            user_prefix = format!("[{username}] ");
-                        }
-                    }
-                }
-            }
+            cells.insert(0, record.user.clone().unwrap_or_default());
         }
-
-        println!("{user_prefix}Date: {date} | Type: {absence_type_name} | Comment: {comment}");
+        table.row(cells);
     }
 
-    Ok(())
+    print_list(output, &records, &table)
 }
 
-/// Create a new absence for the current user
-#[allow(dead_code)]
-pub async fn create_absence(
-    client: &TimedClient,
-    date_str: &str,
+/// An absence as shown by `absence list` in structured output formats
+#[derive(Debug, Clone, Serialize)]
+pub struct AbsenceRecord {
+    pub id: Option<String>,
+    pub date: NaiveDate,
+    pub user: Option<String>,
+    pub absence_type: String,
+    pub comment: Option<String>,
+}
+
+/// Build an absence of the given type for a user
+fn new_absence(
+    user_id: &str,
+    date: NaiveDate,
     absence_type_id: &str,
     comment: Option<&str>,
-) -> Result<()> {
-    // Get current user info
-    let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-    let user_id = current_user_response["data"]["id"]
-        .as_str()
-        .ok_or_else(|| anyhow::anyhow!("Failed to get user ID"))?;
-
-    // Create absence
-    let absence = Absence {
+) -> Absence {
+    Absence {
         id: None,
         type_name: "absences".to_string(),
         attributes: AbsenceAttributes {
-            date: date_str.to_string(),
+            date,
             comment: comment.map(|s| s.to_string()),
         },
         relationships: AbsenceRelationships {
@@ -183,31 +177,160 @@ pub async fn create_absence(
                 }),
             }),
         },
+    }
+}
+
+/// Add absences for a single date or for every working day of a range
+///
+/// Days that already have an absence are skipped. Booking several days is
+/// confirmed first, unless `yes` is set.
+pub async fn add_absences(client: &TimedClient, options: AddAbsenceOptions) -> Result<()> {
+    // Step 1: Work out the days to book
+    let days = match (&options.from_date, &options.to_date) {
+        (Some(from), Some(to)) => {
+            let from = parse_date(Some(from))?;
+            let to = parse_date(Some(to))?;
+            if from > to {
+                return Err(anyhow::anyhow!("--from must not be after --to"));
+            }
+            working_days(from, to)
+        }
+        (None, None) => vec![parse_date(options.date.as_deref())?],
+        _ => return Err(anyhow::anyhow!("--from and --to must be used together")),
+    };
+    let (Some(first), Some(last)) = (days.first().copied(), days.last().copied()) else {
+        return Err(anyhow::anyhow!("No working days in the given range"));
     };
 
-    // Post to API
-    let request_body = serde_json::json!({
-        "data": absence
-    });
+    // Step 2: Pick the absence type
+    let types = client.list::<AbsenceType>(None).await?.data;
+    let absence_type = match &options.absence_type {
+        Some(query) => match_absence_type(&types, query)?,
+        None if options.interactive => select_absence_type(&types)?,
+        None => {
+            return Err(anyhow::anyhow!(
+                "Absence type required. Provide --type or remove --non-interactive flag"
+            ))
+        }
+    };
+    let type_id = absence_type
+        .id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Invalid absence type ID"))?;
+
+    // Step 3: Skip days that are already booked
+    let user_id = get_current_user_id(client).await?;
+    let filter = FilterParams {
+        from_date: Some(first.format("%Y-%m-%d").to_string()),
+        to_date: Some(last.format("%Y-%m-%d").to_string()),
+        user: Some(user_id.clone()),
+        ..Default::default()
+    };
+    let existing: Vec<NaiveDate> = client
+        .list_all::<Absence>(Some(&filter))
+        .await?
+        .data
+        .iter()
+        .map(|absence| absence.attributes.date)
+        .collect();
+
+    let (skipped, days): (Vec<_>, Vec<_>) =
+        days.into_iter().partition(|day| existing.contains(day));
+    for day in skipped {
+        println!("Skipping {day}, an absence already exists");
+    }
 
-    let response = client
-        .post::<_, ResourceResponse<Absence>>("absences", &request_body)
-        .await?;
+    // Step 4: Confirm booking several days, a typo in --to could book years
+    if days.len() > 1 && !options.yes {
+        if !options.interactive {
+            return Err(anyhow::anyhow!(
+                "Booking {} days needs confirmation. Pass --yes or remove --non-interactive flag",
+                days.len()
+            ));
+        }
+        let confirm = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Book {} {} absences from {} to {}?",
+                days.len(),
+                absence_type.attributes.name,
+                days[0],
+                days[days.len() - 1]
+            ))
+            .default(false)
+            .interact()?;
+        if !confirm {
+            println!("No absences added");
+            return Ok(());
+        }
+    }
 
-    info!(
-        "Created absence for {} with ID: {:?}",
-        date_str, response.data.id
-    );
-    println!("Created absence for {date_str}");
+    // Step 5: Create one absence per day
+    let total = days.len();
+    let mut created = 0;
+    for day in days {
+        let absence = new_absence(&user_id, day, type_id, options.comment.as_deref());
+        let response = client.create(&absence).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to add the absence for {} after adding {} of {}: {}",
+                day,
+                created,
+                total,
+                e
+            )
+        })?;
+        info!(
+            "Created absence for {} with ID: {:?}",
+            day, response.data.id
+        );
+        println!("Created {} absence for {day}", absence_type.attributes.name);
+        created += 1;
+    }
+
+    if created > 1 {
+        println!("Created {created} absences");
+    }
 
     Ok(())
 }
 
+/// Get the working days (Monday to Friday) between two dates, inclusive
+fn working_days(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+        .collect()
+}
+
+/// Find an absence type by ID, by name or by a unique part of its name
+fn match_absence_type<'a>(types: &'a [AbsenceType], query: &str) -> Result<&'a AbsenceType> {
+    find_by_name(
+        types,
+        query,
+        "Absence type",
+        |t| t.id.as_deref(),
+        |t| &t.attributes.name,
+    )
+}
+
+/// Let the user pick an absence type with fuzzy search
+fn select_absence_type(types: &[AbsenceType]) -> Result<&AbsenceType> {
+    if types.is_empty() {
+        return Err(anyhow::anyhow!("No absence types found"));
+    }
+
+    let names: Vec<&str> = types.iter().map(|t| t.attributes.name.as_str()).collect();
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Absence type")
+        .default(0)
+        .items(&names)
+        .interact()?;
+
+    Ok(&types[selection])
+}
+
 /// Delete an absence by ID
-#[allow(dead_code)]
 pub async fn delete_absence(client: &TimedClient, absence_id: &str) -> Result<()> {
-    let endpoint = format!("absences/{absence_id}");
-    client.delete(&endpoint).await?;
+    client.destroy::<Absence>(absence_id).await?;
 
     info!("Deleted absence with ID: {}", absence_id);
     println!("Deleted absence");
@@ -215,33 +338,212 @@ pub async fn delete_absence(client: &TimedClient, absence_id: &str) -> Result<()
     Ok(())
 }
 
+/// Delete an absence by ID, or pick one of the current user's absences on a date
+pub async fn delete_absences(
+    client: &TimedClient,
+    absence_id: Option<&str>,
+    date_str: Option<&str>,
+    interactive: bool,
+) -> Result<()> {
+    if let Some(id) = absence_id {
+        return delete_absence(client, id).await;
+    }
+
+    let date = parse_date(date_str)?;
+    let filter = FilterParams {
+        date: Some(date.format("%Y-%m-%d").to_string()),
+        user: Some(get_current_user_id(client).await?),
+        include: Some("absence-type".to_string()),
+        ..Default::default()
+    };
+    let response = client.list_all::<Absence>(Some(&filter)).await?;
+
+    let absence = match response.data.as_slice() {
+        [] => {
+            println!("No absences found for {date}");
+            return Ok(());
+        }
+        [absence] => absence,
+        absences if interactive => {
+            let items: Vec<String> = absences
+                .iter()
+                .map(|absence| {
+                    let type_name = absence
+                        .relationships
+                        .absence_type
+                        .as_ref()
+                        .and_then(|t| t.id())
+                        .and_then(|id| response.find_included("absence-types", id))
+                        .and_then(|t| t.attribute_str("name"))
+                        .unwrap_or("Unknown");
+                    let comment = absence.attributes.comment.as_deref().unwrap_or("-");
+                    format!("{type_name} - {comment}")
+                })
+                .collect();
+
+            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                .with_prompt("Select absence to delete")
+                .default(0)
+                .items(&items)
+                .interact()?;
+            &absences[selection]
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+            "Multiple absences found for {}. Pass an absence ID or remove --non-interactive flag",
+            date
+        ))
+        }
+    };
+
+    let id = absence
+        .id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Invalid absence ID"))?;
+    delete_absence(client, id).await
+}
+
 /// List all available absence types
-#[allow(dead_code)]
-pub async fn list_absence_types(client: &TimedClient) -> Result<()> {
-    let response = client
-        .get::<ResourcesResponse<AbsenceType>>("absence-types", None)
-        .await?;
-
-    if response.data.is_empty() {
-        println!("No absence types found");
-        return Ok(());
-    }
-
-    println!("Available absence types:");
-    println!("----------------------------------------");
-
-    for absence_type in response.data {
-        println!(
-            "ID: {} | Name: {} | Fill worktime: {}",
-            absence_type.id.unwrap_or_else(|| "N/A".to_string()),
-            absence_type.attributes.name,
-            if absence_type.attributes.fill_worktime {
-                "Yes"
-            } else {
-                "No"
-            }
+pub async fn list_absence_types(client: &TimedClient, output: OutputFormat) -> Result<()> {
+    let response = client.list::<AbsenceType>(None).await?;
+
+    #[derive(Serialize)]
+    struct AbsenceTypeRecord {
+        id: Option<String>,
+        name: String,
+        fill_worktime: bool,
+    }
+
+    let records: Vec<AbsenceTypeRecord> = response
+        .data
+        .into_iter()
+        .map(|absence_type| AbsenceTypeRecord {
+            id: absence_type.id,
+            name: absence_type.attributes.name,
+            fill_worktime: absence_type.attributes.fill_worktime,
+        })
+        .collect();
+
+    let mut table = Table::new(["ID", "Name", "Fill worktime"])
+        .title("Available absence types")
+        .empty("No absence types found");
+    for record in &records {
+        table.row([
+            record.id.clone().unwrap_or_else(|| "N/A".to_string()),
+            record.name.clone(),
+            if record.fill_worktime { "Yes" } else { "No" }.to_string(),
+        ]);
+    }
+
+    print_list(output, &records, &table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtimed::{fake::FakeTimed, models::AbsenceTypeAttributes};
+    use serde_json::json;
+
+    fn absence_type(id: &str, name: &str) -> AbsenceType {
+        AbsenceType {
+            id: Some(id.to_string()),
+            type_name: "absence-types".to_string(),
+            attributes: AbsenceTypeAttributes {
+                name: name.to_string(),
+                fill_worktime: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_working_days_skip_weekends() {
+        let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let days: Vec<String> = working_days(from, to)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(days, vec!["2024-03-01", "2024-03-04", "2024-03-05"]);
+    }
+
+    #[test]
+    fn test_match_absence_type() {
+        let types = vec![
+            absence_type("1", "Vacation"),
+            absence_type("2", "Sick"),
+            absence_type("3", "Sick (child)"),
+        ];
+
+        assert_eq!(
+            match_absence_type(&types, "vacation").unwrap().id,
+            types[0].id
         );
+        assert_eq!(match_absence_type(&types, "vac").unwrap().id, types[0].id);
+        assert_eq!(match_absence_type(&types, "3").unwrap().id, types[2].id);
+        // An exact name wins over partial matches
+        assert_eq!(match_absence_type(&types, "sick").unwrap().id, types[1].id);
+        assert!(match_absence_type(&types, "i")
+            .unwrap_err()
+            .to_string()
+            .contains("ambiguous"));
+        assert!(match_absence_type(&types, "military").is_err());
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn test_add_absences_for_range() {
+        let server = FakeTimed::start().await.unwrap();
+        let vacation = server.insert(
+            "absence-types",
+            json!({ "name": "Vacation", "fill-worktime": false }),
+            json!({}),
+        );
+        server.insert(
+            "absences",
+            json!({ "date": "2024-03-04", "comment": null }),
+            json!({ "absence-type": libtimed::fake::to_one("absence-types", &vacation) }),
+        );
+
+        let options = AddAbsenceOptions {
+            date: None,
+            from_date: Some("2024-03-01".to_string()),
+            to_date: Some("2024-03-05".to_string()),
+            absence_type: Some("vac".to_string()),
+            comment: Some("Skiing".to_string()),
+            yes: false,
+            interactive: false,
+        };
+        // Several days are only booked when confirmed
+        let error = add_absences(&server.client(), options.clone())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Booking 2 days"));
+        assert_eq!(server.all("absences").len(), 1);
+
+        let options = AddAbsenceOptions {
+            yes: true,
+            ..options
+        };
+        add_absences(&server.client(), options.clone())
+            .await
+            .unwrap();
+
+        let dates: Vec<String> = server
+            .all("absences")
+            .iter()
+            .map(|a| a["attributes"]["date"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(dates, vec!["2024-03-04", "2024-03-01", "2024-03-05"]);
+
+        // A failure tells how far the booking got
+        server.fail("POST", "absences");
+        let options = AddAbsenceOptions {
+            from_date: Some("2024-03-11".to_string()),
+            to_date: Some("2024-03-12".to_string()),
+            ..options
+        };
+        let error = add_absences(&server.client(), options).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("for 2024-03-11 after adding 0 of 2"));
+    }
 }
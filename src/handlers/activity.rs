@@ -1,10 +1,32 @@
 use anyhow::Result;
-use chrono::{Local, NaiveTime, Timelike};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::Serialize;
 use tracing::{debug, info};
 
-use libtimed::{models::FilterParams, TimedClient};
-
-use super::parse_date;
+use libtimed::{
+    models::{Activity, FilterParams, ResourcesResponse},
+    transforms::TimedDuration,
+    TimedClient,
+};
+
+use super::{
+    date_param, format_duration, get_current_user_id, hours, lookup::resolve_task_path, parse_date,
+    parse_time, resolve_username, ListSort, Sortable, TaskPath,
+};
+use crate::config::RoundingConfig;
+use crate::output::{print_item, print_view, ListView, OutputFormat, Table};
+
+/// Options for listing activities
+#[derive(Debug, Clone)]
+pub struct GetActivitiesOptions {
+    pub date: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub all_users: bool,
+    pub output: OutputFormat,
+    pub sort_by: ListSort,
+    pub view: ListView,
+}
 
 /// Start a new activity
 #[allow(clippy::too_many_arguments)]
@@ -59,16 +81,7 @@ pub async fn start_activity(
 
     // Parse start time if provided, or ask for it interactively
     let start = if let Some(time_str) = start_time {
-        // Try various time formats
-        if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-            time
-        } else if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M:%S") {
-            time
-        } else {
-            return Err(anyhow::anyhow!(
-                "Invalid time format. Use HH:MM or HH:MM:SS"
-            ));
-        }
+        parse_time(time_str)?
     } else if interactive {
         // Default to current time
         let now = Local::now().time();
@@ -88,44 +101,24 @@ pub async fn start_activity(
             let time_input = Input::with_theme(&ColorfulTheme::default())
                 .with_prompt("Enter start time (HH:MM)")
                 .default(format!("{:02}:{:02}", now.hour(), now.minute()))
-                .validate_with(|input: &String| -> Result<(), &str> {
-                    if NaiveTime::parse_from_str(input, "%H:%M").is_ok() {
-                        Ok(())
-                    } else {
-                        Err("Please use HH:MM format")
-                    }
+                .validate_with(|input: &String| -> Result<(), String> {
+                    parse_time(input).map(|_| ()).map_err(|e| e.to_string())
                 })
                 .interact_text()?;
 
-            NaiveTime::parse_from_str(&time_input, "%H:%M")?
+            parse_time(&time_input)?
         }
     } else {
         // In non-interactive mode, default to current time
         Local::now().time()
     };
 
-    // Create activity
-    let activity = serde_json::json!({
-        "data": {
-            "type": "activities",
-            "attributes": {
-                "comment": activity_comment,
-                "date": Local::now().date_naive().format("%Y-%m-%d").to_string(),
-                "from-time": format!("{:02}:{:02}:00", start.hour(), start.minute()),
-                "to-time": null,
-                "review": false,
-                "not-billable": false
-            },
-            "relationships": {
-                "task": {
-                    "data": {
-                        "type": "tasks",
-                        "id": task_id
-                    }
-                }
-            }
-        }
-    });
+    let activity = activity_document(
+        &activity_comment,
+        Local::now().date_naive(),
+        start,
+        &task_id,
+    );
 
     client
         .post::<_, serde_json::Value>("activities", &activity)
@@ -160,21 +153,7 @@ pub async fn stop_activity(client: &TimedClient) -> Result<()> {
                 .as_str()
                 .ok_or_else(|| anyhow::anyhow!("Invalid activity ID"))?;
 
-            // Stop the activity by setting to-time
-            let now = Local::now().time();
-            let update = serde_json::json!({
-                "data": {
-                    "type": "activities",
-                    "id": id,
-                    "attributes": {
-                        "to-time": format!("{:02}:{:02}:00", now.hour(), now.minute())
-                    }
-                }
-            });
-
-            client
-                .patch::<_, serde_json::Value>(&format!("activities/{id}"), &update)
-                .await?;
+            set_activity_end(client, id, Some(Local::now().time())).await?;
             info!("Activity stopped");
             return Ok(());
         }
@@ -184,15 +163,179 @@ pub async fn stop_activity(client: &TimedClient) -> Result<()> {
     Ok(())
 }
 
-/// Show information about activities within a date range
-pub async fn show_activity(
+/// Set or clear the end time of an activity, to the minute
+async fn set_activity_end(client: &TimedClient, id: &str, end: Option<NaiveTime>) -> Result<()> {
+    let update = serde_json::json!({
+        "data": {
+            "type": "activities",
+            "id": id,
+            "attributes": {
+                "to-time": end.map(|t| format!("{:02}:{:02}:00", t.hour(), t.minute()))
+            }
+        }
+    });
+
+    client
+        .patch::<_, serde_json::Value>(&format!("activities/{id}"), &update)
+        .await?;
+    Ok(())
+}
+
+/// Document for creating a running activity
+fn activity_document(
+    comment: &str,
+    date: NaiveDate,
+    start: NaiveTime,
+    task_id: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "data": {
+            "type": "activities",
+            "attributes": {
+                "comment": comment,
+                "date": date.format("%Y-%m-%d").to_string(),
+                "from-time": format!("{:02}:{:02}:00", start.hour(), start.minute()),
+                "to-time": null,
+                "review": false,
+                "not-billable": false
+            },
+            "relationships": {
+                "task": {
+                    "data": {
+                        "type": "tasks",
+                        "id": task_id
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Fetch the running activity, including its task, project, customer and user
+async fn fetch_active_activity(client: &TimedClient) -> Result<ResourcesResponse<Activity>> {
+    let mut filter = FilterParams::default();
+    filter
+        .custom
+        .insert("active".to_string(), "true".to_string());
+    filter.include = Some("task,task.project,task.project.customer,user".to_string());
+
+    Ok(client.list::<Activity>(Some(&filter)).await?)
+}
+
+/// Options for switching to another activity
+#[derive(Debug, Clone, Default)]
+pub struct SwitchActivityOptions {
+    /// Task as `task`, `project/task` or `customer/project/task`
+    pub task: Option<String>,
+    pub comment: Option<String>,
+    pub show_archived: bool,
+    pub interactive: bool,
+}
+
+/// Stop the running activity and start another one at the same minute
+pub async fn switch_activity(client: &TimedClient, options: &SwitchActivityOptions) -> Result<()> {
+    use dialoguer::{theme::ColorfulTheme, Input};
+
+    // Resolve everything first, so a typo leaves the running activity alone
+    let task_id = match &options.task {
+        Some(query) => resolve_task_path(client, query, options.show_archived).await?,
+        None if options.interactive => {
+            interactive_select_task(client, options.show_archived).await?
+        }
+        None => {
+            return Err(anyhow::anyhow!(
+                "Task required. Pass a task like customer/project/task or an alias, or remove --non-interactive flag"
+            ))
+        }
+    };
+    let comment = match &options.comment {
+        Some(comment) => comment.clone(),
+        None if options.interactive => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Activity description")
+            .interact_text()?,
+        None => {
+            return Err(anyhow::anyhow!(
+                "Comment is required in non-interactive mode. Provide --comment or remove --non-interactive flag"
+            ))
+        }
+    };
+
+    let now = Local::now().naive_local();
+    switch_to(client, &task_id, &comment, now.date(), now.time()).await?;
+
+    info!(
+        "Switched to activity: {} at {:02}:{:02}",
+        comment,
+        now.hour(),
+        now.minute()
+    );
+    Ok(())
+}
+
+/// Stop the running activity and start a new one, both at the given time
+///
+/// If the new activity can't be created, the stopped one is resumed.
+async fn switch_to(
     client: &TimedClient,
-    _short: bool,
-    date_str: Option<&str>,
-    from_str: Option<&str>,
-    to_str: Option<&str>,
-    all_users: bool,
+    task_id: &str,
+    comment: &str,
+    date: NaiveDate,
+    at: NaiveTime,
 ) -> Result<()> {
+    let running = fetch_active_activity(client).await?;
+    if let Some(activity) = running.data.first() {
+        let attributes = &activity.attributes;
+        if attributes.date != date {
+            return Err(anyhow::anyhow!(
+                "The running activity '{}' started on {}. Stop it before switching",
+                attributes.comment,
+                attributes.date
+            ));
+        }
+        if attributes.from_time >= at {
+            return Err(anyhow::anyhow!(
+                "The running activity '{}' only started at {}",
+                attributes.comment,
+                attributes.from_time.format("%H:%M")
+            ));
+        }
+    }
+    let previous = running.data.first().and_then(|a| a.id.clone());
+    if let Some(id) = &previous {
+        set_activity_end(client, id, Some(at)).await?;
+    }
+
+    let activity = activity_document(comment, date, at, task_id);
+    if let Err(error) = client
+        .post::<_, serde_json::Value>("activities", &activity)
+        .await
+    {
+        if let Some(id) = &previous {
+            if let Err(restore_error) = set_activity_end(client, id, None).await {
+                return Err(anyhow::anyhow!(
+                    "Failed to start the activity ({}) and to resume the previous one: {}",
+                    error,
+                    restore_error
+                ));
+            }
+            debug!("Resumed activity {} after failing to start a new one", id);
+        }
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Show information about activities within a date range
+pub async fn show_activity(client: &TimedClient, options: &GetActivitiesOptions) -> Result<()> {
+    options.view.validate(ActivityRecord::COLUMNS)?;
+
+    // Relative dates like yesterday are turned into YYYY-MM-DD
+    let date = options.date.as_deref().map(date_param).transpose()?;
+    let from = options.from_date.as_deref().map(date_param).transpose()?;
+    let to = options.to_date.as_deref().map(date_param).transpose()?;
+    let (date_str, from_str, to_str) = (date.as_deref(), from.as_deref(), to.as_deref());
+    let all_users = options.all_users;
+
     // Create filter for activities
     let mut filter = FilterParams::default();
 
@@ -220,286 +363,358 @@ pub async fn show_activity(
 
     // Add user filter unless all_users flag is set
     if !all_users {
-        // Get current user ID from /users/me endpoint
-        let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-
-        if let Some(user_id) = current_user_response["data"]["id"].as_str() {
-            debug!("Filtering for current user: {}", user_id);
-            filter.user = Some(user_id.to_string());
-        }
+        let user_id = get_current_user_id(client).await?;
+        debug!("Filtering for current user: {}", user_id);
+        filter.user = Some(user_id);
     }
 
     // Include related entities for better display
     filter.include = Some("task,task.project,task.project.customer,user".to_string());
 
-    let response = client
-        .get::<serde_json::Value>("activities", Some(&filter))
-        .await?;
+    let response = client.list_all::<Activity>(Some(&filter)).await?;
 
-    if let Some(activities) = response["data"].as_array() {
-        if activities.is_empty() {
-            if date_str.is_some() {
-                println!("No activities found for date: {}", date_str.unwrap());
-            } else if from_str.is_some() || to_str.is_some() {
-                let from_msg = from_str.map_or("today", |d| d);
-                let to_msg = to_str.map_or("today", |d| d);
-                println!("No activities found from {from_msg} to {to_msg}");
-            } else {
-                println!("No activities found for today");
-            }
-            return Ok(());
-        }
+    // Describe the selected dates for the title
+    let period = if let Some(date) = date_str {
+        format!("for {date}")
+    } else if from_str.is_some() || to_str.is_some() {
+        let from_msg = from_str.map_or("today", |d| d);
+        let to_msg = to_str.map_or("today", |d| d);
+        format!("from {from_msg} to {to_msg}")
+    } else {
+        format!("for {}", Local::now().date_naive().format("%Y-%m-%d"))
+    };
 
-        // Show the activities as a list
-        if date_str.is_some() {
-            println!("Activities for {}", date_str.unwrap());
-        } else if from_str.is_some() || to_str.is_some() {
-            let from_msg = from_str.map_or("today", |d| d);
-            let to_msg = to_str.map_or("today", |d| d);
-            println!("Activities from {from_msg} to {to_msg}");
-        } else {
-            let today = Local::now().date_naive().format("%Y-%m-%d");
-            println!("Activities for {today}");
+    let mut records = response
+        .data
+        .iter()
+        .map(|activity| ActivityRecord::new(&response, activity))
+        .collect::<Vec<_>>();
+    records.sort_by(|a, b| options.sort_by.compare(a, b));
+
+    let mut header = vec![
+        "Start", "Duration", "Customer", "Project", "Task", "Comment",
+    ];
+    if all_users {
+        header.insert(0, "User");
+    }
+    let mut table = Table::new(header)
+        .title(format!("Activities {period}"))
+        .empty(format!("No activities found {period}"));
+
+    for record in &records {
+        let duration_str = match record.to_time {
+            Some(to_time) => format_duration(to_time.signed_duration_since(record.from_time)),
+            None => "active".to_string(),
+        };
+
+        let mut cells = vec![
+            record.from_time.format("%H:%M").to_string(),
+            duration_str,
+            record.customer.clone(),
+            record.project.clone(),
+            record.task.clone(),
+            record.comment.clone(),
+        ];
+        if all_users {
+            cells.insert(
+                0,
+                record
+                    .user
+                    .clone()
+                    .unwrap_or_else(|| "Unknown User".to_string()),
+            );
         }
-        println!("----------------------------------------");
+        table.row(cells);
+    }
+    table.footer(format!("Total: {} activities", records.len()));
 
-        for activity in activities {
-            let comment = activity["attributes"]["comment"]
-                .as_str()
-                .unwrap_or("No comment");
-            let from_time = activity["attributes"]["from-time"]
-                .as_str()
-                .unwrap_or("00:00:00");
-            let to_time = activity["attributes"]["to-time"]
-                .as_str()
-                .unwrap_or("(active)");
-
-            // Get task info (and related project/customer) if available
-            let mut task_name = "Unknown Task";
-            let mut project_name = "Unknown Project";
-            let mut customer_name = "Unknown Customer";
-            let mut username = "Unknown User";
-
-            if let Some(task_data) = activity["relationships"]["task"]["data"].as_object() {
-                if let (Some(task_type), Some(task_id)) =
-                    (task_data["type"].as_str(), task_data["id"].as_str())
-                {
-                    if task_type == "tasks" {
-                        // Find included task
-                        if let Some(included) = response["included"].as_array() {
-                            if let Some(task) = included
-                                .iter()
-                                .find(|i| i["type"] == "tasks" && i["id"] == task_id)
-                            {
-                                task_name =
-                                    task["attributes"]["name"].as_str().unwrap_or(task_name);
-
-                                // Find project
-                                if let Some(project_id) =
-                                    task["relationships"]["project"]["data"]["id"].as_str()
-                                {
-                                    if let Some(project) = included
-                                        .iter()
-                                        .find(|i| i["type"] == "projects" && i["id"] == project_id)
-                                    {
-                                        project_name = project["attributes"]["name"]
-                                            .as_str()
-                                            .unwrap_or(project_name);
-
-                                        // Find customer
-                                        if let Some(customer_id) = project["relationships"]
-                                            ["customer"]["data"]["id"]
-                                            .as_str()
-                                        {
-                                            if let Some(customer) = included.iter().find(|i| {
-                                                i["type"] == "customers" && i["id"] == customer_id
-                                            }) {
-                                                customer_name = customer["attributes"]["name"]
-                                                    .as_str()
-                                                    .unwrap_or(customer_name);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    print_view(options.output, &records, &table, &options.view)
+}
 
-            // Get user info if all_users is true
-            if all_users {
-                if let Some(user_data) = activity["relationships"]["user"]["data"].as_object() {
-                    if let (Some(user_type), Some(user_id)) =
-                        (user_data["type"].as_str(), user_data["id"].as_str())
-                    {
-                        if user_type == "users" {
-                            if let Some(included) = response["included"].as_array() {
-                                if let Some(user) = included
-                                    .iter()
-                                    .find(|i| i["type"] == "users" && i["id"] == user_id)
-                                {
-                                    username =
-                                        user["attributes"]["username"].as_str().unwrap_or(username);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+/// An activity as shown by `get activities` and `activity show` in structured output formats
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityRecord {
+    pub id: Option<String>,
+    pub date: NaiveDate,
+    pub user: Option<String>,
+    pub customer: String,
+    pub project: String,
+    pub task: String,
+    pub from_time: NaiveTime,
+    /// End time, `null` while the activity is running
+    pub to_time: Option<NaiveTime>,
+    /// Duration as `HH:MM:SS`, up to now for a running activity
+    pub duration: String,
+    /// Duration in decimal hours
+    pub hours: f64,
+    pub comment: String,
+    pub review: bool,
+    pub not_billable: bool,
+    pub transferred: bool,
+    pub active: bool,
+}
 
-            // Calculate duration if there's a to_time
-            let duration_str = if to_time != "(active)" {
-                let start = NaiveTime::parse_from_str(from_time, "%H:%M:%S").unwrap_or_default();
-                let end = NaiveTime::parse_from_str(to_time, "%H:%M:%S").unwrap_or_default();
-                let duration_mins = end.signed_duration_since(start).num_minutes();
-                format!("{:.2}h", duration_mins as f64 / 60.0)
-            } else {
-                "active".to_string()
-            };
-
-            // Format output
-            if all_users {
-                println!(
-                    "[{username}] {from_time} - {duration_str} - {customer_name}/{project_name}/{task_name} - {comment}"
-                );
-            } else {
-                println!(
-                    "{from_time} - {duration_str} - {customer_name}/{project_name}/{task_name} - {comment}"
-                );
-            }
+impl ActivityRecord {
+    /// Fields that can be selected with `--columns` or used in `--template`
+    pub const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "date",
+        "user",
+        "customer",
+        "project",
+        "task",
+        "from_time",
+        "to_time",
+        "duration",
+        "hours",
+        "comment",
+        "review",
+        "not_billable",
+        "transferred",
+        "active",
+    ];
+
+    /// Build a record, taking names from the included resources of the response
+    pub fn new(response: &ResourcesResponse<Activity>, activity: &Activity) -> Self {
+        let attributes = &activity.attributes;
+        let task_id = activity
+            .relationships
+            .task
+            .as_ref()
+            .and_then(|t| t.id())
+            .unwrap_or_default();
+        let TaskPath {
+            customer,
+            project,
+            task,
+        } = TaskPath::resolve(response, task_id);
+
+        let end = attributes.to_time.unwrap_or_else(|| Local::now().time());
+        let duration = TimedDuration::from(end.signed_duration_since(attributes.from_time));
+
+        Self {
+            id: activity.id.clone(),
+            date: attributes.date,
+            user: activity
+                .relationships
+                .user
+                .as_ref()
+                .and_then(|u| u.id())
+                .and_then(|id| resolve_username(response, id)),
+            customer,
+            project,
+            task,
+            from_time: attributes.from_time,
+            to_time: attributes.to_time,
+            duration: duration.to_string(),
+            hours: hours(duration),
+            comment: attributes.comment.clone(),
+            review: attributes.review,
+            not_billable: attributes.not_billable,
+            transferred: attributes.transferred,
+            active: attributes.to_time.is_none(),
         }
+    }
+}
 
-        println!("----------------------------------------");
-        println!("Total: {} activities", activities.len());
-
-        return Ok(());
+impl Sortable for ActivityRecord {
+    fn when(&self) -> (NaiveDate, Option<NaiveTime>) {
+        (self.date, Some(self.from_time))
     }
 
-    if date_str.is_some() {
-        println!("No activities found for date: {}", date_str.unwrap());
-    } else if from_str.is_some() || to_str.is_some() {
-        let from_msg = from_str.map_or("today", |d| d);
-        let to_msg = to_str.map_or("today", |d| d);
-        println!("No activities found from {from_msg} to {to_msg}");
-    } else {
-        println!("No activities found for today");
+    fn hours(&self) -> f64 {
+        self.hours
     }
 
-    Ok(())
+    fn task_path(&self) -> [&str; 3] {
+        [&self.customer, &self.project, &self.task]
+    }
 }
 
 /// Get the currently active activity
-pub async fn get_active_activity(client: &TimedClient, short: bool) -> Result<()> {
+pub async fn get_active_activity(
+    client: &TimedClient,
+    short: bool,
+    output: OutputFormat,
+) -> Result<()> {
     debug!("Getting active activity");
 
-    // Create filter for active activities
-    let mut filter = FilterParams::default();
-    filter
-        .custom
-        .insert("active".to_string(), "true".to_string());
-    filter.include = Some("task,task.project,task.project.customer,user".to_string());
+    let response = fetch_active_activity(client).await?;
 
-    let response = client
-        .get::<serde_json::Value>("activities", Some(&filter))
-        .await?;
-
-    if let Some(activities) = response["data"].as_array() {
-        if activities.is_empty() {
+    let Some(activity) = response.data.first() else {
+        // Structured formats get an explicit null so scripts can tell
+        if output == OutputFormat::Table || short {
             println!("No active activity found");
-            return Ok(());
+        } else {
+            print_item(output, &serde_json::Value::Null, "")?;
         }
+        return Ok(());
+    };
 
-        // If short flag is set, just show the comment
-        if short {
-            let activity = &activities[0];
-            let comment = activity["attributes"]["comment"]
-                .as_str()
-                .unwrap_or("No comment");
-            println!("{comment}");
-            return Ok(());
-        }
+    let record = ActivityRecord::new(&response, activity);
 
-        // Get the first active activity
-        let activity = &activities[0];
-        let comment = activity["attributes"]["comment"]
-            .as_str()
-            .unwrap_or("No comment");
-        let from_time = activity["attributes"]["from-time"]
-            .as_str()
-            .unwrap_or("00:00:00");
-
-        // Find task/project/customer info
-        if let Some(task_data) = activity["relationships"]["task"]["data"].as_object() {
-            if let (Some(_task_type), Some(task_id)) =
-                (task_data["type"].as_str(), task_data["id"].as_str())
-            {
-                if let Some(included) = response["included"].as_array() {
-                    if let Some(task) = included
-                        .iter()
-                        .find(|i| i["type"] == "tasks" && i["id"] == task_id)
-                    {
-                        let task_name = task["attributes"]["name"]
-                            .as_str()
-                            .unwrap_or("Unknown Task");
-
-                        if let Some(project_id) =
-                            task["relationships"]["project"]["data"]["id"].as_str()
-                        {
-                            if let Some(project) = included
-                                .iter()
-                                .find(|i| i["type"] == "projects" && i["id"] == project_id)
-                            {
-                                let project_name = project["attributes"]["name"]
-                                    .as_str()
-                                    .unwrap_or("Unknown Project");
-
-                                if let Some(customer_id) =
-                                    project["relationships"]["customer"]["data"]["id"].as_str()
-                                {
-                                    if let Some(customer) = included.iter().find(|i| {
-                                        i["type"] == "customers" && i["id"] == customer_id
-                                    }) {
-                                        let customer_name = customer["attributes"]["name"]
-                                            .as_str()
-                                            .unwrap_or("Unknown Customer");
-
-                                        // Calculate elapsed time
-                                        let start_time =
-                                            NaiveTime::parse_from_str(from_time, "%H:%M:%S")
-                                                .unwrap_or_default();
-                                        let now = Local::now().time();
-                                        let hours_elapsed =
-                                            now.signed_duration_since(start_time).num_minutes()
-                                                as f64
-                                                / 60.0;
-
-                                        println!("Active Activity");
-                                        println!("----------------------------------------");
-                                        println!("Activity: {comment}");
-                                        println!("Customer: {customer_name}");
-                                        println!("Project: {project_name}");
-                                        println!("Task: {task_name}");
-                                        println!("Started at: {from_time}");
-                                        println!("Elapsed time: {hours_elapsed:.2} hours");
-
-                                        return Ok(());
-                                    }
-                                }
-                            }
-                        }
-                    }
+    // If short flag is set, just show the comment
+    if short {
+        println!("{}", record.comment);
+        return Ok(());
+    }
+
+    let elapsed = TimedDuration::from(Local::now().time().signed_duration_since(record.from_time));
+    let text = [
+        "Active Activity".to_string(),
+        "----------------------------------------".to_string(),
+        format!("Activity: {}", record.comment),
+        format!("Customer: {}", record.customer),
+        format!("Project: {}", record.project),
+        format!("Task: {}", record.task),
+        format!("Started at: {}", record.from_time),
+        format!(
+            "Elapsed time: {} ({} hours)",
+            elapsed.format_clock(),
+            elapsed.format_decimal()
+        ),
+    ]
+    .join("\n");
+
+    print_item(output, &record, &text)
+}
+
+/// Changes to the running activity, `None` keeps a value
+#[derive(Debug, Clone, Default)]
+pub struct AmendActivityOptions {
+    pub start: Option<String>,
+    pub comment: Option<String>,
+    /// Task as `task`, `project/task` or `customer/project/task`
+    pub task: Option<String>,
+    pub show_archived: bool,
+    pub review: Option<bool>,
+    pub not_billable: Option<bool>,
+}
+
+impl AmendActivityOptions {
+    fn has_changes(&self) -> bool {
+        self.start.is_some()
+            || self.comment.is_some()
+            || self.task.is_some()
+            || self.review.is_some()
+            || self.not_billable.is_some()
+    }
+}
+
+/// Change the start time, comment, task or flags of the running activity
+pub async fn amend_activity(client: &TimedClient, options: &AmendActivityOptions) -> Result<()> {
+    amend_activity_at(client, options, Local::now().naive_local()).await
+}
+
+/// Amend the running activity as of the given current time
+async fn amend_activity_at(
+    client: &TimedClient,
+    options: &AmendActivityOptions,
+    now: NaiveDateTime,
+) -> Result<()> {
+    if !options.has_changes() {
+        return Err(anyhow::anyhow!(
+            "Nothing to change, use --start, --comment, --task, --review or --not-billable"
+        ));
+    }
+
+    let response = fetch_active_activity(client).await?;
+    let Some(activity) = response.data.first() else {
+        return Err(anyhow::anyhow!("No active activity to amend"));
+    };
+    let id = activity
+        .id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Invalid activity ID"))?;
+
+    let mut attributes = serde_json::Map::new();
+    if let Some(start) = &options.start {
+        let start = parse_time(start)?;
+        validate_activity_start(client, activity, start, now).await?;
+        attributes.insert(
+            "from-time".to_string(),
+            format!("{:02}:{:02}:00", start.hour(), start.minute()).into(),
+        );
+    }
+    if let Some(comment) = &options.comment {
+        attributes.insert("comment".to_string(), comment.clone().into());
+    }
+    if let Some(review) = options.review {
+        attributes.insert("review".to_string(), review.into());
+    }
+    if let Some(not_billable) = options.not_billable {
+        attributes.insert("not-billable".to_string(), not_billable.into());
+    }
+
+    let mut data = serde_json::json!({
+        "type": "activities",
+        "id": id,
+        "attributes": attributes
+    });
+    if let Some(task) = &options.task {
+        let task_id = resolve_task_path(client, task, options.show_archived).await?;
+        data["relationships"] = serde_json::json!({
+            "task": {
+                "data": {
+                    "type": "tasks",
+                    "id": task_id
                 }
             }
-        }
+        });
+    }
 
-        // Fallback if we couldn't get all the details
-        println!("Active Activity: {comment}");
-        println!("Started at: {from_time}");
-    } else {
-        println!("No active activity found");
+    client
+        .patch::<_, serde_json::Value>(
+            &format!("activities/{id}"),
+            &serde_json::json!({ "data": data }),
+        )
+        .await?;
+
+    info!("Activity amended");
+    Ok(())
+}
+
+/// Check that a new start of the running activity is not in the future and
+/// does not overlap the finished activities of its day
+async fn validate_activity_start(
+    client: &TimedClient,
+    activity: &Activity,
+    start: NaiveTime,
+    now: NaiveDateTime,
+) -> Result<()> {
+    let date = activity.attributes.date;
+    if date > now.date() || (date == now.date() && start > now.time()) {
+        return Err(anyhow::anyhow!(
+            "Start time {} is after the current time",
+            start.format("%H:%M")
+        ));
     }
 
+    let mut filter = FilterParams {
+        user: Some(get_current_user_id(client).await?),
+        ..Default::default()
+    };
+    filter
+        .custom
+        .insert("day".to_string(), date.format("%Y-%m-%d").to_string());
+    let day = client.list_all::<Activity>(Some(&filter)).await?;
+
+    let overlapping = day.data.iter().find(|other| {
+        other.id != activity.id && other.attributes.to_time.is_some_and(|end| end > start)
+    });
+    if let Some(other) = overlapping {
+        return Err(anyhow::anyhow!(
+            "Start time {} overlaps the activity '{}' from {} to {}",
+            start.format("%H:%M"),
+            other.attributes.comment,
+            other.attributes.from_time.format("%H:%M"),
+            other
+                .attributes
+                .to_time
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default()
+        ));
+    }
     Ok(())
 }
 
@@ -555,27 +770,8 @@ pub async fn restart_activity(client: &TimedClient, date_str: Option<&str>) -> R
         stop_activity(client).await?;
 
         // Create new activity with same task and comment
-        let activity = serde_json::json!({
-            "data": {
-                "type": "activities",
-                "attributes": {
-                    "comment": comment,
-                    "date": Local::now().date_naive().format("%Y-%m-%d").to_string(),
-                    "from-time": format!("{:02}:{:02}:00", Local::now().hour(), Local::now().minute()),
-                    "to-time": null,
-                    "review": false,
-                    "not-billable": false
-                },
-                "relationships": {
-                    "task": {
-                        "data": {
-                            "type": "tasks",
-                            "id": task_id
-                        }
-                    }
-                }
-            }
-        });
+        let now = Local::now().naive_local();
+        let activity = activity_document(comment, now.date(), now.time(), task_id);
 
         client
             .post::<_, serde_json::Value>("activities", &activity)
@@ -653,113 +849,301 @@ pub async fn delete_activity(client: &TimedClient, date_str: Option<&str>) -> Re
     ))
 }
 
-/// Generate a timesheet from the current activities
-pub async fn generate_timesheet(client: &TimedClient) -> Result<()> {
-    let today = Local::now().date_naive();
+/// Options for turning activities into reports
+#[derive(Debug, Clone, Default)]
+pub struct GenerateTimesheetOptions {
+    pub date: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub rounding: RoundingConfig,
+    pub dry_run: bool,
+    pub interactive: bool,
+}
 
-    // Get activities for today
-    let filter = FilterParams {
-        date: Some(today.format("%Y-%m-%d").to_string()),
-        include: Some("task,task.project,task.project.customer".to_string()),
-        ..Default::default()
-    };
+/// Finished activities of a day with the same task and comment, booked as one report
+#[derive(Debug, Clone)]
+struct TimesheetEntry {
+    date: NaiveDate,
+    task_id: String,
+    path: TaskPath,
+    comment: String,
+    /// Sum of the activity durations
+    tracked: TimedDuration,
+    /// Rounded duration of the report
+    duration: TimedDuration,
+    review: bool,
+    not_billable: bool,
+    activity_ids: Vec<String>,
+}
 
-    let response = client
-        .get::<serde_json::Value>("activities", Some(&filter))
-        .await?;
+/// Group finished, not yet transferred activities into report entries
+fn timesheet_entries(
+    response: &ResourcesResponse<Activity>,
+    rounding: &RoundingConfig,
+) -> Vec<TimesheetEntry> {
+    let mut entries: Vec<TimesheetEntry> = Vec::new();
+
+    for activity in &response.data {
+        let attributes = &activity.attributes;
+        let (Some(to_time), Some(id), Some(task_id)) = (
+            attributes.to_time,
+            activity.id.as_ref(),
+            activity.relationships.task.as_ref().and_then(|t| t.id()),
+        ) else {
+            continue;
+        };
+        if attributes.transferred {
+            continue;
+        }
 
-    if let Some(activities) = response["data"].as_array() {
-        if activities.is_empty() {
-            return Err(anyhow::anyhow!("No activities found for today"));
+        let tracked = TimedDuration::from(to_time.signed_duration_since(attributes.from_time));
+        let existing = entries.iter_mut().find(|e| {
+            e.date == attributes.date && e.task_id == task_id && e.comment == attributes.comment
+        });
+        match existing {
+            Some(entry) => {
+                entry.tracked += tracked;
+                entry.review |= attributes.review;
+                entry.not_billable |= attributes.not_billable;
+                entry.activity_ids.push(id.clone());
+            }
+            None => entries.push(TimesheetEntry {
+                date: attributes.date,
+                task_id: task_id.to_string(),
+                path: TaskPath::resolve(response, task_id),
+                comment: attributes.comment.clone(),
+                tracked,
+                duration: TimedDuration::ZERO,
+                review: attributes.review,
+                not_billable: attributes.not_billable,
+                activity_ids: vec![id.clone()],
+            }),
+        }
+    }
+
+    // Round the sums, not the single activities
+    for entry in &mut entries {
+        entry.duration = rounding
+            .for_task(&entry.path.customer, &entry.path.project)
+            .apply(entry.tracked);
+    }
+    entries.sort_by(|a, b| {
+        (
+            a.date,
+            &a.path.customer,
+            &a.path.project,
+            &a.path.task,
+            &a.comment,
+        )
+            .cmp(&(
+                b.date,
+                &b.path.customer,
+                &b.path.project,
+                &b.path.task,
+                &b.comment,
+            ))
+    });
+    entries
+}
+
+/// Set whether an activity was booked as a report
+async fn set_transferred(
+    client: &TimedClient,
+    id: &str,
+    transferred: bool,
+) -> libtimed::Result<()> {
+    let update = serde_json::json!({
+        "data": {
+            "type": "activities",
+            "id": id,
+            "attributes": {
+                "transferred": transferred
+            }
         }
+    });
+    client
+        .patch::<_, serde_json::Value>(&format!("activities/{id}"), &update)
+        .await?;
+    Ok(())
+}
 
-        println!("Timesheet for {}", today.format("%Y-%m-%d"));
-        println!("----------------------------------------");
+/// Turn finished activities into reports
+///
+/// Activities of a day with the same task and comment become one report with
+/// the rounded sum of their durations. Booked activities are marked as
+/// transferred, so running this again never books them twice. Returns the
+/// number of reports created.
+pub async fn generate_timesheet(
+    client: &TimedClient,
+    options: &GenerateTimesheetOptions,
+) -> Result<usize> {
+    use dialoguer::{theme::ColorfulTheme, MultiSelect};
 
-        let mut total_duration = 0.0;
+    // Relative dates like yesterday are turned into YYYY-MM-DD
+    let date = options.date.as_deref().map(date_param).transpose()?;
+    let from = options.from_date.as_deref().map(date_param).transpose()?;
+    let to = options.to_date.as_deref().map(date_param).transpose()?;
 
-        for activity in activities {
-            let comment = activity["attributes"]["comment"]
-                .as_str()
-                .unwrap_or("No comment");
-            let from_time = activity["attributes"]["from-time"]
-                .as_str()
-                .unwrap_or("00:00:00");
-            let to_time = activity["attributes"]["to-time"].as_str();
+    let mut filter = FilterParams {
+        user: Some(get_current_user_id(client).await?),
+        include: Some("task,task.project,task.project.customer".to_string()),
+        ..Default::default()
+    };
+    let period = if from.is_some() || to.is_some() {
+        filter.from_date = from.clone();
+        filter.to_date = to.clone();
+        format!(
+            "from {} to {}",
+            from.as_deref().unwrap_or("the start"),
+            to.as_deref().unwrap_or("today")
+        )
+    } else {
+        let day = date.unwrap_or_else(|| Local::now().date_naive().format("%Y-%m-%d").to_string());
+        filter.custom.insert("day".to_string(), day.clone());
+        format!("for {day}")
+    };
 
-            // Skip activities without an end time
-            if to_time.is_none() {
-                continue;
-            }
+    let response = client.list_all::<Activity>(Some(&filter)).await?;
+    if response.data.iter().any(|a| a.attributes.to_time.is_none()) {
+        println!("Skipping the running activity, stop it to include it");
+    }
 
-            let to_time = to_time.unwrap();
+    let entries = timesheet_entries(&response, &options.rounding);
+    if entries.is_empty() {
+        println!("No activities left to transfer {period}");
+        return Ok(0);
+    }
 
-            // Calculate duration
-            let start = NaiveTime::parse_from_str(from_time, "%H:%M:%S")?;
-            let end = NaiveTime::parse_from_str(to_time, "%H:%M:%S")?;
+    let mut table = Table::new([
+        "Date", "Duration", "Tracked", "Customer", "Project", "Task", "Comment",
+    ])
+    .title(format!("Timesheet {period}"));
+    for entry in &entries {
+        table.row([
+            entry.date.format("%Y-%m-%d").to_string(),
+            entry.duration.format_clock(),
+            entry.tracked.format_clock(),
+            entry.path.customer.clone(),
+            entry.path.project.clone(),
+            entry.path.task.clone(),
+            entry.comment.clone(),
+        ]);
+    }
+    let total: TimedDuration = entries.iter().map(|e| e.duration).sum();
+    let tracked: TimedDuration = entries.iter().map(|e| e.tracked).sum();
+    table.footer(format!(
+        "Total: {} ({} hours), tracked {}",
+        total.format_clock(),
+        total.format_decimal(),
+        tracked.format_clock()
+    ));
+    println!("{}", table.render());
+
+    // Entries rounded away would be booked as empty reports
+    let (bookable, empty): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| e.duration > TimedDuration::ZERO);
+    if !empty.is_empty() {
+        println!("Skipping {} entries that round to zero", empty.len());
+    }
 
-            let duration_minutes = end.signed_duration_since(start).num_minutes() as f64;
-            let duration_hours = duration_minutes / 60.0;
-            total_duration += duration_hours;
+    if options.dry_run {
+        println!("Dry run, {} reports would be created", bookable.len());
+        return Ok(0);
+    }
 
-            // Get task/project/customer info
-            let task_id = activity["relationships"]["task"]["data"]["id"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Invalid task reference"))?;
+    let selected: Vec<TimesheetEntry> = if options.interactive && !bookable.is_empty() {
+        let items: Vec<String> = bookable
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {} - {} / {} / {} - {}",
+                    e.date,
+                    e.duration.format_clock(),
+                    e.path.customer,
+                    e.path.project,
+                    e.path.task,
+                    e.comment
+                )
+            })
+            .collect();
+        let chosen = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select the entries to book")
+            .items(&items)
+            .defaults(&vec![true; items.len()])
+            .interact()?;
+        chosen.into_iter().map(|i| bookable[i].clone()).collect()
+    } else {
+        bookable
+    };
 
-            let mut task_name = "Unknown Task";
-            let mut project_name = "Unknown Project";
-            let mut customer_name = "Unknown Customer";
+    if selected.is_empty() {
+        println!("Nothing to book");
+        return Ok(0);
+    }
 
-            if let Some(included) = response["included"].as_array() {
-                // Find task
-                if let Some(task) = included
-                    .iter()
-                    .find(|item| item["type"] == "tasks" && item["id"] == task_id)
-                {
-                    task_name = task["attributes"]["name"].as_str().unwrap_or(task_name);
-
-                    // Find project
-                    if let Some(project_id) =
-                        task["relationships"]["project"]["data"]["id"].as_str()
-                    {
-                        if let Some(project) = included
-                            .iter()
-                            .find(|item| item["type"] == "projects" && item["id"] == project_id)
-                        {
-                            project_name = project["attributes"]["name"]
-                                .as_str()
-                                .unwrap_or(project_name);
-
-                            // Find customer
-                            if let Some(customer_id) =
-                                project["relationships"]["customer"]["data"]["id"].as_str()
-                            {
-                                if let Some(customer) = included.iter().find(|item| {
-                                    item["type"] == "customers" && item["id"] == customer_id
-                                }) {
-                                    customer_name = customer["attributes"]["name"]
-                                        .as_str()
-                                        .unwrap_or(customer_name);
-                                }
-                            }
+    let mut activity_count = 0;
+    for entry in &selected {
+        let report = serde_json::json!({
+            "data": {
+                "type": "reports",
+                "attributes": {
+                    "comment": entry.comment,
+                    "date": entry.date.format("%Y-%m-%d").to_string(),
+                    "duration": entry.duration.to_string(),
+                    "review": entry.review,
+                    "not-billable": entry.not_billable
+                },
+                "relationships": {
+                    "task": {
+                        "data": {
+                            "type": "tasks",
+                            "id": entry.task_id
                         }
                     }
                 }
             }
-
-            println!(
-                "{duration_hours:.2}h - {customer_name} / {project_name} / {task_name} - {comment}"
-            );
+        });
+        let created = client
+            .post::<_, serde_json::Value>("reports", &report)
+            .await?;
+        let report_id = created["data"]["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid report ID"))?;
+
+        for (marked, id) in entry.activity_ids.iter().enumerate() {
+            if let Err(error) = set_transferred(client, id, true).await {
+                // Unmarked activities would be booked again by the next run
+                let rollback = async {
+                    client.delete(&format!("reports/{report_id}")).await?;
+                    for id in &entry.activity_ids[..marked] {
+                        set_transferred(client, id, false).await?;
+                    }
+                    Ok::<_, libtimed::TimedError>(())
+                }
+                .await;
+                return Err(match rollback {
+                    Ok(()) => anyhow::anyhow!(
+                        "Failed to mark activities as transferred, the report was removed again: {}",
+                        error
+                    ),
+                    Err(rollback_error) => anyhow::anyhow!(
+                        "Failed to mark activities as transferred ({}) and to remove report {}: {}",
+                        error,
+                        report_id,
+                        rollback_error
+                    ),
+                });
+            }
         }
-
-        println!("----------------------------------------");
-        println!("Total: {total_duration:.2} hours");
-
-        return Ok(());
+        activity_count += entry.activity_ids.len();
     }
 
-    Err(anyhow::anyhow!("No activities found for today"))
+    println!(
+        "Created {} reports from {activity_count} activities",
+        selected.len()
+    );
+    Ok(selected.len())
 }
 
 /// Interactive function to select task
@@ -950,3 +1334,448 @@ async fn get_task_id(
 
     Err(anyhow::anyhow!("Failed to get task ID"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtimed::fake::{to_one, FakeTimed};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_start_stop_and_generate_timesheet() {
+        let server = FakeTimed::start().await.unwrap();
+        let task_id = server.add_task("Acme", "Website", "Development");
+        let client = server.client();
+
+        start_activity(
+            &client,
+            "Login form",
+            Some("Acme"),
+            Some("Website"),
+            Some("Development"),
+            false,
+            Some("00:00"),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let activities = server.all("activities");
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0]["attributes"]["from-time"], "00:00:00");
+        assert!(activities[0]["attributes"]["to-time"].is_null());
+        assert_eq!(
+            activities[0]["relationships"]["task"]["data"]["id"],
+            task_id
+        );
+
+        stop_activity(&client).await.unwrap();
+        let activities = server.all("activities");
+        assert!(activities[0]["attributes"]["to-time"].is_string());
+
+        // Nothing is active anymore, so stopping again is a no-op
+        stop_activity(&client).await.unwrap();
+
+        let options = GenerateTimesheetOptions {
+            rounding: RoundingConfig::disabled(),
+            ..Default::default()
+        };
+        let booked = generate_timesheet(&client, &options).await.unwrap();
+        assert_eq!(server.all("reports").len(), booked);
+    }
+
+    #[tokio::test]
+    async fn test_start_with_unknown_task_fails() {
+        let server = FakeTimed::start().await.unwrap();
+        server.add_task("Acme", "Website", "Development");
+
+        let result = start_activity(
+            &server.client(),
+            "Login form",
+            Some("Acme"),
+            Some("Website"),
+            Some("Design"),
+            false,
+            None,
+            false,
+        )
+        .await;
+
+        assert!(result.unwrap_err().to_string().contains("Task not found"));
+        assert!(server.all("activities").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generate_timesheet_books_each_activity_once() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let tickets = server.add_task("Globex", "Support", "Tickets");
+        let client = server.client();
+
+        let mut ids = Vec::new();
+        for (date, task, comment, from, to, review, transferred) in [
+            (
+                "2024-03-04",
+                &development,
+                "Login",
+                "09:00:00",
+                Some("10:00:00"),
+                false,
+                false,
+            ),
+            (
+                "2024-03-04",
+                &development,
+                "Login",
+                "10:30:00",
+                Some("10:50:00"),
+                false,
+                false,
+            ),
+            (
+                "2024-03-04",
+                &tickets,
+                "Tickets",
+                "13:00:00",
+                Some("13:20:00"),
+                true,
+                false,
+            ),
+            // Rounds to zero, so it is left for later
+            (
+                "2024-03-04",
+                &development,
+                "Call",
+                "11:00:00",
+                Some("11:05:00"),
+                false,
+                false,
+            ),
+            (
+                "2024-03-04",
+                &development,
+                "Login",
+                "14:00:00",
+                Some("15:00:00"),
+                false,
+                true,
+            ),
+            (
+                "2024-03-04",
+                &development,
+                "Login",
+                "16:00:00",
+                None,
+                false,
+                false,
+            ),
+            (
+                "2024-03-05",
+                &development,
+                "Login",
+                "09:00:00",
+                Some("10:00:00"),
+                false,
+                false,
+            ),
+        ] {
+            ids.push(server.insert(
+                "activities",
+                json!({
+                    "date": date,
+                    "comment": comment,
+                    "from-time": from,
+                    "to-time": to,
+                    "review": review,
+                    "not-billable": false,
+                    "transferred": transferred
+                }),
+                json!({ "task": to_one("tasks", task) }),
+            ));
+        }
+
+        let options = GenerateTimesheetOptions {
+            date: Some("2024-03-04".to_string()),
+            dry_run: true,
+            ..Default::default()
+        };
+        assert_eq!(generate_timesheet(&client, &options).await.unwrap(), 0);
+        assert!(server.all("reports").is_empty());
+
+        let options = GenerateTimesheetOptions {
+            dry_run: false,
+            ..options
+        };
+        assert_eq!(generate_timesheet(&client, &options).await.unwrap(), 2);
+        let reports: Vec<_> = server
+            .all("reports")
+            .iter()
+            .map(|r| {
+                let attributes = &r["attributes"];
+                (
+                    attributes["comment"].as_str().unwrap().to_string(),
+                    attributes["duration"].as_str().unwrap().to_string(),
+                    attributes["review"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            [
+                ("Login".to_string(), "01:15:00".to_string(), false),
+                ("Tickets".to_string(), "00:15:00".to_string(), true),
+            ]
+        );
+
+        let transferred: Vec<bool> = ids
+            .iter()
+            .map(|id| {
+                server.get("activities", id).unwrap()["attributes"]["transferred"]
+                    .as_bool()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(transferred, [true, true, true, false, true, false, false]);
+
+        // Running it again books nothing twice
+        assert_eq!(generate_timesheet(&client, &options).await.unwrap(), 0);
+        assert_eq!(server.all("reports").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_timesheet_rolls_back_when_marking_fails() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let client = server.client();
+
+        let ids: Vec<String> = [("09:00:00", "10:00:00"), ("10:30:00", "10:50:00")]
+            .into_iter()
+            .map(|(from, to)| {
+                server.insert(
+                    "activities",
+                    json!({
+                        "date": "2024-03-04",
+                        "comment": "Login",
+                        "from-time": from,
+                        "to-time": to,
+                        "review": false,
+                        "not-billable": false,
+                        "transferred": false
+                    }),
+                    json!({ "task": to_one("tasks", &development) }),
+                )
+            })
+            .collect();
+        server.fail("PATCH", &format!("activities/{}", ids[1]));
+
+        let options = GenerateTimesheetOptions {
+            date: Some("2024-03-04".to_string()),
+            ..Default::default()
+        };
+        let error = generate_timesheet(&client, &options).await.unwrap_err();
+        assert!(error.to_string().contains("report was removed again"));
+
+        // Neither the report nor the first mark survive, so a retry books everything once
+        assert!(server.all("reports").is_empty());
+        for id in &ids {
+            let activity = server.get("activities", id).unwrap();
+            assert_eq!(activity["attributes"]["transferred"], false);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switch_activity_at_the_same_minute() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let tickets = server.add_task("Globex", "Support", "Tickets");
+        let client = server.client();
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let login = server.insert(
+            "activities",
+            json!({
+                "date": today,
+                "comment": "Login",
+                "from-time": "00:00:00",
+                "to-time": null,
+                "review": false,
+                "not-billable": false
+            }),
+            json!({ "task": to_one("tasks", &development) }),
+        );
+
+        let options = SwitchActivityOptions {
+            task: Some("globex/support/tick".to_string()),
+            comment: Some("Tickets".to_string()),
+            ..Default::default()
+        };
+        switch_activity(&client, &options).await.unwrap();
+
+        let activities = server.all("activities");
+        assert_eq!(activities.len(), 2);
+        let stopped = &activities[0]["attributes"];
+        let started = &activities[1];
+        assert!(stopped["to-time"].is_string());
+        assert_eq!(stopped["to-time"], started["attributes"]["from-time"]);
+        assert!(started["attributes"]["to-time"].is_null());
+        assert_eq!(started["relationships"]["task"]["data"]["id"], tickets);
+
+        // A failing start resumes the activity that was just stopped
+        let running = started["id"].as_str().unwrap().to_string();
+        let at = NaiveTime::from_hms_opt(23, 59, 0).unwrap();
+        let result = switch_to(&client, "999", "Unknown", Local::now().date_naive(), at).await;
+        assert!(result.is_err());
+        assert_eq!(server.all("activities").len(), 2);
+        assert!(server.get("activities", &running).unwrap()["attributes"]["to-time"].is_null());
+        assert!(server.get("activities", &login).unwrap()["attributes"]["to-time"].is_string());
+
+        // Unknown tasks fail before anything is stopped
+        let options = SwitchActivityOptions {
+            task: Some("Hosting".to_string()),
+            ..options
+        };
+        assert!(switch_activity(&client, &options).await.is_err());
+        assert!(server.get("activities", &running).unwrap()["attributes"]["to-time"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_switch_refuses_activities_it_cannot_close() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let client = server.client();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let running = server.insert(
+            "activities",
+            json!({
+                "date": "2024-03-03",
+                "comment": "Deployment",
+                "from-time": "16:00:00",
+                "to-time": null,
+                "review": false,
+                "not-billable": false
+            }),
+            json!({ "task": to_one("tasks", &development) }),
+        );
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        // Left running since yesterday
+        let error = switch_to(&client, &development, "Review", date, at(9, 0))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("started on 2024-03-03"));
+
+        // Started in the same minute
+        let error = switch_to(
+            &client,
+            &development,
+            "Review",
+            date.pred_opt().unwrap(),
+            at(16, 0),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("only started at 16:00"));
+
+        assert_eq!(server.all("activities").len(), 1);
+        assert!(server.get("activities", &running).unwrap()["attributes"]["to-time"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_amend_running_activity() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let tickets = server.add_task("Globex", "Support", "Tickets");
+        server.insert(
+            "tasks",
+            json!({ "name": "Old hosting", "archived": true }),
+            json!({ "project": server.get("tasks", &tickets).unwrap()["relationships"]["project"] }),
+        );
+        let client = server.client();
+        let now = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+
+        let mut ids = Vec::new();
+        for (from, to, comment) in [
+            ("07:30:00", Some("08:30:00"), "Standup"),
+            ("09:00:00", None, "Login"),
+        ] {
+            ids.push(server.insert(
+                "activities",
+                json!({
+                    "date": "2024-03-04",
+                    "comment": comment,
+                    "from-time": from,
+                    "to-time": to,
+                    "review": false,
+                    "not-billable": false
+                }),
+                json!({ "task": to_one("tasks", &development) }),
+            ));
+        }
+        let running = &ids[1];
+        let amend = |options: AmendActivityOptions| {
+            let client = &client;
+            async move { amend_activity_at(client, &options, now).await }
+        };
+
+        let error = |result: Result<()>| result.unwrap_err().to_string();
+        assert!(error(amend(AmendActivityOptions::default()).await).contains("Nothing to change"));
+        assert!(error(
+            amend(AmendActivityOptions {
+                start: Some("10:15".to_string()),
+                ..Default::default()
+            })
+            .await
+        )
+        .contains("after the current time"));
+        assert!(error(
+            amend(AmendActivityOptions {
+                start: Some("08:15".to_string()),
+                ..Default::default()
+            })
+            .await
+        )
+        .contains("overlaps the activity 'Standup'"));
+        // Archived tasks are only matched with show_archived
+        assert!(error(
+            amend(AmendActivityOptions {
+                task: Some("hosting".to_string()),
+                ..Default::default()
+            })
+            .await
+        )
+        .contains("Task not found"));
+
+        amend(AmendActivityOptions {
+            start: Some("8:45".to_string()),
+            comment: Some("Login form".to_string()),
+            task: Some("tickets".to_string()),
+            review: Some(true),
+            not_billable: Some(true),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let activity = server.get("activities", running).unwrap();
+        let attributes = &activity["attributes"];
+        assert_eq!(attributes["from-time"], "08:45:00");
+        assert_eq!(attributes["comment"], "Login form");
+        assert_eq!(attributes["review"], true);
+        assert_eq!(attributes["not-billable"], true);
+        assert!(attributes["to-time"].is_null());
+        assert_eq!(activity["relationships"]["task"]["data"]["id"], tickets);
+
+        // Flags can be reset and everything else stays
+        amend(AmendActivityOptions {
+            review: Some(false),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let attributes = server.get("activities", running).unwrap()["attributes"].clone();
+        assert_eq!(attributes["review"], false);
+        assert_eq!(attributes["comment"], "Login form");
+    }
+}
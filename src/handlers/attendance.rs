@@ -1,24 +1,39 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{Local, NaiveDate, NaiveTime, Timelike};
+use dialoguer::{theme::ColorfulTheme, FuzzySelect};
+use serde::Serialize;
 use tracing::{debug, info};
 
 use libtimed::{
     models::{
         Attendance, AttendanceAttributes, AttendanceRelationships, FilterParams, RelationshipData,
-        RelationshipResource, ResourceResponse, ResourcesResponse,
+        RelationshipResource,
     },
+    transforms::TimedDuration,
     TimedClient,
 };
 
+use super::{
+    date_param, durations::parse_time_span, format_duration, get_current_user_id, hours,
+    parse_date, parse_time, resolve_username,
+};
+use crate::output::{print_list, OutputFormat, Table};
+
 /// List attendances for the current user or all users
-#[allow(dead_code)]
 pub async fn list_attendances(
     client: &TimedClient,
     date_str: Option<&str>,
     from_str: Option<&str>,
     to_str: Option<&str>,
     all_users: bool,
+    output: OutputFormat,
 ) -> Result<()> {
+    // Relative dates like yesterday are turned into YYYY-MM-DD
+    let date = date_str.map(date_param).transpose()?;
+    let from = from_str.map(date_param).transpose()?;
+    let to = to_str.map(date_param).transpose()?;
+    let (date_str, from_str, to_str) = (date.as_deref(), from.as_deref(), to.as_deref());
+
     // Create filter for attendances
     let mut filter = FilterParams::default();
 
@@ -46,148 +61,325 @@ pub async fn list_attendances(
 
     // Add user filter unless all_users flag is set
     if !all_users {
-        // Get current user ID from /users/me endpoint
-        let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-
-        if let Some(user_id) = current_user_response["data"]["id"].as_str() {
-            debug!("Filtering for current user: {}", user_id);
-            filter.user = Some(user_id.to_string());
-        }
+        let user_id = get_current_user_id(client).await?;
+        debug!("Filtering for current user: {}", user_id);
+        filter.user = Some(user_id);
     }
 
     // Include related entities for better display
     filter.include = Some("user".to_string());
 
-    let response = client
-        .get::<ResourcesResponse<Attendance>>("attendances", Some(&filter))
-        .await?;
-
-    // Display results
-    if response.data.is_empty() {
-        if date_str.is_some() {
-            println!("No attendances found for date: {}", date_str.unwrap());
-        } else if from_str.is_some() || to_str.is_some() {
-            let from_msg = from_str.map_or("today", |d| d);
-            let to_msg = to_str.map_or("today", |d| d);
-            println!("No attendances found from {from_msg} to {to_msg}");
-        } else {
-            println!("No attendances found for today");
-        }
-        return Ok(());
-    }
+    let response = client.list_all::<Attendance>(Some(&filter)).await?;
 
-    // Display date range in the header
-    if date_str.is_some() {
-        println!("Attendances for {}", date_str.unwrap());
+    // Describe the selected dates for the title
+    let period = if let Some(date) = date_str {
+        format!("for {date}")
     } else if from_str.is_some() || to_str.is_some() {
         let from_msg = from_str.map_or("today", |d| d);
         let to_msg = to_str.map_or("today", |d| d);
-        println!("Attendances from {from_msg} to {to_msg}");
+        format!("from {from_msg} to {to_msg}")
     } else {
-        let today = Local::now().date_naive().format("%Y-%m-%d");
-        println!("Attendances for {today}");
-    }
+        format!("for {}", Local::now().date_naive().format("%Y-%m-%d"))
+    };
 
-    println!("----------------------------------------");
+    let records: Vec<AttendanceRecord> = response
+        .data
+        .iter()
+        .map(|attendance| {
+            let attributes = &attendance.attributes;
+            let duration = attributes
+                .to_time
+                .map(|end| TimedDuration::from(end.signed_duration_since(attributes.from_time)));
+            AttendanceRecord {
+                id: attendance.id.clone(),
+                date: attributes.date,
+                user: attendance
+                    .relationships
+                    .user
+                    .as_ref()
+                    .and_then(|u| u.id())
+                    .and_then(|id| resolve_username(&response, id)),
+                from_time: attributes.from_time,
+                to_time: attributes.to_time,
+                duration: duration.map(|d| d.to_string()),
+                hours: duration.map(hours),
+            }
+        })
+        .collect();
 
-    // Get included data
-    let included = response.included.unwrap_or_default();
+    let mut header = vec!["ID", "Date", "From", "To"];
+    if all_users {
+        header.insert(0, "User");
+    }
+    let mut table = Table::new(header)
+        .title(format!("Attendances {period}"))
+        .empty(format!("No attendances found {period}"));
 
-    // Display each attendance
-    for attendance in response.data {
-        let date = attendance.attributes.date;
-        let from_time = attendance.attributes.from_time;
-        let to_time = attendance
-            .attributes
-            .to_time
-            .unwrap_or_else(|| "-".to_string());
+    let mut total = chrono::Duration::zero();
+    for (attendance, record) in response.data.iter().zip(&records) {
+        if let Some(end) = attendance.attributes.to_time {
+            total += end.signed_duration_since(record.from_time);
+        }
 
-        // Get user info if we're showing all users
-        let mut user_prefix = "".to_string();
+        let mut cells = vec![
+            record.id.clone().unwrap_or_else(|| "N/A".to_string()),
+            record.date.to_string(),
+            record.from_time.to_string(),
+            record
+                .to_time
+                .map_or_else(|| "-".to_string(), |t| t.to_string()),
+        ];
         if all_users {
-            if let Some(user_rel) = &attendance.relationships.user {
-                if let Some(user_res) = &user_rel.data {
-                    let user_id = &user_res.id;
-
-                    // Find user in included data
-                    if let Some(user) = included
-                        .iter()
-                        .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                    {
-                        if let Some(username) =
-                            user.attributes.get("username").and_then(|n| n.as_str())
-                        {
-                            user_prefix = format!("[{username}] ");
-                        }
-                    }
-                }
-            }
+            cells.insert(0, record.user.clone().unwrap_or_default());
         }
-
-        println!("{user_prefix}Date: {date} | From: {from_time} | To: {to_time}");
+        table.row(cells);
     }
+    table.footer(format!("Total: {}", format_duration(total)));
 
-    Ok(())
+    print_list(output, &records, &table)
+}
+
+/// An attendance as shown by `attendance list` in structured output formats
+#[derive(Debug, Clone, Serialize)]
+pub struct AttendanceRecord {
+    pub id: Option<String>,
+    pub date: NaiveDate,
+    pub user: Option<String>,
+    pub from_time: NaiveTime,
+    /// End time, `null` while clocked in
+    pub to_time: Option<NaiveTime>,
+    /// Duration as `HH:MM:SS`, `null` while clocked in
+    pub duration: Option<String>,
+    /// Duration in decimal hours, `null` while clocked in
+    pub hours: Option<f64>,
 }
 
-/// Create a new attendance for the current user
-#[allow(dead_code)]
-pub async fn create_attendance(
+/// The current time, truncated to the minute
+fn now_minute() -> NaiveTime {
+    let now = Local::now().time();
+    NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now)
+}
+
+/// Get the current user's attendances on a date, ordered by start time
+async fn attendances_on(
     client: &TimedClient,
-    date_str: &str,
-    from_time: &str,
-    to_time: Option<&str>,
+    user_id: &str,
+    date: NaiveDate,
+) -> Result<Vec<Attendance>> {
+    let filter = FilterParams {
+        date: Some(date.format("%Y-%m-%d").to_string()),
+        user: Some(user_id.to_string()),
+        ..Default::default()
+    };
+
+    let mut attendances = client.list_all::<Attendance>(Some(&filter)).await?.data;
+    attendances.sort_by_key(|a| a.attributes.from_time);
+    Ok(attendances)
+}
+
+/// Parse a time like `08:00`, or a span like `8-12` giving both times
+fn parse_time_or_span(text: &str) -> Result<(NaiveTime, Option<NaiveTime>)> {
+    if text.contains('-') {
+        let (start, end) = parse_time_span(text)?;
+        Ok((start, Some(end)))
+    } else {
+        Ok((parse_time(text)?, None))
+    }
+}
+
+/// Clock in by opening an attendance without an end time
+///
+/// A day may have several attendances, e.g. before and after lunch,
+/// but only one of them can be open and they must not overlap. As the
+/// open attendance runs until now, it must start after all closed ones.
+/// A span like `8-12` adds a closed attendance instead, e.g. from paper notes.
+pub async fn clock_in(
+    client: &TimedClient,
+    date_str: Option<&str>,
+    time_str: Option<&str>,
 ) -> Result<()> {
-    // Get current user info
-    let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-    let user_id = current_user_response["data"]["id"]
-        .as_str()
-        .ok_or_else(|| anyhow::anyhow!("Failed to get user ID"))?;
+    let date = parse_date(date_str)?;
+    let (from_time, to_time) = match time_str {
+        Some(time) => parse_time_or_span(time)?,
+        None => (now_minute(), None),
+    };
+    let user_id = get_current_user_id(client).await?;
+
+    for attendance in attendances_on(client, &user_id, date).await? {
+        let start = attendance.attributes.from_time;
+        let overlaps = match (attendance.attributes.to_time, to_time) {
+            (None, None) => {
+                return Err(anyhow::anyhow!(
+                    "Already clocked in since {}. Clock out first",
+                    start.format("%H:%M")
+                ))
+            }
+            (None, Some(to_time)) => start < to_time,
+            (Some(end), None) => from_time < end,
+            (Some(end), Some(to_time)) => from_time < end && start < to_time,
+        };
+        if overlaps {
+            let end = attendance
+                .attributes
+                .to_time
+                .map_or_else(|| "now".to_string(), |t| t.format("%H:%M").to_string());
+            return Err(anyhow::anyhow!(
+                "{} overlaps the attendance from {} to {}",
+                match to_time {
+                    Some(to_time) =>
+                        format!("{}-{}", from_time.format("%H:%M"), to_time.format("%H:%M")),
+                    None => from_time.format("%H:%M").to_string(),
+                },
+                start.format("%H:%M"),
+                end
+            ));
+        }
+    }
 
-    // Create attendance
     let attendance = Attendance {
         id: None,
         type_name: "attendances".to_string(),
         attributes: AttendanceAttributes {
-            date: date_str.to_string(),
-            from_time: from_time.to_string(),
-            to_time: to_time.map(|s| s.to_string()),
+            date,
+            from_time,
+            to_time,
         },
         relationships: AttendanceRelationships {
             user: Some(RelationshipData {
                 data: Some(RelationshipResource {
                     type_name: "users".to_string(),
-                    id: user_id.to_string(),
+                    id: user_id,
                 }),
             }),
         },
     };
+    let response = client.create(&attendance).await?;
 
-    // Post to API
-    let request_body = serde_json::json!({
-        "data": attendance
-    });
+    info!("Clocked in with attendance ID: {:?}", response.data.id);
+    match to_time {
+        Some(to_time) => println!(
+            "Added attendance from {} to {} on {date}",
+            from_time.format("%H:%M"),
+            to_time.format("%H:%M")
+        ),
+        None => println!("Clocked in at {} on {date}", from_time.format("%H:%M")),
+    }
 
-    let response = client
-        .post::<_, ResourceResponse<Attendance>>("attendances", &request_body)
-        .await?;
+    Ok(())
+}
 
-    info!(
-        "Created attendance for {} with ID: {:?}",
-        date_str, response.data.id
-    );
+/// Clock out by closing the open attendance
+pub async fn clock_out(
+    client: &TimedClient,
+    date_str: Option<&str>,
+    time_str: Option<&str>,
+) -> Result<()> {
+    let date = parse_date(date_str)?;
+    let to_time = time_str
+        .map(parse_time)
+        .transpose()?
+        .unwrap_or_else(now_minute);
+    let user_id = get_current_user_id(client).await?;
+
+    let (mut open, closed): (Vec<_>, Vec<_>) = attendances_on(client, &user_id, date)
+        .await?
+        .into_iter()
+        .partition(|a| a.attributes.to_time.is_none());
+    let mut attendance = open
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("Not clocked in on {}", date))?;
+
+    let from_time = attendance.attributes.from_time;
+    if to_time <= from_time {
+        return Err(anyhow::anyhow!(
+            "Clock out time {} must be after clock in time {}",
+            to_time.format("%H:%M"),
+            from_time.format("%H:%M")
+        ));
+    }
+
+    // Closing the attendance must not swallow a later one
+    if let Some(later) = closed.iter().find(|a| {
+        let start = a.attributes.from_time;
+        from_time < start && start < to_time
+    }) {
+        return Err(anyhow::anyhow!(
+            "Clock out time {} overlaps the attendance from {} to {}",
+            to_time.format("%H:%M"),
+            later.attributes.from_time.format("%H:%M"),
+            later
+                .attributes
+                .to_time
+                .map_or_else(String::new, |t| t.format("%H:%M").to_string())
+        ));
+    }
+
+    attendance.attributes.to_time = Some(to_time);
+    client.update(&attendance).await?;
+
+    info!("Clocked out of attendance ID: {:?}", attendance.id);
     println!(
-        "Created attendance for {} from {} to {}",
-        date_str,
-        from_time,
-        to_time.unwrap_or("now")
+        "Clocked out at {} ({} since {})",
+        to_time.format("%H:%M"),
+        format_duration(to_time.signed_duration_since(from_time)),
+        from_time.format("%H:%M")
     );
 
     Ok(())
 }
 
+/// Resolve the attendance to work on, asking the user if a date has several
+async fn select_attendance(
+    client: &TimedClient,
+    attendance_id: Option<&str>,
+    date_str: Option<&str>,
+    interactive: bool,
+) -> Result<String> {
+    if let Some(id) = attendance_id {
+        return Ok(id.to_string());
+    }
+
+    let date = parse_date(date_str)?;
+    let user_id = get_current_user_id(client).await?;
+    let attendances = attendances_on(client, &user_id, date).await?;
+
+    let attendance = match attendances.as_slice() {
+        [] => return Err(anyhow::anyhow!("No attendances found for {}", date)),
+        [attendance] => attendance,
+        attendances if interactive => {
+            let items: Vec<String> = attendances
+                .iter()
+                .map(|a| {
+                    let to_time = a
+                        .attributes
+                        .to_time
+                        .map_or_else(|| "open".to_string(), |t| t.format("%H:%M").to_string());
+                    format!("{} - {}", a.attributes.from_time.format("%H:%M"), to_time)
+                })
+                .collect();
+
+            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                .with_prompt("Select attendance")
+                .default(0)
+                .items(&items)
+                .interact()?;
+            &attendances[selection]
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Multiple attendances found for {}. Pass an attendance ID or remove --non-interactive flag",
+                date
+            ))
+        }
+    };
+
+    attendance
+        .id
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("Invalid attendance ID"))
+}
+
 /// Update an existing attendance record
-#[allow(dead_code)]
 pub async fn update_attendance(
     client: &TimedClient,
     attendance_id: &str,
@@ -195,38 +387,49 @@ pub async fn update_attendance(
     from_time: Option<&str>,
     to_time: Option<&str>,
 ) -> Result<()> {
+    // A span like 8-12 sets both times
+    let (from_time, span_end) = match from_time {
+        Some(time) => {
+            let (from_time, span_end) = parse_time_or_span(time)?;
+            (Some(from_time), span_end)
+        }
+        None => (None, None),
+    };
+    if span_end.is_some() && to_time.is_some() {
+        return Err(anyhow::anyhow!(
+            "Pass either a time span or --to-time, not both"
+        ));
+    }
+
     // Fetch current attendance data
-    let endpoint = format!("attendances/{attendance_id}");
-    let current = client
-        .get::<ResourceResponse<Attendance>>(&endpoint, None)
-        .await?;
+    let current = client.fetch::<Attendance>(attendance_id, None).await?;
 
     // Update with new values or keep existing ones
     let attendance = Attendance {
         id: Some(attendance_id.to_string()),
         type_name: "attendances".to_string(),
         attributes: AttendanceAttributes {
-            date: date_str
-                .map(|s| s.to_string())
-                .unwrap_or(current.data.attributes.date),
-            from_time: from_time
-                .map(|s| s.to_string())
-                .unwrap_or(current.data.attributes.from_time),
-            to_time: to_time
-                .map(|s| s.to_string())
-                .or(current.data.attributes.to_time),
+            date: match date_str {
+                Some(date) => parse_date(Some(date))?,
+                None => current.data.attributes.date,
+            },
+            from_time: from_time.unwrap_or(current.data.attributes.from_time),
+            to_time: match to_time {
+                Some(time) => Some(parse_time(time)?),
+                None => span_end.or(current.data.attributes.to_time),
+            },
         },
         relationships: current.data.relationships,
     };
 
-    // Patch to API
-    let request_body = serde_json::json!({
-        "data": attendance
-    });
+    if let Some(end) = attendance.attributes.to_time {
+        if end <= attendance.attributes.from_time {
+            return Err(anyhow::anyhow!("End time must be after start time"));
+        }
+    }
 
-    let _response = client
-        .patch::<_, ResourceResponse<Attendance>>(&endpoint, &request_body)
-        .await?;
+    // Patch to API
+    client.update(&attendance).await?;
 
     info!("Updated attendance with ID: {}", attendance_id);
     println!("Updated attendance");
@@ -235,13 +438,205 @@ pub async fn update_attendance(
 }
 
 /// Delete an attendance record by ID
-#[allow(dead_code)]
 pub async fn delete_attendance(client: &TimedClient, attendance_id: &str) -> Result<()> {
-    let endpoint = format!("attendances/{attendance_id}");
-    client.delete(&endpoint).await?;
+    client.destroy::<Attendance>(attendance_id).await?;
 
     info!("Deleted attendance with ID: {}", attendance_id);
     println!("Deleted attendance");
 
     Ok(())
 }
+
+/// Edit an attendance given by ID or picked from a date
+///
+/// Without an ID the date selects the attendance instead of changing it.
+pub async fn edit_attendance(
+    client: &TimedClient,
+    attendance_id: Option<&str>,
+    date_str: Option<&str>,
+    from_time: Option<&str>,
+    to_time: Option<&str>,
+    interactive: bool,
+) -> Result<()> {
+    if from_time.is_none() && to_time.is_none() && (attendance_id.is_none() || date_str.is_none()) {
+        return Err(anyhow::anyhow!(
+            "Nothing to change. Provide --from-time, --to-time or a new --date"
+        ));
+    }
+
+    let id = select_attendance(client, attendance_id, date_str, interactive).await?;
+    let new_date = attendance_id.and(date_str);
+    update_attendance(client, &id, new_date, from_time, to_time).await
+}
+
+/// Delete an attendance given by ID or picked from a date
+pub async fn remove_attendance(
+    client: &TimedClient,
+    attendance_id: Option<&str>,
+    date_str: Option<&str>,
+    interactive: bool,
+) -> Result<()> {
+    let id = select_attendance(client, attendance_id, date_str, interactive).await?;
+    delete_attendance(client, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtimed::fake::{to_one, FakeTimed};
+    use serde_json::json;
+
+    fn times(server: &FakeTimed) -> Vec<(String, String)> {
+        server
+            .all("attendances")
+            .iter()
+            .map(|a| {
+                (
+                    a["attributes"]["from-time"].as_str().unwrap().to_string(),
+                    a["attributes"]["to-time"]
+                        .as_str()
+                        .unwrap_or("open")
+                        .to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_clock_in_and_out_with_lunch_break() {
+        let server = FakeTimed::start().await.unwrap();
+        let client = server.client();
+        let date = Some("2024-03-04");
+
+        clock_in(&client, date, Some("08:00")).await.unwrap();
+        assert!(clock_in(&client, date, Some("08:30")).await.is_err());
+        clock_out(&client, date, Some("12:00")).await.unwrap();
+
+        // The morning is taken, the afternoon is not
+        assert!(clock_in(&client, date, Some("11:00")).await.is_err());
+        clock_in(&client, date, Some("12:45")).await.unwrap();
+        assert!(clock_out(&client, date, Some("12:30")).await.is_err());
+        clock_out(&client, date, Some("17:15")).await.unwrap();
+
+        assert_eq!(
+            times(&server),
+            vec![
+                ("08:00:00".to_string(), "12:00:00".to_string()),
+                ("12:45:00".to_string(), "17:15:00".to_string()),
+            ]
+        );
+        assert!(clock_out(&client, date, Some("18:00")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clock_in_before_a_closed_attendance_is_rejected() {
+        let server = FakeTimed::start().await.unwrap();
+        let client = server.client();
+        let date = Some("2024-03-04");
+
+        clock_in(&client, date, Some("13:00")).await.unwrap();
+        clock_out(&client, date, Some("17:00")).await.unwrap();
+
+        // An open attendance from 08:00 would run across the afternoon
+        assert!(clock_in(&client, date, Some("08:00")).await.is_err());
+        clock_in(&client, date, Some("17:30")).await.unwrap();
+        assert_eq!(server.all("attendances").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_clock_out_across_a_later_attendance_is_rejected() {
+        let server = FakeTimed::start().await.unwrap();
+        let client = server.client();
+        let date = Some("2024-03-04");
+
+        clock_in(&client, date, Some("13:00")).await.unwrap();
+        clock_out(&client, date, Some("17:00")).await.unwrap();
+        // An open morning attendance, e.g. created in the web interface
+        server.insert(
+            "attendances",
+            json!({ "date": "2024-03-04", "from-time": "08:00:00", "to-time": null }),
+            json!({ "user": to_one("users", &server.current_user_id()) }),
+        );
+
+        assert!(clock_out(&client, date, Some("18:00")).await.is_err());
+        clock_out(&client, date, Some("12:00")).await.unwrap();
+        assert_eq!(
+            times(&server),
+            vec![
+                ("13:00:00".to_string(), "17:00:00".to_string()),
+                ("08:00:00".to_string(), "12:00:00".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enter_attendances_as_spans() {
+        let server = FakeTimed::start().await.unwrap();
+        let client = server.client();
+        let date = Some("2024-03-04");
+
+        clock_in(&client, date, Some("8-12")).await.unwrap();
+        assert!(clock_in(&client, date, Some("11:30-13")).await.is_err());
+        clock_in(&client, date, Some("13:00-17:30")).await.unwrap();
+        assert!(clock_in(&client, date, Some("12-9"))
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("ends before it starts"));
+        assert_eq!(
+            times(&server),
+            vec![
+                ("08:00:00".to_string(), "12:00:00".to_string()),
+                ("13:00:00".to_string(), "17:30:00".to_string()),
+            ]
+        );
+
+        // A span given as start time replaces both times
+        let first = server.all("attendances")[0]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        edit_attendance(&client, Some(&first), None, Some("7:30-11:45"), None, false)
+            .await
+            .unwrap();
+        assert!(edit_attendance(
+            &client,
+            Some(&first),
+            None,
+            Some("7:30-11:45"),
+            Some("12:00"),
+            false
+        )
+        .await
+        .is_err());
+        assert_eq!(
+            times(&server)[0],
+            ("07:30:00".to_string(), "11:45:00".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_remove_by_date() {
+        let server = FakeTimed::start().await.unwrap();
+        let client = server.client();
+        let date = Some("2024-03-04");
+
+        clock_in(&client, date, Some("08:00")).await.unwrap();
+        edit_attendance(&client, None, date, None, Some("16:30"), false)
+            .await
+            .unwrap();
+        assert_eq!(
+            times(&server),
+            vec![("08:00:00".to_string(), "16:30:00".to_string())]
+        );
+
+        assert!(
+            edit_attendance(&client, None, date, Some("17:00"), None, false)
+                .await
+                .is_err()
+        );
+
+        remove_attendance(&client, None, date, false).await.unwrap();
+        assert!(server.all("attendances").is_empty());
+    }
+}
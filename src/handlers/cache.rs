@@ -0,0 +1,28 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::info;
+
+use libtimed::cache::{default_cache_dir, ResponseCache};
+
+fn cache_dir() -> Result<PathBuf> {
+    default_cache_dir().ok_or_else(|| anyhow::anyhow!("Could not determine cache directory"))
+}
+
+/// Remove all cached responses
+pub fn clear_cache() -> Result<()> {
+    let dir = cache_dir()?;
+    ResponseCache::persistent(&dir).clear()?;
+
+    info!("Cache cleared at {}", dir.display());
+    println!("Cache cleared");
+
+    Ok(())
+}
+
+/// Show the cache directory path
+pub fn cache_path() -> Result<()> {
+    let dir = cache_dir()?;
+    println!("Cache directory: {}", dir.display());
+
+    Ok(())
+}
@@ -1,7 +1,11 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
+use libtimed::cache::{default_cache_dir, ResponseCache};
+
+use crate::config::RoundingMode;
 use crate::config::{get_default_config_path, TimedConfig};
 
 /// View the current configuration
@@ -12,6 +16,39 @@ pub fn view_config(config: &TimedConfig, config_path: &Path) -> Result<()> {
     println!("Timed URL: {}", config.timed_url);
     println!("SSO Discovery URL: {}", config.sso_discovery_url);
     println!("SSO Client ID: {}", config.sso_client_id);
+    println!("API Namespace: {}", config.api_namespace);
+    println!("Timeout: {}s", config.timeout_secs);
+    if let Some(user_agent) = &config.user_agent {
+        println!("User Agent: {user_agent}");
+    }
+    if let Some(proxy) = &config.proxy {
+        println!("Proxy: {proxy}");
+    }
+    for path in &config.ca_certificates {
+        println!("CA Certificate: {}", path.display());
+    }
+    if let Some(path) = &config.client_identity {
+        println!("Client Identity: {}", path.display());
+    }
+    println!(
+        "Rounding: {} minutes, {}",
+        config.rounding.increment_minutes,
+        rounding_mode_name(config.rounding.mode)
+    );
+    for o in &config.rounding.overrides {
+        let target = match &o.project {
+            Some(project) => format!("{} / {project}", o.customer),
+            None => o.customer.clone(),
+        };
+        let increment = o
+            .increment_minutes
+            .unwrap_or(config.rounding.increment_minutes);
+        let mode = o.mode.unwrap_or(config.rounding.mode);
+        println!(
+            "Rounding for {target}: {increment} minutes, {}",
+            rounding_mode_name(mode)
+        );
+    }
     println!("----------------------------------------");
 
     // Check if token exists
@@ -53,12 +90,45 @@ pub fn set_config(
     value: &str,
 ) -> Result<()> {
     debug!("Setting configuration {} = {}", key, value);
+    let previous_username = config.username.clone();
 
     match key {
         "username" => config.username = value.to_string(),
         "timed_url" | "timedurl" => config.timed_url = value.to_string(),
         "sso_discovery_url" | "ssodiscoveryurl" => config.sso_discovery_url = value.to_string(),
         "sso_client_id" | "ssoclientid" => config.sso_client_id = value.to_string(),
+        "api_namespace" | "apinamespace" => config.api_namespace = value.to_string(),
+        "timeout" | "timeout_secs" => {
+            config.timeout_secs = value
+                .parse()
+                .context("Timeout must be a number of seconds")?
+        }
+        "user_agent" | "useragent" => config.user_agent = non_empty(value),
+        "proxy" => config.proxy = non_empty(value),
+        "ca_certificates" | "cacertificates" => {
+            config.ca_certificates = value
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        }
+        "client_identity" | "clientidentity" => {
+            config.client_identity = non_empty(value).map(PathBuf::from)
+        }
+        "rounding.increment_minutes" | "rounding_increment" => {
+            config.rounding.increment_minutes = value
+                .parse()
+                .context("Rounding increment must be a number of minutes")?
+        }
+        "rounding.mode" | "rounding_mode" => {
+            config.rounding.mode = RoundingMode::from_str(value, true).map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid rounding mode: {} (use nearest, up, down or none)",
+                    value
+                )
+            })?
+        }
         _ => return Err(anyhow::anyhow!("Unknown configuration key: {}", key)),
     }
 
@@ -72,12 +142,33 @@ pub fn set_config(
 
     std::fs::write(config_path, toml).context("Failed to write configuration file")?;
 
+    // Cached responses like the current user belong to the previous username
+    if config.username != previous_username {
+        if let Some(dir) = default_cache_dir() {
+            ResponseCache::persistent(dir)
+                .clear()
+                .context("Failed to clear cache")?;
+        }
+    }
+
     info!("Configuration updated successfully");
     println!("{key} = {value}");
 
     Ok(())
 }
 
+/// Name of a rounding mode as written in the configuration file
+fn rounding_mode_name(mode: RoundingMode) -> String {
+    mode.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Treat an empty value as unsetting an optional key
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.to_string()).filter(|v| !v.is_empty())
+}
+
 /// Set a configuration value without loading the config first
 pub fn set_config_without_loading(config_path: &Path, key: &str, value: &str) -> Result<()> {
     debug!(
@@ -165,6 +256,9 @@ pub fn init_config(config_path: &Path) -> Result<()> {
     println!("  - timed_url");
     println!("  - sso_discovery_url");
     println!("  - sso_client_id");
+    println!("Optional keys:");
+    println!("  - api_namespace, timeout, user_agent, proxy");
+    println!("  - ca_certificates (comma-separated PEM files), client_identity (PEM file)");
 
     Ok(())
 }
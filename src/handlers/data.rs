@@ -1,10 +1,39 @@
 use anyhow::Result;
+use serde::Serialize;
 use tracing::debug;
 
 use libtimed::{models::FilterParams, TimedClient};
 
+use crate::output::{print_list, OutputFormat, Table};
+
+/// A customer as shown by `get data customers`
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomerRecord {
+    pub id: String,
+    pub name: String,
+    pub archived: bool,
+}
+
+/// A project as shown by `get data projects`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectRecord {
+    pub id: String,
+    pub name: String,
+    pub customer_id: String,
+    pub archived: bool,
+}
+
+/// A task as shown by `get data tasks`
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskRecord {
+    pub id: String,
+    pub name: String,
+    pub project_id: String,
+    pub archived: bool,
+}
+
 /// Get customers data
-pub async fn get_customers(client: &TimedClient, output_format: &str) -> Result<()> {
+pub async fn get_customers(client: &TimedClient, output: OutputFormat) -> Result<()> {
     debug!("Getting customers data");
 
     let filter = FilterParams::default();
@@ -13,42 +42,33 @@ pub async fn get_customers(client: &TimedClient, output_format: &str) -> Result<
         .get::<serde_json::Value>("customers", Some(&filter))
         .await?;
 
-    if let Some(customers) = response["data"].as_array() {
-        match output_format {
-            "json" => {
-                println!("{}", serde_json::to_string_pretty(&customers)?);
-            }
-            "csv" => {
-                println!("id,name,archived");
-                for customer in customers {
-                    let id = customer["id"].as_str().unwrap_or("");
-                    let name = customer["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = customer["attributes"]["archived"]
-                        .as_bool()
-                        .unwrap_or(false);
-                    println!("{id},{name},{archived}");
-                }
-            }
-            "text" => {
-                println!("Customers:");
-                for customer in customers {
-                    let id = customer["id"].as_str().unwrap_or("");
-                    let name = customer["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = customer["attributes"]["archived"]
-                        .as_bool()
-                        .unwrap_or(false);
-                    println!("{id}: {name} (archived: {archived})");
-                }
-            }
-            _ => {
-                return Err(anyhow::anyhow!("Invalid output format: {}", output_format));
-            }
-        }
-
-        return Ok(());
+    let customers = response["data"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("No customers found"))?;
+
+    let records: Vec<CustomerRecord> = customers
+        .iter()
+        .map(|customer| CustomerRecord {
+            id: text(&customer["id"]),
+            name: text(&customer["attributes"]["name"]),
+            archived: customer["attributes"]["archived"]
+                .as_bool()
+                .unwrap_or(false),
+        })
+        .collect();
+
+    let mut table = Table::new(["ID", "Name", "Archived"])
+        .title("Customers")
+        .empty("No customers found");
+    for record in &records {
+        table.row([
+            record.id.clone(),
+            record.name.clone(),
+            record.archived.to_string(),
+        ]);
     }
 
-    Err(anyhow::anyhow!("No customers found"))
+    print_list(output, &records, &table)
 }
 
 /// Get projects data
@@ -57,7 +77,7 @@ pub async fn get_projects(
     customer_id: Option<i32>,
     customer_name: Option<&str>,
     archived: bool,
-    output_format: &str,
+    output: OutputFormat,
 ) -> Result<()> {
     debug!("Getting projects data");
 
@@ -97,44 +117,33 @@ pub async fn get_projects(
         .get::<serde_json::Value>("projects", Some(&filter))
         .await?;
 
-    if let Some(projects) = response["data"].as_array() {
-        match output_format {
-            "json" => {
-                println!("{}", serde_json::to_string_pretty(&projects)?);
-            }
-            "csv" => {
-                println!("id,name,customer_id,archived");
-                for project in projects {
-                    let id = project["id"].as_str().unwrap_or("");
-                    let name = project["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = project["attributes"]["archived"].as_bool().unwrap_or(false);
-                    let customer_id = project["relationships"]["customer"]["data"]["id"]
-                        .as_str()
-                        .unwrap_or("");
-                    println!("{id},{name},{customer_id},{archived}");
-                }
-            }
-            "text" => {
-                println!("Projects:");
-                for project in projects {
-                    let id = project["id"].as_str().unwrap_or("");
-                    let name = project["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = project["attributes"]["archived"].as_bool().unwrap_or(false);
-                    let customer_id = project["relationships"]["customer"]["data"]["id"]
-                        .as_str()
-                        .unwrap_or("");
-                    println!("{id}: {name} (customer: {customer_id}, archived: {archived})");
-                }
-            }
-            _ => {
-                return Err(anyhow::anyhow!("Invalid output format: {}", output_format));
-            }
-        }
-
-        return Ok(());
+    let projects = response["data"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("No projects found"))?;
+
+    let records: Vec<ProjectRecord> = projects
+        .iter()
+        .map(|project| ProjectRecord {
+            id: text(&project["id"]),
+            name: text(&project["attributes"]["name"]),
+            customer_id: text(&project["relationships"]["customer"]["data"]["id"]),
+            archived: project["attributes"]["archived"].as_bool().unwrap_or(false),
+        })
+        .collect();
+
+    let mut table = Table::new(["ID", "Name", "Customer", "Archived"])
+        .title("Projects")
+        .empty("No projects found");
+    for record in &records {
+        table.row([
+            record.id.clone(),
+            record.name.clone(),
+            record.customer_id.clone(),
+            record.archived.to_string(),
+        ]);
     }
 
-    Err(anyhow::anyhow!("No projects found"))
+    print_list(output, &records, &table)
 }
 
 /// Get tasks data
@@ -145,7 +154,7 @@ pub async fn get_tasks(
     project_id: Option<i32>,
     project_name: Option<&str>,
     archived: bool,
-    output_format: &str,
+    output: OutputFormat,
 ) -> Result<()> {
     debug!("Getting tasks data");
 
@@ -273,42 +282,35 @@ pub async fn get_tasks(
         .get::<serde_json::Value>("tasks", Some(&filter))
         .await?;
 
-    if let Some(tasks) = response["data"].as_array() {
-        match output_format {
-            "json" => {
-                println!("{}", serde_json::to_string_pretty(&tasks)?);
-            }
-            "csv" => {
-                println!("id,name,project_id,archived");
-                for task in tasks {
-                    let id = task["id"].as_str().unwrap_or("");
-                    let name = task["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = task["attributes"]["archived"].as_bool().unwrap_or(false);
-                    let project_id = task["relationships"]["project"]["data"]["id"]
-                        .as_str()
-                        .unwrap_or("");
-                    println!("{id},{name},{project_id},{archived}");
-                }
-            }
-            "text" => {
-                println!("Tasks:");
-                for task in tasks {
-                    let id = task["id"].as_str().unwrap_or("");
-                    let name = task["attributes"]["name"].as_str().unwrap_or("");
-                    let archived = task["attributes"]["archived"].as_bool().unwrap_or(false);
-                    let project_id = task["relationships"]["project"]["data"]["id"]
-                        .as_str()
-                        .unwrap_or("");
-                    println!("{id}: {name} (project: {project_id}, archived: {archived})");
-                }
-            }
-            _ => {
-                return Err(anyhow::anyhow!("Invalid output format: {}", output_format));
-            }
-        }
-
-        return Ok(());
+    let tasks = response["data"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("No tasks found"))?;
+
+    let records: Vec<TaskRecord> = tasks
+        .iter()
+        .map(|task| TaskRecord {
+            id: text(&task["id"]),
+            name: text(&task["attributes"]["name"]),
+            project_id: text(&task["relationships"]["project"]["data"]["id"]),
+            archived: task["attributes"]["archived"].as_bool().unwrap_or(false),
+        })
+        .collect();
+
+    let mut table = Table::new(["ID", "Name", "Project", "Archived"])
+        .title("Tasks")
+        .empty("No tasks found");
+    for record in &records {
+        table.row([
+            record.id.clone(),
+            record.name.clone(),
+            record.project_id.clone(),
+            record.archived.to_string(),
+        ]);
     }
 
-    Err(anyhow::anyhow!("No tasks found"))
+    print_list(output, &records, &table)
+}
+
+fn text(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
 }
@@ -0,0 +1,322 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, Weekday};
+
+/// Formats accepted for absolute dates
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%d-%m-%Y"];
+
+/// Parse an absolute or relative date
+///
+/// Besides dates like 2024-03-15 or 15.03.2024 this accepts `today`,
+/// `yesterday`, `tomorrow`, offsets like `-3d` or `+1w`, weekdays like `mon`
+/// (the last one up to today) and `last friday` or `next monday`.
+pub fn parse_relative_date(text: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let text = text.trim();
+    let lower = text.to_lowercase();
+
+    if let Some(date) = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+    {
+        return Ok(date);
+    }
+
+    let date = match lower.as_str() {
+        "today" => Some(today),
+        "yesterday" => Some(today - Duration::days(1)),
+        "tomorrow" => Some(today + Duration::days(1)),
+        _ => None,
+    };
+    if let Some(date) = date.or_else(|| parse_offset(&lower, today)) {
+        return Ok(date);
+    }
+
+    let (direction, day) = match lower.split_once(' ') {
+        Some((direction, day)) => (Some(direction), day.trim()),
+        None => (None, lower.as_str()),
+    };
+    let weekday = day.parse::<Weekday>().ok();
+    match (direction, weekday) {
+        // The last one up to and including today
+        (None, Some(weekday)) => Ok(today - Duration::days(days_since(today, weekday))),
+        (Some("last"), Some(weekday)) => {
+            let days = days_since(today - Duration::days(1), weekday) + 1;
+            Ok(today - Duration::days(days))
+        }
+        (Some("next"), Some(weekday)) => {
+            let days = (7 + weekday.num_days_from_monday() as i64
+                - today.weekday().num_days_from_monday() as i64
+                - 1)
+                % 7
+                + 1;
+            Ok(today + Duration::days(days))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Invalid date: {} (use YYYY-MM-DD, today, yesterday, -3d, mon or last friday)",
+            text
+        )),
+    }
+}
+
+/// Parse an offset from today like `-3d`, `+2w` or `3d`
+fn parse_offset(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let unit_days = match rest.chars().last()? {
+        'd' => 1,
+        'w' => 7,
+        _ => return None,
+    };
+    let count: i64 = rest[..rest.len() - 1].parse().ok()?;
+    let days = count.checked_mul(unit_days)?.checked_mul(sign)?;
+    today.checked_add_signed(Duration::try_days(days)?)
+}
+
+/// Days from the last given weekday up to a date, 0 if it is that weekday
+fn days_since(date: NaiveDate, weekday: Weekday) -> i64 {
+    (7 + date.weekday().num_days_from_monday() as i64 - weekday.num_days_from_monday() as i64) % 7
+}
+
+/// An inclusive range of dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl DateRange {
+    /// Parse a named range relative to today
+    ///
+    /// Accepts `this-week`, `last-week`, `this-month`, `last-month`,
+    /// `this-year`, `last-year`, `ytd`, ISO weeks like `2025-W07`, months like
+    /// `2025-03` and any single date [`parse_relative_date`] accepts.
+    pub fn parse(text: &str, today: NaiveDate) -> Result<Self> {
+        let text = text.trim();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let month_start = today.with_day(1).unwrap_or(today);
+        let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap_or(today);
+
+        let range = match text.to_lowercase().as_str() {
+            "this-week" => Self::week(monday),
+            "last-week" => Self::week(monday - Duration::days(7)),
+            "this-month" => Self::month(month_start),
+            "last-month" => Self::month(month_start - Months::new(1)),
+            "this-year" => Self::year(today.year()),
+            "last-year" => Self::year(today.year() - 1),
+            "ytd" => Self {
+                from: year_start,
+                to: today,
+            },
+            other => match parse_iso_week(other).or_else(|| parse_month(other)) {
+                Some(range) => range,
+                None => {
+                    let date = parse_relative_date(text, today).map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid range: {} (use this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03 or a date)",
+                            text
+                        )
+                    })?;
+                    Self {
+                        from: date,
+                        to: date,
+                    }
+                }
+            },
+        };
+        Ok(range)
+    }
+
+    /// Parse a range relative to the current date, for command line arguments
+    pub fn parse_arg(text: &str) -> Result<Self> {
+        Self::parse(text, Local::now().date_naive())
+    }
+
+    /// Use the range as `--from` and `--to`, unless it is missing
+    pub fn or(
+        range: Option<Self>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> (Option<String>, Option<String>) {
+        match range {
+            Some(range) => (
+                Some(range.from.format("%Y-%m-%d").to_string()),
+                Some(range.to.format("%Y-%m-%d").to_string()),
+            ),
+            None => (from, to),
+        }
+    }
+
+    fn week(monday: NaiveDate) -> Self {
+        Self {
+            from: monday,
+            to: monday + Duration::days(6),
+        }
+    }
+
+    fn month(first: NaiveDate) -> Self {
+        Self {
+            from: first,
+            to: first + Months::new(1) - Duration::days(1),
+        }
+    }
+
+    fn year(year: i32) -> Self {
+        Self {
+            from: NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default(),
+            to: NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or_default(),
+        }
+    }
+}
+
+/// Parse an ISO week like 2025-W07
+fn parse_iso_week(text: &str) -> Option<DateRange> {
+    let (year, week) = text.split_once("-w")?;
+    let monday = NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)?;
+    Some(DateRange::week(monday))
+}
+
+/// Parse a month like 2025-03
+fn parse_month(text: &str) -> Option<DateRange> {
+    let (year, month) = text.split_once('-')?;
+    if year.len() != 4 || month.len() > 2 {
+        return None;
+    }
+    let first = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)?;
+    Some(DateRange::month(first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    /// Wednesday
+    fn today() -> NaiveDate {
+        date("2025-01-01")
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        for text in ["2024-03-15", "2024/03/15", "15.03.2024", "15-03-2024"] {
+            assert_eq!(
+                parse_relative_date(text, today()).unwrap(),
+                date("2024-03-15")
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        let cases = [
+            ("today", "2025-01-01"),
+            ("Yesterday", "2024-12-31"),
+            ("tomorrow", "2025-01-02"),
+            ("-3d", "2024-12-29"),
+            ("+1w", "2025-01-08"),
+            ("2d", "2025-01-03"),
+            ("wed", "2025-01-01"),
+            ("mon", "2024-12-30"),
+            ("friday", "2024-12-27"),
+            ("last wednesday", "2024-12-25"),
+            ("last friday", "2024-12-27"),
+            ("next monday", "2025-01-06"),
+            ("next wed", "2025-01-08"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_relative_date(text, today()).unwrap(),
+                date(expected),
+                "{text}"
+            );
+        }
+
+        for text in [
+            "someday",
+            "last",
+            "3x",
+            "next month",
+            "2024-02-30",
+            "999999999999999d",
+            "-9999999999999999999w",
+            "2000000000000000000w",
+        ] {
+            assert!(parse_relative_date(text, today()).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_ranges_across_boundaries() {
+        let range = |text, today| {
+            let range = DateRange::parse(text, date(today)).unwrap();
+            (range.from.to_string(), range.to.to_string())
+        };
+        let expect = |from: &str, to: &str| (from.to_string(), to.to_string());
+
+        // Weeks spanning the turn of the year
+        assert_eq!(
+            range("this-week", "2025-01-01"),
+            expect("2024-12-30", "2025-01-05")
+        );
+        assert_eq!(
+            range("last-week", "2025-01-01"),
+            expect("2024-12-23", "2024-12-29")
+        );
+        assert_eq!(
+            range("this-week", "2025-03-09"),
+            expect("2025-03-03", "2025-03-09")
+        );
+
+        // Months of different lengths and the previous year
+        assert_eq!(
+            range("last-month", "2025-03-31"),
+            expect("2025-02-01", "2025-02-28")
+        );
+        assert_eq!(
+            range("this-month", "2024-02-10"),
+            expect("2024-02-01", "2024-02-29")
+        );
+        assert_eq!(
+            range("last-month", "2025-01-15"),
+            expect("2024-12-01", "2024-12-31")
+        );
+        assert_eq!(
+            range("ytd", "2025-03-09"),
+            expect("2025-01-01", "2025-03-09")
+        );
+        assert_eq!(
+            range("last-year", "2025-03-09"),
+            expect("2024-01-01", "2024-12-31")
+        );
+
+        // ISO week 1 can start in the previous year, week 53 ends in the next
+        assert_eq!(
+            range("2025-W01", "2025-03-09"),
+            expect("2024-12-30", "2025-01-05")
+        );
+        assert_eq!(
+            range("2025-W07", "2025-03-09"),
+            expect("2025-02-10", "2025-02-16")
+        );
+        assert_eq!(
+            range("2020-w53", "2025-03-09"),
+            expect("2020-12-28", "2021-01-03")
+        );
+        assert_eq!(
+            range("2025-03", "2025-01-01"),
+            expect("2025-03-01", "2025-03-31")
+        );
+        assert_eq!(
+            range("yesterday", "2025-01-01"),
+            expect("2024-12-31", "2024-12-31")
+        );
+
+        for text in ["2025-W54", "2025-13", "next-week", "2025-W"] {
+            assert!(DateRange::parse(text, today()).is_err(), "{text}");
+        }
+    }
+}
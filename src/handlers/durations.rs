@@ -0,0 +1,191 @@
+use anyhow::Result;
+use chrono::NaiveTime;
+
+use libtimed::transforms::TimedDuration;
+
+use super::parse_time;
+
+const DURATION_HELP: &str = "use 1h30m, 90m, 1.25, 1:30, 09:00-12:30 or sums like 1h+20m";
+
+/// Longest duration accepted, for a single term as well as for the sum
+const MAX_HOURS: i64 = 24;
+
+/// Parse a duration entered by hand
+///
+/// Accepts units (`1h30m`, `45m`, `1.5h`), clock durations (`1:30`), decimal
+/// hours (`1.25`), wall-clock spans (`09:00-12:30`, `9-12:30`) and sums of
+/// those (`1h+20m`).
+pub fn parse_duration_input(text: &str) -> Result<TimedDuration> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("Duration is empty ({})", DURATION_HELP));
+    }
+
+    let mut total = TimedDuration::ZERO;
+    for term in text.split('+') {
+        total += parse_term(term)?;
+    }
+
+    if total <= TimedDuration::ZERO {
+        return Err(anyhow::anyhow!("Duration must be positive: {}", text));
+    }
+    if total > TimedDuration::seconds(MAX_HOURS * 3600) {
+        return Err(too_long(&text));
+    }
+    Ok(total)
+}
+
+/// Parse a wall-clock span like `09:00-12:30` or `9-12:30`
+pub fn parse_time_span(text: &str) -> Result<(NaiveTime, NaiveTime)> {
+    let invalid = || anyhow::anyhow!("Invalid time span: '{}' (use 09:00-12:30 or 9-12:30)", text);
+    let (start, end) = text.split_once('-').ok_or_else(invalid)?;
+    let start = parse_time(start).map_err(|_| invalid())?;
+    let end = parse_time(end).map_err(|_| invalid())?;
+    if end <= start {
+        return Err(anyhow::anyhow!("Time span ends before it starts: {}", text));
+    }
+    Ok((start, end))
+}
+
+/// Parse one summand of a duration
+fn parse_term(term: &str) -> Result<TimedDuration> {
+    let invalid = || anyhow::anyhow!("Invalid duration: '{}' ({})", term, DURATION_HELP);
+
+    // Bound hours before converting, huge values overflow the duration
+    let from_hours = |hours: f64| {
+        if hours > MAX_HOURS as f64 {
+            Err(too_long(term))
+        } else {
+            TimedDuration::from_hours(hours).ok_or_else(|| too_long(term))
+        }
+    };
+
+    if term.contains('-') {
+        let (start, end) = parse_time_span(term)?;
+        return Ok(TimedDuration::from(end - start));
+    }
+
+    if term.contains(['h', 'm']) {
+        return from_hours(parse_units(term).ok_or_else(invalid)?);
+    }
+
+    if let Some((hours, rest)) = term.split_once(':') {
+        let (minutes, seconds) = rest.split_once(':').unwrap_or((rest, "0"));
+        let (Ok(hours), Ok(minutes), Ok(seconds)) = (
+            hours.parse::<i64>(),
+            minutes.parse::<i64>(),
+            seconds.parse::<i64>(),
+        ) else {
+            return Err(invalid());
+        };
+        if minutes >= 60 || seconds >= 60 {
+            return Err(invalid());
+        }
+        if hours > MAX_HOURS {
+            return Err(too_long(term));
+        }
+        return Ok(TimedDuration::seconds(
+            hours * 3600 + minutes * 60 + seconds,
+        ));
+    }
+
+    let hours = term
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|hours| hours.is_finite() && *hours >= 0.0)
+        .ok_or_else(invalid)?;
+    from_hours(hours)
+}
+
+/// Error for durations above `MAX_HOURS`
+fn too_long(text: &str) -> anyhow::Error {
+    anyhow::anyhow!("Duration is longer than {} hours: {}", MAX_HOURS, text)
+}
+
+/// Parse hours and minutes with units, like `1h30m`, `1.5h`, `45m` or `1h30`,
+/// into hours
+fn parse_units(term: &str) -> Option<f64> {
+    let mut hours = 0.0;
+    let mut number = String::new();
+    let mut last_unit = None;
+
+    for c in term.chars() {
+        match c {
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            'h' | 'm' if !number.is_empty() && last_unit != Some(c) && last_unit != Some('m') => {
+                let value: f64 = number.parse().ok()?;
+                hours += if c == 'h' { value } else { value / 60.0 };
+                number.clear();
+                last_unit = Some(c);
+            }
+            _ => return None,
+        }
+    }
+
+    // Trailing minutes after hours, like 1h30
+    if !number.is_empty() {
+        if last_unit != Some('h') {
+            return None;
+        }
+        hours += number.parse::<f64>().ok()? / 60.0;
+    }
+
+    Some(hours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn minutes(text: &str) -> i64 {
+        parse_duration_input(text).unwrap().num_minutes()
+    }
+
+    #[test]
+    fn test_parse_duration_input() {
+        let cases = [
+            ("1h30m", 90),
+            ("1h30", 90),
+            ("90m", 90),
+            ("45m", 45),
+            ("1.5h", 90),
+            ("2h", 120),
+            ("1.25", 75),
+            ("0,5", 30),
+            ("1:30", 90),
+            ("01:30:00", 90),
+            ("09:00-12:30", 210),
+            ("9-12:30", 210),
+            ("8:15-9", 45),
+            ("1h+20m", 80),
+            ("9-12 + 13-17:30", 450),
+            (" 1h 30m ", 90),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(minutes(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_input_errors() {
+        let error = |text| parse_duration_input(text).unwrap_err().to_string();
+
+        assert!(error("").contains("empty"));
+        assert!(error("12:30-09:00").contains("ends before it starts"));
+        assert!(error("25:00-26:00").starts_with("Invalid time span"));
+        assert!(error("9-").starts_with("Invalid time span"));
+        assert!(error("0m").contains("positive"));
+        for text in ["1e13", "10000000000000h", "25", "24:30", "20h+5h"] {
+            assert!(error(text).contains("longer than 24 hours"), "{text}");
+        }
+        for text in ["abc", "1x", "1:75", "1h+", "30m15", "1m1h", "1hh"] {
+            assert!(
+                error(text).starts_with("Invalid duration"),
+                "{text}: {}",
+                error(text)
+            );
+        }
+    }
+}
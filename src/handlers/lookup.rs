@@ -0,0 +1,341 @@
+use anyhow::Result;
+
+use libtimed::{
+    models::{Customer, FilterParams, Project, Task, User},
+    TimedClient,
+};
+
+/// Find an item by ID, by name or by a unique part of its name
+///
+/// Names are compared case-insensitively and an exact name wins over partial matches.
+pub fn find_by_name<'a, T>(
+    items: &'a [T],
+    query: &str,
+    kind: &str,
+    id: impl Fn(&T) -> Option<&str>,
+    name: impl Fn(&T) -> &str,
+) -> Result<&'a T> {
+    let query = query.trim();
+    let needle = query.to_lowercase();
+
+    if let Some(item) = items.iter().find(|item| id(item) == Some(query)) {
+        return Ok(item);
+    }
+
+    let exact: Vec<&T> = items
+        .iter()
+        .filter(|item| name(item).to_lowercase() == needle)
+        .collect();
+    let candidates = if exact.is_empty() {
+        items
+            .iter()
+            .filter(|item| name(item).to_lowercase().contains(&needle))
+            .collect()
+    } else {
+        exact
+    };
+
+    match candidates.as_slice() {
+        [item] => Ok(item),
+        [] => Err(anyhow::anyhow!("{} not found: {}", kind, query)),
+        _ => Err(anyhow::anyhow!(
+            "{} '{}' is ambiguous: {}",
+            kind,
+            query,
+            candidates
+                .iter()
+                .map(|item| name(item))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Turn a found item into its ID
+fn id_of(id: Option<&str>, kind: &str) -> Result<String> {
+    id.map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} ID", kind.to_lowercase()))
+}
+
+/// Resolve a username or user ID to a user ID
+pub async fn resolve_user_id(client: &TimedClient, query: &str) -> Result<String> {
+    let users = client.list_all::<User>(None).await?.data;
+    let user = find_by_name(
+        &users,
+        query,
+        "User",
+        |u| u.id.as_deref(),
+        |u| &u.attributes.username,
+    )?;
+    id_of(user.id.as_deref(), "User")
+}
+
+/// Resolve a customer name or ID to a customer ID
+pub async fn resolve_customer_id(client: &TimedClient, query: &str) -> Result<String> {
+    let customers = client.list_all::<Customer>(None).await?.data;
+    let customer = find_by_name(
+        &customers,
+        query,
+        "Customer",
+        |c| c.id.as_deref(),
+        |c| &c.attributes.name,
+    )?;
+    id_of(customer.id.as_deref(), "Customer")
+}
+
+/// Resolve a project name or ID to a project ID, optionally within a customer
+pub async fn resolve_project_id(
+    client: &TimedClient,
+    query: &str,
+    customer_id: Option<&str>,
+) -> Result<String> {
+    let filter = FilterParams {
+        customer: customer_id.map(String::from),
+        ..Default::default()
+    };
+    let projects = client.list_all::<Project>(Some(&filter)).await?.data;
+    let project = find_by_name(
+        &projects,
+        query,
+        "Project",
+        |p| p.id.as_deref(),
+        |p| &p.attributes.name,
+    )?;
+    id_of(project.id.as_deref(), "Project")
+}
+
+/// Resolve a task name or ID to a task ID, optionally within a project
+pub async fn resolve_task_id(
+    client: &TimedClient,
+    query: &str,
+    project_id: Option<&str>,
+) -> Result<String> {
+    let filter = FilterParams {
+        project: project_id.map(String::from),
+        ..Default::default()
+    };
+    let tasks = client.list_all::<Task>(Some(&filter)).await?.data;
+    let task = find_by_name(
+        &tasks,
+        query,
+        "Task",
+        |t| t.id.as_deref(),
+        |t| &t.attributes.name,
+    )?;
+    id_of(task.id.as_deref(), "Task")
+}
+
+/// Resolve a task given as `task`, `project/task` or `customer/project/task`
+///
+/// Each part is matched like the `--customer`, `--project` and `--task` filters.
+/// Archived customers, projects and tasks are skipped unless `show_archived` is set.
+pub async fn resolve_task_path(
+    client: &TimedClient,
+    query: &str,
+    show_archived: bool,
+) -> Result<String> {
+    let parts: Vec<&str> = query.split('/').map(str::trim).collect();
+    let (customer, project, task) = match parts.as_slice() {
+        [task] => (None, None, *task),
+        [project, task] => (None, Some(*project), *task),
+        [customer, project, task] => (Some(*customer), Some(*project), *task),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Invalid task: {} (use task, project/task or customer/project/task)",
+                query
+            ))
+        }
+    };
+
+    let mut filter = FilterParams::default();
+    if !show_archived {
+        filter
+            .custom
+            .insert("archived".to_string(), "0".to_string());
+    }
+
+    if let Some(customer) = customer {
+        let customers = client.list_all::<Customer>(Some(&filter)).await?.data;
+        let customer = find_by_name(
+            &customers,
+            customer,
+            "Customer",
+            |c| c.id.as_deref(),
+            |c| &c.attributes.name,
+        )?;
+        filter.customer = Some(id_of(customer.id.as_deref(), "Customer")?);
+    }
+    if let Some(project) = project {
+        let projects = client.list_all::<Project>(Some(&filter)).await?.data;
+        let project = find_by_name(
+            &projects,
+            project,
+            "Project",
+            |p| p.id.as_deref(),
+            |p| &p.attributes.name,
+        )?;
+        filter.customer = None;
+        filter.project = Some(id_of(project.id.as_deref(), "Project")?);
+    }
+
+    let tasks = client.list_all::<Task>(Some(&filter)).await?.data;
+    let task = find_by_name(
+        &tasks,
+        task,
+        "Task",
+        |t| t.id.as_deref(),
+        |t| &t.attributes.name,
+    )?;
+    id_of(task.id.as_deref(), "Task")
+}
+
+/// IDs of the customer, project, task and user to filter by
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedFilters {
+    pub customer: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub user: Option<String>,
+}
+
+impl ResolvedFilters {
+    /// Resolve names given on the command line to IDs
+    ///
+    /// Each level narrows down the next one, so a project name only has to be
+    /// unique within the given customer.
+    pub async fn resolve(
+        client: &TimedClient,
+        customer: Option<&str>,
+        project: Option<&str>,
+        task: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<Self> {
+        let customer = match customer {
+            Some(name) => Some(resolve_customer_id(client, name).await?),
+            None => None,
+        };
+        let project = match project {
+            Some(name) => Some(resolve_project_id(client, name, customer.as_deref()).await?),
+            None => None,
+        };
+        let task = match task {
+            Some(name) => Some(resolve_task_id(client, name, project.as_deref()).await?),
+            None => None,
+        };
+        let user = match user {
+            Some(name) => Some(resolve_user_id(client, name).await?),
+            None => None,
+        };
+
+        Ok(Self {
+            customer,
+            project,
+            task,
+            user,
+        })
+    }
+
+    /// Apply the filters to request parameters
+    pub fn apply(&self, filter: &mut FilterParams) {
+        filter.customer = self.customer.clone().or(filter.customer.take());
+        filter.project = self.project.clone().or(filter.project.take());
+        filter.task = self.task.clone().or(filter.task.take());
+        filter.user = self.user.clone().or(filter.user.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtimed::fake::{to_one, FakeTimed};
+    use serde_json::json;
+
+    #[test]
+    fn test_find_by_name() {
+        let items = vec![("1", "Website"), ("2", "Web shop"), ("3", "Support")];
+        let find = |query| find_by_name(&items, query, "Project", |i| Some(i.0), |i| i.1);
+
+        assert_eq!(find("website").unwrap().0, "1");
+        assert_eq!(find("sup").unwrap().0, "3");
+        assert_eq!(find("2").unwrap().0, "2");
+        assert!(find("web").unwrap_err().to_string().contains("ambiguous"));
+        assert!(find("Hosting").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_filters_within_parents() {
+        let server = FakeTimed::start().await.unwrap();
+        let acme_dev = server.add_task("Acme", "Website", "Development");
+        server.add_task("Globex", "Website", "Development");
+        let client = server.client();
+
+        let resolved =
+            ResolvedFilters::resolve(&client, Some("acme"), Some("website"), Some("dev"), None)
+                .await
+                .unwrap();
+        assert_eq!(resolved.task, Some(acme_dev));
+
+        // Without a customer the project name is ambiguous
+        assert!(
+            ResolvedFilters::resolve(&client, None, Some("website"), None, None)
+                .await
+                .is_err()
+        );
+
+        let resolved = ResolvedFilters::resolve(&client, None, None, None, Some("fake"))
+            .await
+            .unwrap();
+        assert_eq!(resolved.user, Some(server.current_user_id()));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_task_path() {
+        let server = FakeTimed::start().await.unwrap();
+        let acme_dev = server.add_task("Acme", "Website", "Development");
+        let globex_dev = server.add_task("Globex", "Website", "Development");
+        let support = server.add_task("Globex", "Support", "Tickets");
+        let client = server.client();
+
+        let resolve = |query| resolve_task_path(&client, query, false);
+        assert_eq!(resolve("tick").await.unwrap(), support);
+        assert_eq!(resolve("acme/web/dev").await.unwrap(), acme_dev);
+        assert_eq!(
+            resolve("Globex / Website / Development").await.unwrap(),
+            globex_dev
+        );
+        assert!(resolve("dev")
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("ambiguous"));
+        assert!(resolve("a/b/c/d").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_task_path_skips_archived() {
+        let server = FakeTimed::start().await.unwrap();
+        let active = server.add_task("Acme", "Website", "Development");
+        let project = server.all("projects")[0]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let archived = server.insert(
+            "tasks",
+            json!({ "name": "Legacy development", "archived": true }),
+            json!({ "project": to_one("projects", &project) }),
+        );
+        let client = server.client();
+
+        assert_eq!(
+            resolve_task_path(&client, "acme/website/dev", false)
+                .await
+                .unwrap(),
+            active
+        );
+        assert!(resolve_task_path(&client, "legacy", false).await.is_err());
+        assert_eq!(
+            resolve_task_path(&client, "legacy", true).await.unwrap(),
+            archived
+        );
+    }
+}
@@ -1,21 +1,51 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
-use chrono::{Local, NaiveDate};
+use chrono::{Local, NaiveDate, NaiveTime};
+use clap::ValueEnum;
 use dialoguer::{theme::ColorfulTheme, Input};
-use serde_json;
+use reqwest::header::CONTENT_DISPOSITION;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tokio::io::AsyncWriteExt;
 use tracing::{debug, info};
 
-use libtimed::{models::FilterParams, TimedClient};
+use libtimed::{
+    models::{FilterParams, Report, ResourcesResponse},
+    transforms::TimedDuration,
+    TimedClient,
+};
 
-use super::parse_date;
+use super::{
+    capitalize, date_param, durations::parse_duration_input, format_duration, get_current_user_id,
+    hours, lookup::ResolvedFilters, parse_date, resolve_username, ListSort, Sortable, TaskPath,
+};
+use crate::config::{Rounding, RoundingConfig};
+use crate::output::{print_list, print_view, ListView, OutputFormat, Table};
 
 /// Options for getting reports
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GetReportsOptions {
     pub date: Option<String>,
     pub from_date: Option<String>,
     pub to_date: Option<String>,
     pub all_users: bool,
     pub interactive: bool,
+    pub customer: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub review: Option<bool>,
+    pub not_billable: Option<bool>,
+    pub verified: Option<bool>,
+    pub rejected: Option<bool>,
+    pub billed: Option<bool>,
+    pub comment_contains: Option<String>,
+    pub output: OutputFormat,
+    pub sort_by: ListSort,
+    pub view: ListView,
+    pub group_by: Vec<ReportGroup>,
 }
 
 /// Options for adding a new report
@@ -31,34 +61,18 @@ pub struct AddReportOptions {
     pub review: bool,
     pub not_billable: bool,
     pub interactive: bool,
+    pub rounding: RoundingConfig,
 }
 
 /// Get reports for a specified date or date range
 ///
 /// If no date parameters are provided, defaults to current date.
 /// Interactive mode can be enabled to prompt for date selection.
-pub async fn get_reports(
-    client: &TimedClient,
-    date_str: Option<&str>,
-    from_str: Option<&str>,
-    to_str: Option<&str>,
-    all_users: bool,
-    interactive: bool,
-) -> Result<()> {
-    let options = GetReportsOptions {
-        date: date_str.map(String::from),
-        from_date: from_str.map(String::from),
-        to_date: to_str.map(String::from),
-        all_users,
-        interactive,
-    };
+pub async fn get_reports(client: &TimedClient, options: &GetReportsOptions) -> Result<()> {
+    options.view.validate(ReportRecord::COLUMNS)?;
 
-    get_reports_with_options(client, options).await
-}
-
-async fn get_reports_with_options(client: &TimedClient, options: GetReportsOptions) -> Result<()> {
     // Determine date input method
-    let (date_param, from_param, to_param) = determine_date_parameters(&options).await?;
+    let (date_param, from_param, to_param) = determine_date_parameters(options).await?;
 
     // Parse date for display purposes
     let date = if let Some(date) = date_param.as_deref() {
@@ -96,201 +110,435 @@ async fn get_reports_with_options(client: &TimedClient, options: GetReportsOptio
 
     // Add user filter unless all_users flag is set
     if !options.all_users {
-        // Get current user ID from /users/me endpoint
-        let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
+        let user_id = get_current_user_id(client).await?;
+        debug!("Filtering for current user: {}", user_id);
+        filter.user = Some(user_id);
+    }
+
+    // Customer, project and task names are resolved to IDs, each within the previous one
+    ResolvedFilters::resolve(
+        client,
+        options.customer.as_deref(),
+        options.project.as_deref(),
+        options.task.as_deref(),
+        None,
+    )
+    .await?
+    .apply(&mut filter);
+    filter.review = options.review.map(i32::from);
+    filter.not_billable = options.not_billable.map(i32::from);
+    filter.verified = options.verified.map(i32::from);
+    filter.rejected = options.rejected.map(i32::from);
+    filter.billed = options.billed.map(i32::from);
+    filter.comment = options.comment_contains.clone();
+
+    let response = client.list_all::<Report>(Some(&filter)).await?;
+
+    // Describe the selected dates for the title
+    let period = if let Some(ref date) = date_param {
+        format!("for {date}")
+    } else if from_param.is_some() || to_param.is_some() {
+        let from_msg = from_param.as_deref().unwrap_or("today");
+        let to_msg = to_param.as_deref().unwrap_or("today");
+        format!("from {from_msg} to {to_msg}")
+    } else {
+        format!("for {}", Local::now().date_naive().format("%Y-%m-%d"))
+    };
 
-        if let Some(user_id) = current_user_response["data"]["id"].as_str() {
-            debug!("Filtering for current user: {}", user_id);
-            filter.user = Some(user_id.to_string());
+    let mut rows = response
+        .data
+        .iter()
+        .map(|report| (report, ReportRecord::new(&response, report)))
+        .collect::<Vec<_>>();
+    rows.sort_by(|(_, a), (_, b)| options.sort_by.compare(a, b));
+    let (reports, records): (Vec<&Report>, Vec<ReportRecord>) = rows.into_iter().unzip();
+
+    if !options.group_by.is_empty() {
+        let rows: Vec<(TimedDuration, &ReportRecord)> = reports
+            .iter()
+            .map(|report| TimedDuration::from(report.attributes.duration))
+            .zip(&records)
+            .collect();
+        let (records, table) =
+            grouped_reports(&options.group_by, options.all_users, &period, &rows);
+        return print_list(options.output, &records, &table);
+    }
+
+    let mut header = vec![
+        "Duration", "Customer", "Project", "Task", "Comment", "Flags",
+    ];
+    if options.all_users {
+        header.insert(0, "User");
+    }
+    let mut table = Table::new(header)
+        .title(format!("Reports {period}"))
+        .empty(format!("No reports found {period}"));
+
+    let mut total_duration = TimedDuration::ZERO;
+    for (report, record) in reports.iter().zip(&records) {
+        let duration = TimedDuration::from(report.attributes.duration);
+        total_duration += duration;
+
+        let mut cells = vec![
+            duration.format_clock(),
+            record.customer.clone(),
+            record.project.clone(),
+            record.task.clone(),
+            record.comment.clone(),
+            record.flags().join(", "),
+        ];
+        if options.all_users {
+            cells.insert(
+                0,
+                record.user.clone().unwrap_or_else(|| "Unknown".to_string()),
+            );
         }
+        table.row(cells);
     }
+    table.footer(format!(
+        "Total: {} ({} hours)",
+        total_duration.format_clock(),
+        total_duration.format_decimal()
+    ));
 
-    let response = client
-        .get::<serde_json::Value>("reports", Some(&filter))
-        .await?;
+    print_view(options.output, &records, &table, &options.view)
+}
 
-    if let Some(reports) = response["data"].as_array() {
-        if reports.is_empty() {
-            if let Some(ref date) = date_param {
-                println!("No reports found for date: {date}");
-            } else if from_param.is_some() || to_param.is_some() {
-                let from_msg = from_param.as_deref().unwrap_or("today");
-                let to_msg = to_param.as_deref().unwrap_or("today");
-                println!("No reports found from {from_msg} to {to_msg}");
-            } else {
-                println!("No reports found for today");
-            }
-            return Ok(());
+/// What to group reports by in `get reports`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportGroup {
+    Day,
+    /// ISO week, like 2024-W10
+    Week,
+    Month,
+    Customer,
+    Project,
+    Task,
+    User,
+}
+
+impl ReportGroup {
+    /// Name of the group as a column and field
+    fn name(self) -> &'static str {
+        match self {
+            ReportGroup::Day => "day",
+            ReportGroup::Week => "week",
+            ReportGroup::Month => "month",
+            ReportGroup::Customer => "customer",
+            ReportGroup::Project => "project",
+            ReportGroup::Task => "task",
+            ReportGroup::User => "user",
         }
+    }
 
-        // Display date range in the header
-        if let Some(ref date) = date_param {
-            println!("Reports for {date}");
-        } else if from_param.is_some() || to_param.is_some() {
-            let from_msg = from_param.as_deref().unwrap_or("today");
-            let to_msg = to_param.as_deref().unwrap_or("today");
-            println!("Reports from {from_msg} to {to_msg}");
-        } else {
-            let today = Local::now().date_naive().format("%Y-%m-%d");
-            println!("Reports for {today}");
+    /// Key the reports of a group share, ending with the label of the group
+    ///
+    /// Projects and tasks are keyed by their whole path, so equally named
+    /// projects of different customers are not merged.
+    fn key(self, record: &ReportRecord) -> Vec<String> {
+        match self {
+            ReportGroup::Day => vec![record.date.format("%Y-%m-%d").to_string()],
+            ReportGroup::Week => vec![record.date.format("%G-W%V").to_string()],
+            ReportGroup::Month => vec![record.date.format("%Y-%m").to_string()],
+            ReportGroup::Customer => vec![record.customer.clone()],
+            ReportGroup::Project => vec![record.customer.clone(), record.project.clone()],
+            ReportGroup::Task => vec![
+                record.customer.clone(),
+                record.project.clone(),
+                record.task.clone(),
+            ],
+            ReportGroup::User => vec![record.user.clone().unwrap_or_else(|| "Unknown".to_string())],
         }
-        println!("----------------------------------------");
+    }
+}
 
-        let mut total_duration = 0.0;
+/// Billable and not billable time of a set of reports
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Subtotal {
+    count: usize,
+    billable: TimedDuration,
+    not_billable: TimedDuration,
+}
 
-        for report in reports {
-            let comment = report["attributes"]["comment"]
-                .as_str()
-                .unwrap_or("No comment");
-            let duration = report["attributes"]["duration"]
-                .as_str()
-                .unwrap_or("00:00:00");
-            let review = report["attributes"]["review"].as_bool().unwrap_or(false);
-            let not_billable = report["attributes"]["not-billable"]
-                .as_bool()
-                .unwrap_or(false);
-            let verified = report["attributes"]["verified"].as_bool().unwrap_or(false);
-            let rejected = report["attributes"]["rejected"].as_bool().unwrap_or(false);
-
-            // Parse duration
-            let parts: Vec<&str> = duration.split(':').collect();
-            let hours: f64 = if parts.len() >= 2 {
-                parts[0].parse::<f64>().unwrap_or(0.0)
-                    + parts[1].parse::<f64>().unwrap_or(0.0) / 60.0
+impl Subtotal {
+    fn of(rows: &[(TimedDuration, &ReportRecord)]) -> Self {
+        let mut subtotal = Self::default();
+        for (duration, record) in rows {
+            subtotal.count += 1;
+            if record.not_billable {
+                subtotal.not_billable += *duration;
             } else {
-                0.0
-            };
+                subtotal.billable += *duration;
+            }
+        }
+        subtotal
+    }
 
-            total_duration += hours;
+    fn total(&self) -> TimedDuration {
+        self.billable + self.not_billable
+    }
 
-            // Get task/project/customer info
-            let task_id = report["relationships"]["task"]["data"]["id"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Invalid task reference"))?;
+    /// Fields of the structured output of a group
+    fn fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        fields.insert("reports".to_string(), json!(self.count));
+        fields.insert("duration".to_string(), json!(self.total().to_string()));
+        fields.insert("hours".to_string(), json!(hours(self.total())));
+        fields.insert("billable_hours".to_string(), json!(hours(self.billable)));
+        fields.insert(
+            "not_billable_hours".to_string(),
+            json!(hours(self.not_billable)),
+        );
+        fields
+    }
+}
 
-            let mut task_name = "Unknown Task";
-            let mut project_name = "Unknown Project";
-            let mut customer_name = "Unknown Customer";
+/// Reports sharing the key of one grouping level
+#[derive(Debug)]
+struct ReportGroupNode<'a> {
+    label: String,
+    subtotal: Subtotal,
+    /// Groups of the next level, empty on the last level
+    children: Vec<ReportGroupNode<'a>>,
+    rows: Vec<(TimedDuration, &'a ReportRecord)>,
+}
 
-            if let Some(included) = response["included"].as_array() {
-                // Find task
-                if let Some(task) = included
-                    .iter()
-                    .find(|item| item["type"] == "tasks" && item["id"] == task_id)
-                {
-                    task_name = task["attributes"]["name"].as_str().unwrap_or(task_name);
+/// Group reports by each level in turn, ordered by their keys
+fn group_reports<'a>(
+    rows: &[(TimedDuration, &'a ReportRecord)],
+    levels: &[ReportGroup],
+) -> Vec<ReportGroupNode<'a>> {
+    let Some((level, rest)) = levels.split_first() else {
+        return Vec::new();
+    };
 
-                    // Find project
-                    if let Some(project_id) =
-                        task["relationships"]["project"]["data"]["id"].as_str()
-                    {
-                        if let Some(project) = included
-                            .iter()
-                            .find(|item| item["type"] == "projects" && item["id"] == project_id)
-                        {
-                            project_name = project["attributes"]["name"]
-                                .as_str()
-                                .unwrap_or(project_name);
+    let mut groups: BTreeMap<Vec<String>, Vec<(TimedDuration, &ReportRecord)>> = BTreeMap::new();
+    for row in rows {
+        groups.entry(level.key(row.1)).or_default().push(*row);
+    }
 
-                            // Find customer
-                            if let Some(customer_id) =
-                                project["relationships"]["customer"]["data"]["id"].as_str()
-                            {
-                                if let Some(customer) = included.iter().find(|item| {
-                                    item["type"] == "customers" && item["id"] == customer_id
-                                }) {
-                                    customer_name = customer["attributes"]["name"]
-                                        .as_str()
-                                        .unwrap_or(customer_name);
-                                }
-                            }
-                        }
+    groups
+        .into_iter()
+        .map(|(key, rows)| ReportGroupNode {
+            label: key.last().cloned().unwrap_or_default(),
+            subtotal: Subtotal::of(&rows),
+            children: group_reports(&rows, rest),
+            rows,
+        })
+        .collect()
+}
+
+/// Group reports with subtotals, a grand total and the billable split
+///
+/// The table lists each group with its subtotal above its reports. Structured
+/// formats get one record per group instead, followed by the grand total
+/// with all group fields set to `null`.
+fn grouped_reports(
+    levels: &[ReportGroup],
+    all_users: bool,
+    period: &str,
+    rows: &[(TimedDuration, &ReportRecord)],
+) -> (Vec<Map<String, Value>>, Table) {
+    let groups = group_reports(rows, levels);
+    let total = Subtotal::of(rows);
+
+    let mut header: Vec<String> = levels.iter().map(|l| capitalize(l.name())).collect();
+    header.push("Duration".to_string());
+    if all_users {
+        header.push("User".to_string());
+    }
+    header.extend(["Customer", "Project", "Task", "Comment", "Flags"].map(String::from));
+    let mut table = Table::new(header)
+        .title(format!("Reports {period}"))
+        .empty(format!("No reports found {period}"));
+    let mut records = Vec::new();
+
+    fn add<'a>(
+        nodes: &[ReportGroupNode<'a>],
+        path: &mut Vec<String>,
+        levels: &[ReportGroup],
+        all_users: bool,
+        table: &mut Table,
+        records: &mut Vec<Map<String, Value>>,
+    ) {
+        for node in nodes {
+            let depth = path.len();
+            path.push(node.label.clone());
+
+            let mut cells = vec![String::new(); levels.len()];
+            cells[depth] = node.label.clone();
+            cells.push(node.subtotal.total().format_clock());
+            table.row(cells);
+
+            let mut record: Map<String, Value> = levels
+                .iter()
+                .enumerate()
+                .map(|(i, level)| (level.name().to_string(), json!(path.get(i))))
+                .collect();
+            record.extend(node.subtotal.fields());
+            records.push(record);
+
+            if node.children.is_empty() {
+                for (duration, report) in &node.rows {
+                    let mut cells = vec![String::new(); levels.len()];
+                    cells.push(duration.format_clock());
+                    if all_users {
+                        cells.push(report.user.clone().unwrap_or_else(|| "Unknown".to_string()));
                     }
+                    cells.extend([
+                        report.customer.clone(),
+                        report.project.clone(),
+                        report.task.clone(),
+                        report.comment.clone(),
+                        report.flags().join(", "),
+                    ]);
+                    table.row(cells);
                 }
+            } else {
+                add(&node.children, path, levels, all_users, table, records);
             }
+            path.pop();
+        }
+    }
+    add(
+        &groups,
+        &mut Vec::new(),
+        levels,
+        all_users,
+        &mut table,
+        &mut records,
+    );
 
-            let mut prefix = "".to_string();
-            let mut flags = Vec::new();
-
-            if review {
-                flags.push("REVIEW");
-            }
-            if not_billable {
-                flags.push("NOT-BILLABLE");
-            }
-            if verified {
-                flags.push("VERIFIED");
-            }
-            if rejected {
-                flags.push("REJECTED");
-            }
+    if !records.is_empty() {
+        let mut record: Map<String, Value> = levels
+            .iter()
+            .map(|level| (level.name().to_string(), Value::Null))
+            .collect();
+        record.extend(total.fields());
+        records.push(record);
+    }
 
-            let _flags_str = if !flags.is_empty() {
-                format!(" [{}]", flags.join(", "))
-            } else {
-                "".to_string()
-            };
-            let mut flags = Vec::new();
+    for (label, duration) in [
+        ("Total", total.total()),
+        ("Billable", total.billable),
+        ("Not billable", total.not_billable),
+    ] {
+        table.footer(format!(
+            "{label}: {} ({} hours)",
+            duration.format_clock(),
+            duration.format_decimal()
+        ));
+    }
 
-            if review {
-                flags.push("REVIEW");
-            }
-            if not_billable {
-                flags.push("NOT-BILLABLE");
-            }
-            if verified {
-                flags.push("VERIFIED");
-            }
-            if rejected {
-                flags.push("REJECTED");
-            }
+    (records, table)
+}
 
-            let _flags_str = if !flags.is_empty() {
-                format!(" [{}]", flags.join(", "))
-            } else {
-                "".to_string()
-            };
+/// A report as shown by `get reports` in structured output formats
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRecord {
+    pub id: Option<String>,
+    pub date: NaiveDate,
+    pub user: Option<String>,
+    pub customer: String,
+    pub project: String,
+    pub task: String,
+    /// Duration as `HH:MM:SS`
+    pub duration: String,
+    /// Duration in decimal hours
+    pub hours: f64,
+    pub comment: String,
+    pub review: bool,
+    pub not_billable: bool,
+    pub verified: bool,
+    pub rejected: bool,
+    pub billed: bool,
+}
 
-            // Show username if all_users flag is set
-            if options.all_users {
-                // Get user info
-                let user_id = report["relationships"]["user"]["data"]["id"]
-                    .as_str()
-                    .unwrap_or("");
-                let mut username = "Unknown";
+impl ReportRecord {
+    /// Fields that can be selected with `--columns` or used in `--template`
+    pub const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "date",
+        "user",
+        "customer",
+        "project",
+        "task",
+        "duration",
+        "hours",
+        "comment",
+        "review",
+        "not_billable",
+        "verified",
+        "rejected",
+        "billed",
+    ];
+
+    /// Build a record, taking names from the included resources of the response
+    pub fn new(response: &ResourcesResponse<Report>, report: &Report) -> Self {
+        let attributes = &report.attributes;
+        let duration = TimedDuration::from(attributes.duration);
+        let task_id = report
+            .relationships
+            .task
+            .as_ref()
+            .and_then(|t| t.id())
+            .unwrap_or_default();
+        let TaskPath {
+            customer,
+            project,
+            task,
+        } = TaskPath::resolve(response, task_id);
+
+        Self {
+            id: report.id.clone(),
+            date: attributes.date,
+            user: report
+                .relationships
+                .user
+                .as_ref()
+                .and_then(|u| u.id())
+                .and_then(|id| resolve_username(response, id)),
+            customer,
+            project,
+            task,
+            duration: duration.to_string(),
+            hours: hours(duration),
+            comment: attributes.comment.clone(),
+            review: attributes.review,
+            not_billable: attributes.not_billable,
+            verified: attributes.verified.unwrap_or(false),
+            rejected: attributes.rejected.unwrap_or(false),
+            billed: attributes.billed.unwrap_or(false),
+        }
+    }
 
-                if let Some(included) = response["included"].as_array() {
-                    if let Some(user) = included
-                        .iter()
-                        .find(|i| i["type"] == "users" && i["id"] == user_id)
-                    {
-                        username = user["attributes"]["username"].as_str().unwrap_or(username);
-                    }
-                }
-                prefix = format!("[{username}] ");
-            }
+    /// Names of the flags set on the report
+    pub fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.review, "REVIEW"),
+            (self.not_billable, "NOT-BILLABLE"),
+            (self.verified, "VERIFIED"),
+            (self.rejected, "REJECTED"),
+            (self.billed, "BILLED"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
 
-            println!(
-                "{prefix}{duration} - {customer_name} / {project_name} / {task_name} - {comment}"
-            );
-        }
+impl Sortable for ReportRecord {
+    fn when(&self) -> (NaiveDate, Option<NaiveTime>) {
+        (self.date, None)
+    }
 
-        println!("----------------------------------------");
-        println!("Total: {total_duration:.2} hours");
-        return Ok(());
+    fn hours(&self) -> f64 {
+        self.hours
     }
 
-    // If we get here, no reports were found
-    if let Some(ref date) = date_param {
-        println!("No reports found for date: {date}");
-    } else if from_param.is_some() || to_param.is_some() {
-        let from_msg = from_param.as_deref().unwrap_or("today");
-        let to_msg = to_param.as_deref().unwrap_or("today");
-        println!("No reports found from {from_msg} to {to_msg}");
-    } else {
-        println!("No reports found for today");
+    fn task_path(&self) -> [&str; 3] {
+        [&self.customer, &self.project, &self.task]
     }
-    Ok(())
 }
 
 /// Determine date parameters for report queries
@@ -303,24 +551,28 @@ async fn determine_date_parameters(
     options: &GetReportsOptions,
 ) -> Result<(Option<String>, Option<String>, Option<String>)> {
     // If interactive mode is explicitly requested and no dates are provided, show interactive prompt
-    if options.interactive
+    let (date, from, to) = if options.interactive
         && options.date.is_none()
         && options.from_date.is_none()
         && options.to_date.is_none()
     {
-        interactive_date_selection().await
+        interactive_date_selection().await?
     } else if options.date.is_none() && options.from_date.is_none() && options.to_date.is_none() {
         // Default to current date when no date parameters are provided
         let today = Local::now().format("%Y-%m-%d").to_string();
-        Ok((Some(today), None, None))
+        (Some(today), None, None)
     } else {
         // Use provided date parameters
-        Ok((
+        (
             options.date.clone(),
             options.from_date.clone(),
             options.to_date.clone(),
-        ))
-    }
+        )
+    };
+
+    // Relative dates like yesterday are turned into YYYY-MM-DD
+    let normalize = |date: Option<String>| date.as_deref().map(date_param).transpose();
+    Ok((normalize(date)?, normalize(from)?, normalize(to)?))
 }
 
 /// Interactive date selection dialog
@@ -360,198 +612,462 @@ async fn interactive_date_selection() -> Result<(Option<String>, Option<String>,
     }
 }
 
-/// Get intersection of common values among reports that match a filter
-#[allow(dead_code)]
-pub async fn get_report_intersection(
-    client: &TimedClient,
-    filter_params: FilterParams,
-) -> Result<()> {
-    // Add the intersection endpoint to the filter
-    let endpoint = "reports/intersection";
-
-    // Add relationship includes if not already set
-    let mut filter_with_includes = filter_params.clone();
-    if filter_with_includes.include.is_none() {
-        filter_with_includes.include =
-            Some("task,task.project,task.project.customer,user".to_string());
-    }
-
-    // Make the request
-    let response = client
-        .get::<serde_json::Value>(endpoint, Some(&filter_with_includes))
-        .await?;
+/// Options for updating all reports that match a filter
+#[derive(Debug, Clone, Default)]
+pub struct BulkEditOptions {
+    pub date: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub user: Option<String>,
+    pub all_users: bool,
+    pub customer: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    /// Only select reports with this review flag
+    pub in_review: Option<bool>,
+    pub set_task: Option<String>,
+    pub review: Option<bool>,
+    pub not_billable: Option<bool>,
+    pub verified: Option<bool>,
+    pub comment: Option<String>,
+    /// Apply the changes without asking
+    pub yes: bool,
+    /// Only show which reports would change
+    pub dry_run: bool,
+}
 
-    // Parse and display the intersection data
-    if let Some(data) = response.get("data") {
-        let map = serde_json::Map::new();
-        let default_value = serde_json::Value::Object(map);
-        let attributes = data.get("attributes").unwrap_or(&default_value);
+impl BulkEditOptions {
+    fn has_changes(&self) -> bool {
+        self.set_task.is_some()
+            || self.review.is_some()
+            || self.not_billable.is_some()
+            || self.verified.is_some()
+            || self.comment.is_some()
+    }
 
-        println!("Common values across matching reports:");
-        println!("----------------------------------------");
+    /// Build the filter selecting the reports to update
+    async fn filter(&self, client: &TimedClient) -> Result<FilterParams> {
+        let mut filter = FilterParams::default();
 
-        // Check for task
-        if let Some(task) = attributes.get("task") {
-            if !task.is_null() {
-                if let Some(task_id) = task.get("id").and_then(|id| id.as_str()) {
-                    println!("Task: {task_id}");
-                }
-            } else {
-                println!("Task: <varies>");
-            }
+        if let Some(date) = &self.date {
+            filter.date = Some(date_param(date)?);
+        }
+        if let Some(from) = &self.from_date {
+            filter.from_date = Some(date_param(from)?);
+        }
+        if let Some(to) = &self.to_date {
+            filter.to_date = Some(date_param(to)?);
+        }
+        filter.review = self.in_review.map(i32::from);
+
+        ResolvedFilters::resolve(
+            client,
+            self.customer.as_deref(),
+            self.project.as_deref(),
+            self.task.as_deref(),
+            self.user.as_deref(),
+        )
+        .await?
+        .apply(&mut filter);
+
+        if self.user.is_none()
+            && filter.date.is_none()
+            && filter.from_date.is_none()
+            && filter.to_date.is_none()
+            && filter.customer.is_none()
+            && filter.project.is_none()
+            && filter.task.is_none()
+            && filter.review.is_none()
+        {
+            return Err(anyhow::anyhow!(
+                "Refusing to update every report, narrow it down with a date, user, customer, project, task or review filter"
+            ));
         }
 
-        // Check for review flag
-        if let Some(review) = attributes.get("review") {
-            if !review.is_null() {
-                println!("Review: {}", review.as_bool().unwrap_or(false));
-            } else {
-                println!("Review: <varies>");
-            }
+        // Never touch other users' reports by accident
+        if filter.user.is_none() && !self.all_users {
+            filter.user = Some(get_current_user_id(client).await?);
         }
 
-        // Check for not_billable flag
-        if let Some(not_billable) = attributes.get("not-billable") {
-            if !not_billable.is_null() {
-                println!("Not Billable: {}", not_billable.as_bool().unwrap_or(false));
-            } else {
-                println!("Not Billable: <varies>");
-            }
+        Ok(filter)
+    }
+
+    /// Build the body of a `reports/bulk` request
+    fn bulk_document(&self, task_id: Option<&str>) -> serde_json::Value {
+        let mut attributes = serde_json::Map::new();
+        if let Some(review) = self.review {
+            attributes.insert("review".to_string(), review.into());
+        }
+        if let Some(not_billable) = self.not_billable {
+            attributes.insert("not-billable".to_string(), not_billable.into());
+        }
+        if let Some(verified) = self.verified {
+            attributes.insert("verified".to_string(), verified.into());
+        }
+        if let Some(comment) = &self.comment {
+            attributes.insert("comment".to_string(), comment.clone().into());
         }
 
-        // Check for comment
-        if let Some(comment) = attributes.get("comment") {
-            if !comment.is_null() {
-                println!("Comment: {}", comment.as_str().unwrap_or("<empty>"));
-            } else {
-                println!("Comment: <varies>");
-            }
+        let mut data = serde_json::json!({
+            "type": "report-bulks",
+            "attributes": attributes,
+        });
+        if let Some(task_id) = task_id {
+            data["relationships"] = serde_json::json!({
+                "task": { "data": { "type": "tasks", "id": task_id } }
+            });
         }
-    } else {
-        println!("No intersection data found");
+        serde_json::json!({ "data": data })
     }
-
-    Ok(())
 }
 
-/// Bulk update reports based on a filter
-#[allow(dead_code)]
-pub async fn bulk_update_reports(
+/// Get the values all reports matching a filter have in common
+///
+/// Values that differ between the reports are `null`.
+pub async fn get_report_intersection(
     client: &TimedClient,
-    filter_params: FilterParams,
-    review: Option<bool>,
-    not_billable: Option<bool>,
-    verified: Option<bool>,
-    comment: Option<String>,
-) -> Result<()> {
+    filter_params: &FilterParams,
+) -> Result<serde_json::Value> {
+    let mut filter = filter_params.clone();
+    filter.include = Some("customer,project,task,user".to_string());
+
+    Ok(client
+        .get::<serde_json::Value>("reports/intersection", Some(&filter))
+        .await?)
+}
+
+/// Describe an intersection as label and value pairs, using `<varies>` for differing values
+fn intersection_lines(document: &serde_json::Value) -> Vec<(&'static str, String)> {
+    let data = &document["data"];
+    let included = document["included"].as_array();
+
+    let related = |name: &str, attribute: &str| {
+        let target = &data["relationships"][name]["data"];
+        let Some(id) = target["id"].as_str() else {
+            return "<varies>".to_string();
+        };
+        included
+            .and_then(|included| {
+                included
+                    .iter()
+                    .find(|inc| inc["type"] == target["type"] && inc["id"] == id)
+            })
+            .and_then(|inc| inc["attributes"][attribute].as_str())
+            .unwrap_or(id)
+            .to_string()
+    };
+    let attribute = |name: &str| match &data["attributes"][name] {
+        serde_json::Value::Null => "<varies>".to_string(),
+        serde_json::Value::String(text) if text.is_empty() => "<empty>".to_string(),
+        serde_json::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+
+    vec![
+        ("Customer", related("customer", "name")),
+        ("Project", related("project", "name")),
+        ("Task", related("task", "name")),
+        ("User", related("user", "username")),
+        ("Comment", attribute("comment")),
+        ("Review", attribute("review")),
+        ("Not Billable", attribute("not-billable")),
+        ("Verified", attribute("verified")),
+    ]
+}
+
+/// Update all reports that match a filter at once
+///
+/// Shows what the reports have in common first and asks before applying the
+/// changes, unless `yes` is set. With `dry_run` the affected reports are only listed.
+pub async fn bulk_update_reports(client: &TimedClient, options: &BulkEditOptions) -> Result<usize> {
     use dialoguer::{theme::ColorfulTheme, Confirm};
-    // Add relationship includes if not already set
-    let mut filter_with_includes = filter_params.clone();
-    if filter_with_includes.include.is_none() {
-        filter_with_includes.include =
-            Some("task,task.project,task.project.customer,user".to_string());
+
+    if !options.has_changes() {
+        return Err(anyhow::anyhow!(
+            "Nothing to change, use --set-task, --review, --not-billable, --verified or --comment"
+        ));
     }
 
-    // First, get the reports that match the filter
-    let response = client
-        .get::<serde_json::Value>("reports", Some(&filter_with_includes))
-        .await?;
+    // Step 1: Select the reports
+    let mut filter = options.filter(client).await?;
+    let task_id = match &options.set_task {
+        Some(task) => Some(super::lookup::resolve_task_path(client, task, true).await?),
+        None => None,
+    };
 
-    let report_count = response["data"].as_array().map_or(0, |a| a.len());
+    filter.include = Some("task,task.project,task.project.customer,user".to_string());
+    let response = client.list_all::<Report>(Some(&filter)).await?;
+    filter.include = None;
 
+    let report_count = response.data.len();
     if report_count == 0 {
         println!("No reports found matching the filter criteria");
-        return Ok(());
+        return Ok(0);
     }
 
+    // Step 2: Show what they have in common
+    let intersection = get_report_intersection(client, &filter).await?;
     println!("Found {report_count} reports matching the filter criteria");
+    println!("Common values across matching reports:");
+    println!("----------------------------------------");
+    for (label, value) in intersection_lines(&intersection) {
+        println!("{label}: {value}");
+    }
 
-    // Confirm the bulk update
-    let confirm = Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!(
-            "Are you sure you want to update {report_count} reports?"
-        ))
-        .default(false)
-        .interact()?;
-
-    if !confirm {
-        println!("Bulk update canceled");
-        return Ok(());
+    if options.dry_run {
+        println!("----------------------------------------");
+        for report in &response.data {
+            let task_id = report
+                .relationships
+                .task
+                .as_ref()
+                .and_then(|t| t.id())
+                .unwrap_or_default();
+            let path = TaskPath::resolve(&response, task_id);
+            println!(
+                "{} | {} | {} > {} > {} | {}",
+                report.attributes.date,
+                format_duration(report.attributes.duration),
+                path.customer,
+                path.project,
+                path.task,
+                report.attributes.comment
+            );
+        }
+        println!("Dry run, {report_count} reports would be updated");
+        return Ok(0);
     }
 
-    // Create the bulk update request
-    let bulk_request = serde_json::json!({
-        "review": review,
-        "not-billable": not_billable,
-        "verified": verified,
-        "comment": comment
-    });
+    // Step 3: Confirm and apply
+    if !options.yes {
+        let confirm = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Are you sure you want to update {report_count} reports?"
+            ))
+            .default(false)
+            .interact()?;
+
+        if !confirm {
+            println!("Bulk update canceled");
+            return Ok(0);
+        }
+    }
 
-    // Make the request
-    let _response = client
-        .post::<_, serde_json::Value>("reports/bulk", &bulk_request)
+    client
+        .post_response(
+            "reports/bulk",
+            Some(&filter),
+            &options.bulk_document(task_id.as_deref()),
+        )
         .await?;
 
-    println!("Successfully updated reports");
+    println!("Successfully updated {report_count} reports");
 
-    Ok(())
+    Ok(report_count)
 }
 
-/// Export reports based on a filter
-#[allow(dead_code)]
-pub async fn export_reports(
-    client: &TimedClient,
-    filter_params: FilterParams,
-    file_type: &str,
-    output_path: &std::path::Path,
-) -> Result<()> {
+/// Options for exporting reports to a file
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub user: Option<String>,
+    pub customer: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub file_type: String,
+    /// File or directory to write to, defaults to the name the server suggests
+    pub output: Option<PathBuf>,
+}
+
+/// Export reports to a CSV, XLSX or ODS file rendered by the server
+///
+/// The response is streamed to disk, so large exports never have to fit in memory.
+pub async fn export_reports(client: &TimedClient, options: &ExportOptions) -> Result<PathBuf> {
     // Validate file type
+    let file_type = options.file_type.as_str();
     if !["csv", "xlsx", "ods"].contains(&file_type) {
         return Err(anyhow::anyhow!(
             "Invalid file type. Must be one of: csv, xlsx, ods"
         ));
     }
 
-    // Add file_type to the filter
-    let mut params = filter_params.clone();
+    let mut params = FilterParams::default();
+    if let Some(from) = &options.from_date {
+        params.from_date = Some(date_param(from)?);
+    }
+    if let Some(to) = &options.to_date {
+        params.to_date = Some(date_param(to)?);
+    }
+    ResolvedFilters::resolve(
+        client,
+        options.customer.as_deref(),
+        options.project.as_deref(),
+        options.task.as_deref(),
+        options.user.as_deref(),
+    )
+    .await?
+    .apply(&mut params);
     params
         .custom
         .insert("file_type".to_string(), file_type.to_string());
 
-    // Use a custom call to get the raw bytes
-    let endpoint = "reports/export";
     debug!("Exporting reports to {} format", file_type);
 
-    // Special handling for export endpoint - custom headers and response type
-    let url = format!("{}{}", client.base_url(), endpoint);
+    // The export endpoint returns a file instead of JSON
+    let mut response = client.get_response("reports/export", Some(&params)).await?;
+
+    let suggested = response
+        .headers()
+        .get(CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(content_disposition_filename)
+        .unwrap_or_else(|| format!("reports.{file_type}"));
+    let output_path = match &options.output {
+        Some(path) if path.is_dir() => path.join(&suggested),
+        Some(path) => path.clone(),
+        None => PathBuf::from(&suggested),
+    };
+
+    // Write to a partial file first so a failed download leaves nothing behind
+    let partial_path = output_path.with_extension(format!("{file_type}.part"));
+    let mut file = tokio::fs::File::create(&partial_path).await?;
+    let mut progress = DownloadProgress::new(response.content_length());
 
-    let token = match client.token() {
-        Some(t) => t,
-        None => return Err(anyhow::anyhow!("No authentication token")),
+    let download = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            progress.advance(chunk.len());
+        }
+        file.flush().await?;
+        anyhow::Ok(())
     };
+    let result = download.await;
+    progress.finish();
 
-    let response = client
-        .http_client()
-        .get(url)
-        .header("Authorization", format!("Bearer {token}"))
-        .query(&params)
-        .send()
-        .await?;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&partial_path, &output_path).await?;
+
+    println!(
+        "Exported reports to {} ({})",
+        output_path.display(),
+        format_bytes(progress.written)
+    );
+
+    Ok(output_path)
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await?;
-        return Err(anyhow::anyhow!("API error ({}): {}", status, text));
+/// Get the file name from a `Content-Disposition` header
+///
+/// Directories are stripped so a server can't write outside the target directory.
+fn content_disposition_filename(header: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+
+    for part in header.split(';').map(str::trim) {
+        let Some((key, value)) = part.split_once('=') else {
+            continue;
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            // RFC 5987 form, like filename*=UTF-8''report%20march.xlsx
+            "filename*" => {
+                extended = value
+                    .split_once("''")
+                    .map(|(_, encoded)| percent_decode(encoded));
+            }
+            "filename" => plain = Some(value.trim().trim_matches('"').to_string()),
+            _ => {}
+        }
     }
 
-    let bytes = response.bytes().await?;
+    extended
+        .or(plain)
+        .and_then(|name| {
+            Path::new(&name.replace('\\', "/"))
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .filter(|name| !name.is_empty())
+}
 
-    // Write to file
-    std::fs::write(output_path, bytes)?;
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
 
-    println!("Exported reports to {}", output_path.display());
+/// Progress of a download, shown on a single line of stderr
+struct DownloadProgress {
+    total: Option<u64>,
+    written: u64,
+    visible: bool,
+}
 
-    Ok(())
+impl DownloadProgress {
+    fn new(total: Option<u64>) -> Self {
+        Self {
+            total,
+            written: 0,
+            visible: std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.written += bytes as u64;
+        if !self.visible {
+            return;
+        }
+        match self.total {
+            Some(total) if total > 0 => eprint!(
+                "\rDownloading... {} of {} ({}%)",
+                format_bytes(self.written),
+                format_bytes(total),
+                self.written * 100 / total
+            ),
+            _ => eprint!("\rDownloading... {}", format_bytes(self.written)),
+        }
+    }
+
+    fn finish(&self) {
+        if self.visible && self.written > 0 {
+            eprintln!();
+        }
+    }
+}
+
+/// Format a number of bytes like `512 B` or `1.5 MB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
 }
 
 /// Add a new report
@@ -568,6 +1084,7 @@ pub async fn add_report(
     review: bool,
     not_billable: bool,
     interactive: bool,
+    rounding: &RoundingConfig,
 ) -> Result<()> {
     let options = AddReportOptions {
         customer: customer.map(String::from),
@@ -580,15 +1097,16 @@ pub async fn add_report(
         review,
         not_billable,
         interactive,
+        rounding: rounding.clone(),
     };
 
     add_report_with_options(client, options).await
 }
 
 async fn add_report_with_options(client: &TimedClient, options: AddReportOptions) -> Result<()> {
-    let task_id = get_task_for_report(client, &options).await?;
+    let (task_id, path) = get_task_for_report(client, &options).await?;
     let comment = get_description_for_report(&options)?;
-    let duration_str = get_duration_for_report(&options)?;
+    let duration_str = get_duration_for_report(&options, &path)?;
 
     let report_date = if let Some(date_str) = &options.date {
         parse_date(Some(date_str))?
@@ -632,11 +1150,20 @@ async fn add_report_with_options(client: &TimedClient, options: AddReportOptions
     Ok(())
 }
 
-async fn get_task_for_report(client: &TimedClient, options: &AddReportOptions) -> Result<String> {
+async fn get_task_for_report(
+    client: &TimedClient,
+    options: &AddReportOptions,
+) -> Result<(String, TaskPath)> {
     if let (Some(customer), Some(project), Some(task)) =
         (&options.customer, &options.project, &options.task)
     {
-        get_task_id(client, customer, project, task, options.show_archived).await
+        let task_id = get_task_id(client, customer, project, task, options.show_archived).await?;
+        let path = TaskPath {
+            customer: customer.clone(),
+            project: project.clone(),
+            task: task.clone(),
+        };
+        Ok((task_id, path))
     } else if options.interactive {
         interactive_select_task(client, options.show_archived).await
     } else {
@@ -661,74 +1188,46 @@ fn get_description_for_report(options: &AddReportOptions) -> Result<String> {
     }
 }
 
-fn get_duration_for_report(options: &AddReportOptions) -> Result<String> {
-    if let Some(dur) = &options.duration {
-        round_duration_to_15min(dur)
+fn get_duration_for_report(options: &AddReportOptions, path: &TaskPath) -> Result<String> {
+    let duration = if let Some(dur) = &options.duration {
+        parse_duration_input(dur)?
     } else if options.interactive {
-        interactive_duration_input()
+        interactive_duration_input()?
     } else {
-        Err(anyhow::anyhow!(
+        return Err(anyhow::anyhow!(
             "Duration required. Provide --duration parameter or use interactive mode"
-        ))
+        ));
+    };
+
+    let rounding = options.rounding.for_task(&path.customer, &path.project);
+    Ok(round_duration(rounding, duration)?.to_string())
+}
+
+/// Round an entered duration, refusing to round it away to an empty report
+fn round_duration(rounding: Rounding, duration: TimedDuration) -> Result<TimedDuration> {
+    let rounded = rounding.apply(duration);
+    if rounded <= TimedDuration::ZERO {
+        return Err(anyhow::anyhow!(
+            "{} rounds to zero with {}-minute rounding. Use --no-round to keep it",
+            duration.format_clock(),
+            rounding.increment_minutes
+        ));
     }
+    Ok(rounded)
 }
 
-fn interactive_duration_input() -> Result<String> {
-    let input_duration = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Duration (format: HH:MM or decimal hours)")
-        .default("01:00".to_string())
-        .validate_with(|input: &String| -> Result<(), &str> {
-            if input.contains(':') {
-                let parts: Vec<&str> = input.split(':').collect();
-                if parts.len() != 2 {
-                    return Err("Duration must be in HH:MM format");
-                }
-                if let Ok(mins) = parts[1].parse::<u32>() {
-                    if mins % 15 != 0 {
-                        return Err("Minutes must be in 15-minute increments (00, 15, 30, 45)");
-                    }
-                } else {
-                    return Err("Invalid minutes value");
-                }
-            }
-            Ok(())
+fn interactive_duration_input() -> Result<TimedDuration> {
+    let input_duration: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Duration (like 1h30m, 1.5, 1:30 or 09:00-10:30)")
+        .default("1h".to_string())
+        .validate_with(|input: &String| -> Result<(), String> {
+            parse_duration_input(input)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
         })
         .interact_text()?;
 
-    round_duration_to_15min(&input_duration)
-}
-
-fn round_duration_to_15min(duration_str: &str) -> Result<String> {
-    if duration_str.contains(':') {
-        let parts: Vec<&str> = duration_str.split(':').collect();
-
-        let hours = parts[0]
-            .parse::<u32>()
-            .map_err(|_| anyhow::anyhow!("Invalid hours value"))?;
-
-        let minutes = if parts.len() > 1 {
-            parts[1]
-                .parse::<u32>()
-                .map_err(|_| anyhow::anyhow!("Invalid minutes value"))?
-        } else {
-            0
-        };
-
-        let rounded_minutes = ((minutes as f32 / 15.0).round() * 15.0) as u32;
-        let adjusted_hours = hours + (rounded_minutes / 60);
-        let adjusted_minutes = rounded_minutes % 60;
-
-        Ok(format!("{adjusted_hours:02}:{adjusted_minutes:02}:00"))
-    } else if let Ok(decimal_hours) = duration_str.parse::<f32>() {
-        let total_minutes = (decimal_hours * 60.0).round() as u32;
-        let rounded_minutes = ((total_minutes as f32 / 15.0).round() * 15.0) as u32;
-        let hours = rounded_minutes / 60;
-        let minutes = rounded_minutes % 60;
-
-        Ok(format!("{hours:02}:{minutes:02}:00"))
-    } else {
-        Err(anyhow::anyhow!("Invalid duration format"))
-    }
+    parse_duration_input(&input_duration)
 }
 
 /// Delete a report
@@ -767,12 +1266,8 @@ pub async fn delete_report(
 
     // Add user filter unless all_users flag is set
     if !all_users {
-        // Get current user ID from /users/me endpoint
-        let current_user_response = client.get::<serde_json::Value>("users/me", None).await?;
-
-        if let Some(user_id) = current_user_response["data"]["id"].as_str() {
-            filter.user = Some(user_id.to_string());
-        }
+        let user_id = get_current_user_id(client).await?;
+        filter.user = Some(user_id);
     }
 
     let response = client
@@ -797,7 +1292,8 @@ pub async fn delete_report(
                 .unwrap_or("No comment");
             let duration = report["attributes"]["duration"]
                 .as_str()
-                .unwrap_or("00:00:00");
+                .and_then(|d| d.parse::<TimedDuration>().ok())
+                .unwrap_or_default();
 
             // Get task/project/customer info
             let task_id = report["relationships"]["task"]["data"]["id"]
@@ -890,7 +1386,8 @@ pub async fn delete_report(
                     .unwrap_or("No comment");
                 let duration = report["attributes"]["duration"]
                     .as_str()
-                    .unwrap_or("00:00:00");
+                    .and_then(|d| d.parse::<TimedDuration>().ok())
+                    .unwrap_or_default();
                 let task_id = report["relationships"]["task"]["data"]["id"]
                     .as_str()
                     .unwrap_or("");
@@ -1004,6 +1501,7 @@ pub async fn edit_report(
     client: &TimedClient,
     date_str: Option<&str>,
     interactive: bool,
+    rounding: &RoundingConfig,
 ) -> Result<()> {
     use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect, Input};
 
@@ -1047,13 +1545,15 @@ pub async fn edit_report(
         let (selection, comment, duration, review, not_billable) = if interactive {
             // Prepare report options for selection
             let mut report_options = Vec::new();
+            let mut report_roundings = Vec::new();
             for report in reports.iter() {
                 let comment = report["attributes"]["comment"]
                     .as_str()
                     .unwrap_or("No comment");
                 let duration = report["attributes"]["duration"]
                     .as_str()
-                    .unwrap_or("00:00:00");
+                    .and_then(|d| d.parse::<TimedDuration>().ok())
+                    .unwrap_or_default();
                 let task_id = report["relationships"]["task"]["data"]["id"]
                     .as_str()
                     .unwrap_or("");
@@ -1102,6 +1602,7 @@ pub async fn edit_report(
                 report_options.push(format!(
                     "{duration} - {customer_name} / {project_name} / {task_name} - {comment}"
                 ));
+                report_roundings.push(rounding.for_task(customer_name, project_name));
             }
 
             // Interactive selection of report to edit
@@ -1116,7 +1617,8 @@ pub async fn edit_report(
             let current_comment = selected["attributes"]["comment"].as_str().unwrap_or("");
             let current_duration = selected["attributes"]["duration"]
                 .as_str()
-                .unwrap_or("00:00:00");
+                .and_then(|d| d.parse::<TimedDuration>().ok())
+                .unwrap_or_default();
             let current_review = selected["attributes"]["review"].as_bool().unwrap_or(false);
             let current_not_billable = selected["attributes"]["not-billable"]
                 .as_bool()
@@ -1129,17 +1631,23 @@ pub async fn edit_report(
                 .interact_text()?;
 
             // Get new duration using dialoguer
-            let duration = Input::with_theme(&ColorfulTheme::default())
-                .with_prompt("Duration (HH:MM:SS)")
-                .default(current_duration.to_string())
-                .validate_with(|input: &String| -> Result<(), &str> {
-                    if input.matches(':').count() == 2 {
-                        Ok(())
-                    } else {
-                        Err("Duration must be in HH:MM:SS format")
-                    }
+            let duration: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Duration (like 1h30m, 1.5, 1:30 or 09:00-10:30)")
+                .default(current_duration.format_clock())
+                .validate_with(|input: &String| -> Result<(), String> {
+                    parse_duration_input(input)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
                 })
                 .interact_text()?;
+            // Only round a changed duration, existing reports stay as they are
+            let entered = parse_duration_input(&duration)?;
+            let duration = if entered == current_duration {
+                entered
+            } else {
+                round_duration(report_roundings[selection], entered)?
+            }
+            .to_string();
 
             // Get review status
             let review = Confirm::with_theme(&ColorfulTheme::default())
@@ -1218,7 +1726,10 @@ pub async fn edit_report(
 }
 
 /// Interactive function to select task
-async fn interactive_select_task(client: &TimedClient, show_archived: bool) -> Result<String> {
+async fn interactive_select_task(
+    client: &TimedClient,
+    show_archived: bool,
+) -> Result<(String, TaskPath)> {
     use dialoguer::{theme::ColorfulTheme, FuzzySelect};
 
     // Get customers
@@ -1251,6 +1762,7 @@ async fn interactive_select_task(client: &TimedClient, show_archived: bool) -> R
             .items(&customer_names)
             .default(0)
             .interact()?;
+        let customer_name = customer_names[selection].clone();
 
         let customer_id = customers[selection]["id"]
             .as_str()
@@ -1283,6 +1795,7 @@ async fn interactive_select_task(client: &TimedClient, show_archived: bool) -> R
                 .items(&project_names)
                 .default(0)
                 .interact()?;
+            let project_name = project_names[selection].clone();
 
             let project_id = projects[selection]["id"]
                 .as_str()
@@ -1321,7 +1834,12 @@ async fn interactive_select_task(client: &TimedClient, show_archived: bool) -> R
                     .as_str()
                     .ok_or_else(|| anyhow::anyhow!("Invalid task ID"))?;
 
-                return Ok(task_id.to_string());
+                let path = TaskPath {
+                    customer: customer_name,
+                    project: project_name,
+                    task: task_names[selection].clone(),
+                };
+                return Ok((task_id.to_string(), path));
             }
         }
     }
@@ -1405,3 +1923,485 @@ async fn get_task_id(
 
     Err(anyhow::anyhow!("Failed to get task ID"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtimed::fake::{to_one, FakeTimed};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_disposition_filename() {
+        let name = |header| content_disposition_filename(header);
+
+        assert_eq!(
+            name("attachment; filename=\"report.xlsx\""),
+            Some("report.xlsx".to_string())
+        );
+        assert_eq!(
+            name("attachment; filename=plain.csv"),
+            Some("plain.csv".to_string())
+        );
+        assert_eq!(
+            name("attachment; filename=\"fallback.ods\"; filename*=UTF-8''M%C3%A4rz%202024.ods"),
+            Some("März 2024.ods".to_string())
+        );
+        assert_eq!(
+            name("attachment; filename=\"../../etc/passwd\""),
+            Some("passwd".to_string())
+        );
+        assert_eq!(name("attachment"), None);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    /// Reports of the current user on Acme and Globex, plus one of another user
+    fn seed_reports(server: &FakeTimed) -> (String, String) {
+        let development = server.add_task("Acme", "Website", "Development");
+        let meetings = server.add_task("Acme", "Website", "Meetings");
+        let tickets = server.add_task("Globex", "Support", "Tickets");
+        let other = server.insert("users", json!({ "username": "other" }), json!({}));
+
+        for (date, task, comment, user) in [
+            ("2024-03-04", &development, "Landing page", None),
+            ("2024-03-05", &development, "Landing page", None),
+            ("2024-03-05", &tickets, "Tickets", None),
+            ("2024-03-05", &development, "Theirs", Some(&other)),
+        ] {
+            let mut relationships = json!({ "task": to_one("tasks", task) });
+            if let Some(user) = user {
+                relationships["user"] = to_one("users", user);
+            }
+            server.insert(
+                "reports",
+                json!({
+                    "date": date,
+                    "duration": "01:30:00",
+                    "comment": comment,
+                    "review": false,
+                    "not-billable": false,
+                    "verified": false
+                }),
+                relationships,
+            );
+        }
+        (development, meetings)
+    }
+
+    #[tokio::test]
+    async fn test_report_records_sort_and_columns() {
+        let server = FakeTimed::start().await.unwrap();
+        seed_reports(&server);
+        let filter = FilterParams {
+            include: Some("task,task.project,task.project.customer,user".to_string()),
+            ..Default::default()
+        };
+        let response = server
+            .client()
+            .list_all::<Report>(Some(&filter))
+            .await
+            .unwrap();
+        let mut records: Vec<ReportRecord> = response
+            .data
+            .iter()
+            .map(|report| ReportRecord::new(&response, report))
+            .collect();
+
+        let fields: Vec<String> = match serde_json::to_value(&records[0]).unwrap() {
+            serde_json::Value::Object(fields) => fields.keys().cloned().collect(),
+            _ => unreachable!(),
+        };
+        assert_eq!(fields, ReportRecord::COLUMNS);
+
+        records.sort_by(|a, b| ListSort::Task.compare(b, a));
+        let view = ListView {
+            columns: None,
+            template: Some("{date} {hours}h {customer}/{task} ({user})".to_string()),
+        };
+        let lines =
+            crate::output::render_view(OutputFormat::Json, &records, &Table::default(), &view)
+                .unwrap();
+        assert_eq!(
+            lines.lines().collect::<Vec<_>>(),
+            vec![
+                "2024-03-05 1.5h Globex/Tickets (fake)",
+                "2024-03-05 1.5h Acme/Development (fake)",
+                "2024-03-05 1.5h Acme/Development (other)",
+                "2024-03-04 1.5h Acme/Development (fake)",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_reports_filters() {
+        let server = FakeTimed::start().await.unwrap();
+        let (development, _) = seed_reports(&server);
+        let client = server.client();
+        let options = GetReportsOptions {
+            from_date: Some("2024-03-01".to_string()),
+            to_date: Some("2024-03-31".to_string()),
+            project: Some("web".to_string()),
+            task: Some("devel".to_string()),
+            review: Some(false),
+            verified: Some(false),
+            comment_contains: Some("LANDING".to_string()),
+            ..Default::default()
+        };
+        get_reports(&client, &options).await.unwrap();
+
+        let request = server
+            .requests()
+            .into_iter()
+            .rfind(|r| r.starts_with("GET /api/v1/reports"))
+            .unwrap();
+        for param in [
+            format!("task={development}"),
+            "review=0".to_string(),
+            "verified=0".to_string(),
+            "comment=LANDING".to_string(),
+        ] {
+            assert!(request.contains(&param), "{param} missing in {request}");
+        }
+
+        // The fake matches comments case-insensitively, like Timed
+        let mut filter = FilterParams {
+            comment: Some("LANDING".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            client
+                .list_all::<Report>(Some(&filter))
+                .await
+                .unwrap()
+                .data
+                .len(),
+            2
+        );
+        filter.comment = Some("ticket".to_string());
+        assert_eq!(
+            client
+                .list_all::<Report>(Some(&filter))
+                .await
+                .unwrap()
+                .data
+                .len(),
+            1
+        );
+
+        // Unknown names fail instead of listing everything
+        let options = GetReportsOptions {
+            customer: Some("Initech".to_string()),
+            ..options
+        };
+        assert!(get_reports(&client, &options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grouped_reports() {
+        let server = FakeTimed::start().await.unwrap();
+        seed_reports(&server);
+        let filter = FilterParams {
+            include: Some("task,task.project,task.project.customer,user".to_string()),
+            ..Default::default()
+        };
+        let response = server
+            .client()
+            .list_all::<Report>(Some(&filter))
+            .await
+            .unwrap();
+        let mut records: Vec<ReportRecord> = response
+            .data
+            .iter()
+            .map(|report| ReportRecord::new(&response, report))
+            .collect();
+        records[2].not_billable = true;
+        let rows: Vec<(TimedDuration, &ReportRecord)> = records
+            .iter()
+            .map(|record| (TimedDuration::minutes(90), record))
+            .collect();
+
+        let levels = [ReportGroup::Customer, ReportGroup::Week];
+        let (groups, table) = grouped_reports(&levels, false, "in March", &rows);
+
+        let summary: Vec<(Value, Value, Value)> = groups
+            .iter()
+            .map(|g| (g["customer"].clone(), g["week"].clone(), g["hours"].clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (json!("Acme"), Value::Null, json!(4.5)),
+                (json!("Acme"), json!("2024-W10"), json!(4.5)),
+                (json!("Globex"), Value::Null, json!(1.5)),
+                (json!("Globex"), json!("2024-W10"), json!(1.5)),
+                (Value::Null, Value::Null, json!(6.0)),
+            ]
+        );
+        assert_eq!(groups[4]["billable_hours"], json!(4.5));
+        assert_eq!(groups[4]["not_billable_hours"], json!(1.5));
+
+        let text = table.render();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[2], "Customer | Week     | Duration | Customer | Project | Task        | Comment      | Flags");
+        assert_eq!(lines[3], "Acme     |          | 4:30");
+        assert_eq!(lines[4], "         | 2024-W10 | 4:30");
+        assert!(lines[5].starts_with("         |          | 1:30     | Acme"));
+        assert!(text.ends_with("Billable: 4:30 (4.50 hours)\nNot billable: 1:30 (1.50 hours)"));
+    }
+
+    #[tokio::test]
+    async fn test_report_intersection() {
+        let server = FakeTimed::start().await.unwrap();
+        seed_reports(&server);
+        let filter = FilterParams {
+            customer: Some(
+                server.all("customers")[0]["id"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            ),
+            user: Some(server.current_user_id()),
+            ..Default::default()
+        };
+
+        let intersection = get_report_intersection(&server.client(), &filter)
+            .await
+            .unwrap();
+        let lines = intersection_lines(&intersection);
+
+        assert_eq!(lines[0], ("Customer", "Acme".to_string()));
+        assert_eq!(lines[2], ("Task", "Development".to_string()));
+        assert_eq!(lines[3], ("User", "fake".to_string()));
+        assert_eq!(lines[4], ("Comment", "Landing page".to_string()));
+        assert_eq!(lines[5], ("Review", "false".to_string()));
+
+        // Across customers the task and comment differ
+        let filter = FilterParams {
+            date: Some("2024-03-05".to_string()),
+            user: Some(server.current_user_id()),
+            ..Default::default()
+        };
+        let intersection = get_report_intersection(&server.client(), &filter)
+            .await
+            .unwrap();
+        let lines = intersection_lines(&intersection);
+        assert_eq!(lines[2], ("Task", "<varies>".to_string()));
+        assert_eq!(lines[4], ("Comment", "<varies>".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_reports() {
+        let server = FakeTimed::start().await.unwrap();
+        let (development, meetings) = seed_reports(&server);
+        let client = server.client();
+        let options = BulkEditOptions {
+            from_date: Some("2024-03-01".to_string()),
+            to_date: Some("2024-03-31".to_string()),
+            customer: Some("acme".to_string()),
+            set_task: Some("acme/website/meetings".to_string()),
+            review: Some(true),
+            comment: Some("Website relaunch".to_string()),
+            yes: true,
+            ..Default::default()
+        };
+
+        // A dry run changes nothing
+        let dry_run = BulkEditOptions {
+            dry_run: true,
+            ..options.clone()
+        };
+        assert_eq!(bulk_update_reports(&client, &dry_run).await.unwrap(), 0);
+        assert!(server
+            .all("reports")
+            .iter()
+            .all(|r| r["attributes"]["review"] == false));
+
+        assert_eq!(bulk_update_reports(&client, &options).await.unwrap(), 2);
+
+        let reports = server.all("reports");
+        let changed: Vec<&serde_json::Value> = reports
+            .iter()
+            .filter(|r| r["attributes"]["review"] == true)
+            .collect();
+        assert_eq!(changed.len(), 2);
+        for report in changed {
+            assert_eq!(report["attributes"]["comment"], "Website relaunch");
+            assert_eq!(report["relationships"]["task"]["data"]["id"], meetings);
+        }
+        // Other customers and other users' reports are left alone
+        assert_eq!(reports[2]["attributes"]["comment"], "Tickets");
+        assert_eq!(
+            reports[3]["relationships"]["task"]["data"]["id"],
+            development
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_needs_changes_and_filters() {
+        let server = FakeTimed::start().await.unwrap();
+        seed_reports(&server);
+        let client = server.client();
+
+        let nothing = BulkEditOptions {
+            date: Some("2024-03-05".to_string()),
+            yes: true,
+            ..Default::default()
+        };
+        let error = bulk_update_reports(&client, &nothing).await.unwrap_err();
+        assert!(error.to_string().contains("Nothing to change"));
+
+        let everything = BulkEditOptions {
+            review: Some(true),
+            yes: true,
+            ..Default::default()
+        };
+        let error = bulk_update_reports(&client, &everything).await.unwrap_err();
+        assert!(error.to_string().contains("Refusing"));
+    }
+
+    #[tokio::test]
+    async fn test_export_reports_to_directory() {
+        let server = FakeTimed::start().await.unwrap();
+        let website = server.add_task("Acme", "Website", "Development");
+        let support = server.add_task("Globex", "Support", "Tickets");
+        for (date, task, comment) in [
+            ("2024-03-04", &website, "Landing page, \"v2\""),
+            ("2024-03-05", &support, "Tickets"),
+            ("2024-04-01", &website, "Next month"),
+        ] {
+            server.insert(
+                "reports",
+                json!({ "date": date, "duration": "01:30:00", "comment": comment }),
+                json!({ "task": to_one("tasks", task) }),
+            );
+        }
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = ExportOptions {
+            from_date: Some("2024-03-01".to_string()),
+            to_date: Some("2024-03-31".to_string()),
+            customer: Some("acme".to_string()),
+            file_type: "csv".to_string(),
+            output: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let path = export_reports(&server.client(), &options).await.unwrap();
+
+        assert_eq!(path, dir.path().join("report.csv"));
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            vec![
+                "date,duration,customer,project,task,comment",
+                "\"2024-03-04\",\"01:30:00\",\"Acme\",\"Website\",\"Development\",\"Landing page, \"\"v2\"\"\"",
+            ]
+        );
+        assert!(!dir.path().join("report.csv.part").exists());
+
+        // Failed exports leave no file behind
+        let options = ExportOptions {
+            file_type: "xlsx".to_string(),
+            output: Some(dir.path().join("march.xlsx")),
+            ..options
+        };
+        assert!(export_reports(&server.client(), &options).await.is_err());
+        assert!(!dir.path().join("march.xlsx").exists());
+    }
+
+    #[tokio::test]
+    async fn test_add_report_rounding() {
+        let server = FakeTimed::start().await.unwrap();
+        server.add_task("Acme", "Website", "Development");
+        server.add_task("Acme", "Support", "Tickets");
+        server.add_task("Globex", "Support", "Tickets");
+        let client = server.client();
+        let rounding: RoundingConfig = toml::from_str(
+            r#"
+increment_minutes = 15
+
+[[overrides]]
+customer = "Acme"
+increment_minutes = 6
+mode = "up"
+
+[[overrides]]
+customer = "Acme"
+project = "Support"
+mode = "none"
+"#,
+        )
+        .unwrap();
+
+        for (customer, project, task) in [
+            ("Acme", "Website", "Development"),
+            ("Acme", "Support", "Tickets"),
+            ("Globex", "Support", "Tickets"),
+        ] {
+            add_report(
+                &client,
+                Some(customer),
+                Some(project),
+                Some(task),
+                Some("Work"),
+                Some("50m"),
+                Some("2024-03-04"),
+                false,
+                false,
+                false,
+                false,
+                &rounding,
+            )
+            .await
+            .unwrap();
+        }
+        add_report(
+            &client,
+            Some("Globex"),
+            Some("Support"),
+            Some("Tickets"),
+            Some("Work"),
+            Some("50m"),
+            Some("2024-03-04"),
+            false,
+            false,
+            false,
+            false,
+            &RoundingConfig::disabled(),
+        )
+        .await
+        .unwrap();
+
+        let durations: Vec<_> = server
+            .all("reports")
+            .iter()
+            .map(|r| r["attributes"]["duration"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(durations, ["00:54:00", "00:50:00", "00:45:00", "00:50:00"]);
+
+        // Durations rounded away would be empty reports
+        let error = add_report(
+            &client,
+            Some("Globex"),
+            Some("Support"),
+            Some("Tickets"),
+            Some("Work"),
+            Some("5m"),
+            Some("2024-03-04"),
+            false,
+            false,
+            false,
+            false,
+            &rounding,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("--no-round"));
+        assert_eq!(server.all("reports").len(), 4);
+    }
+}
@@ -1,580 +1,583 @@
 use anyhow::Result;
 use chrono::{Datelike, Local};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use super::{
+    capitalize, date_param, format_duration, hours, lookup::ResolvedFilters, resolve_username,
+    TaskPath,
+};
+use crate::output::{print_list, OutputFormat, Table};
 
 use libtimed::{
     models::{
         CustomerStatistic, FilterParams, MonthStatistic, ProjectStatistic, ResourcesResponse,
         TaskStatistic, UserStatistic, WorkReport, YearStatistic,
     },
+    transforms::TimedDuration,
     TimedClient,
 };
 
-/// Get year statistics for the current user or a specific user
-#[allow(dead_code)]
-pub async fn get_year_statistics(
-    client: &TimedClient,
-    year: Option<i32>,
-    user_id: Option<&str>,
-) -> Result<()> {
-    let mut filter = FilterParams::default();
-
-    // Use current year if not specified
-    let year_value = year.unwrap_or_else(|| Local::now().year());
-    filter
-        .custom
-        .insert("year".to_string(), year_value.to_string());
-
-    // Add user filter if specified
-    if let Some(id) = user_id {
-        filter.user = Some(id.to_string());
-    }
-
-    // Include user data
-    filter.include = Some("user".to_string());
-
-    let response = client
-        .get::<ResourcesResponse<YearStatistic>>("year-statistics", Some(&filter))
-        .await?;
-
-    if response.data.is_empty() {
-        println!("No year statistics found for year {year_value}");
-        return Ok(());
-    }
-
-    println!("Year Statistics for {year_value}");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
-        let total_attendance = stat.attributes.total_attendance;
-
-        // Get user info
-        let mut user_name = "Current User".to_string();
-        if let Some(user_rel) = &stat.relationships.user {
-            if let Some(user_res) = &user_rel.data {
-                let user_id = &user_res.id;
-
-                // Find user in included data
-                if let Some(user) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                {
-                    if let Some(username) = user.attributes.get("username").and_then(|n| n.as_str())
-                    {
-                        user_name = username.to_string();
-                    }
-                }
-            }
-        }
-
-        println!(
-            "User: {user_name} | Total Time: {duration} | Total Attendance: {total_attendance}"
-        );
-    }
-
-    Ok(())
+/// How to order the rows of a statistic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum StatisticsSort {
+    /// Alphabetically by the names of each row
+    #[default]
+    Name,
+    /// Longest duration first
+    Duration,
 }
 
-/// Get month statistics for the current user or a specific user
-#[allow(dead_code)]
-pub async fn get_month_statistics(
-    client: &TimedClient,
-    year: Option<i32>,
-    month: Option<i32>,
-    user_id: Option<&str>,
-) -> Result<()> {
-    let mut filter = FilterParams::default();
-
-    // Use current year/month if not specified
-    let now = Local::now();
-    let year_value = year.unwrap_or_else(|| now.year());
-    let month_value = month.unwrap_or_else(|| now.month() as i32);
-
-    filter
-        .custom
-        .insert("year".to_string(), year_value.to_string());
-    filter
-        .custom
-        .insert("month".to_string(), month_value.to_string());
-
-    // Add user filter if specified
-    if let Some(id) = user_id {
-        filter.user = Some(id.to_string());
-    }
+/// Filters and output settings shared by all statistics
+#[derive(Debug, Clone, Default)]
+pub struct StatisticsOptions {
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub user: Option<String>,
+    pub customer: Option<String>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub sort_by: StatisticsSort,
+    pub output: OutputFormat,
+}
 
-    // Include user data
-    filter.include = Some("user".to_string());
+impl StatisticsOptions {
+    /// Build request parameters, resolving names to IDs
+    async fn filter(&self, client: &TimedClient) -> Result<FilterParams> {
+        let mut filter = FilterParams::default();
 
-    let response = client
-        .get::<ResourcesResponse<MonthStatistic>>("month-statistics", Some(&filter))
-        .await?;
+        if let Some(from) = &self.from_date {
+            filter.from_date = Some(date_param(from)?);
+        }
+        if let Some(to) = &self.to_date {
+            filter.to_date = Some(date_param(to)?);
+        }
 
-    if response.data.is_empty() {
-        println!("No month statistics found for {year_value}/{month_value}");
-        return Ok(());
+        ResolvedFilters::resolve(
+            client,
+            self.customer.as_deref(),
+            self.project.as_deref(),
+            self.task.as_deref(),
+            self.user.as_deref(),
+        )
+        .await?
+        .apply(&mut filter);
+
+        Ok(filter)
     }
 
-    println!("Month Statistics for {year_value}/{month_value}");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
-        let total_attendance = stat.attributes.total_attendance;
-
-        // Get user info
-        let mut user_name = "Current User".to_string();
-        if let Some(user_rel) = &stat.relationships.user {
-            if let Some(user_res) = &user_rel.data {
-                let user_id = &user_res.id;
-
-                // Find user in included data
-                if let Some(user) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                {
-                    if let Some(username) = user.attributes.get("username").and_then(|n| n.as_str())
-                    {
-                        user_name = username.to_string();
-                    }
-                }
-            }
-        }
-
-        println!(
-            "User: {user_name} | Total Time: {duration} | Total Attendance: {total_attendance}"
-        );
+    fn has_date_range(&self) -> bool {
+        self.from_date.is_some() || self.to_date.is_some()
     }
+}
 
-    Ok(())
+/// One row of a statistic
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatisticRow {
+    /// Names identifying the row, one per column
+    pub labels: Vec<String>,
+    pub duration: TimedDuration,
+    pub total_attendance: Option<TimedDuration>,
 }
 
-/// Get task statistics for a user and date range
-#[allow(dead_code)]
-pub async fn get_task_statistics(
-    client: &TimedClient,
-    from_date: Option<&str>,
-    to_date: Option<&str>,
-    user_id: Option<&str>,
-    task_id: Option<&str>,
-) -> Result<()> {
-    let mut filter = FilterParams::default();
+/// A statistic ready to be rendered
+#[derive(Debug, Clone)]
+pub struct StatisticTable {
+    pub title: String,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<StatisticRow>,
+}
 
-    // Add date range if specified
-    if let Some(from) = from_date {
-        filter.from_date = Some(from.to_string());
+impl StatisticTable {
+    /// Order the rows
+    pub fn sort(&mut self, sort_by: StatisticsSort) {
+        match sort_by {
+            StatisticsSort::Name => self.rows.sort_by(|a, b| a.labels.cmp(&b.labels)),
+            StatisticsSort::Duration => self.rows.sort_by(|a, b| {
+                b.duration
+                    .cmp(&a.duration)
+                    .then_with(|| a.labels.cmp(&b.labels))
+            }),
+        }
     }
 
-    if let Some(to) = to_date {
-        filter.to_date = Some(to.to_string());
+    /// Sum of all rows
+    pub fn total(&self) -> TimedDuration {
+        self.rows.iter().map(|row| row.duration).sum()
     }
 
-    // Add user filter if specified
-    if let Some(id) = user_id {
-        filter.user = Some(id.to_string());
+    fn has_attendance(&self) -> bool {
+        self.rows.iter().any(|row| row.total_attendance.is_some())
     }
 
-    // Add task filter if specified
-    if let Some(id) = task_id {
-        filter.task = Some(id.to_string());
+    /// Records for structured output, with one field per column
+    pub fn records(&self) -> Vec<Map<String, Value>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut record: Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .zip(&row.labels)
+                    .map(|(column, label)| (column.to_string(), json!(label)))
+                    .collect();
+                record.insert("duration".to_string(), json!(row.duration.to_string()));
+                record.insert("hours".to_string(), json!(hours(row.duration)));
+                if let Some(attendance) = row.total_attendance {
+                    record.insert(
+                        "total_attendance".to_string(),
+                        json!(attendance.to_string()),
+                    );
+                }
+                record
+            })
+            .collect()
     }
 
-    // Include task and user data
-    filter.include = Some("task,task.project,task.project.customer,user".to_string());
+    /// Human-readable table with a total
+    pub fn table(&self) -> Table {
+        let mut header: Vec<String> = self.columns.iter().map(|c| capitalize(c)).collect();
+        header.push("Duration".to_string());
+        if self.has_attendance() {
+            header.push("Attendance".to_string());
+        }
 
-    let response = client
-        .get::<ResourcesResponse<TaskStatistic>>("task-statistics", Some(&filter))
-        .await?;
+        let mut table = Table::new(header).title(self.title.clone()).empty(format!(
+            "No {} found for the specified criteria",
+            self.title.to_lowercase()
+        ));
+        for row in &self.rows {
+            let mut cells = row.labels.clone();
+            cells.push(format_duration(row.duration.into()));
+            if let Some(attendance) = row.total_attendance {
+                cells.push(format_duration(attendance.into()));
+            }
+            table.row(cells);
+        }
 
-    if response.data.is_empty() {
-        println!("No task statistics found for the specified criteria");
-        return Ok(());
+        let total = self.total();
+        table.footer(format!(
+            "Total: {} ({} hours)",
+            total.format_clock(),
+            total.format_decimal()
+        ));
+        table
     }
+}
 
-    println!("Task Statistics");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
-
-        // Get task info
-        let mut task_name = "Unknown Task".to_string();
-        let mut project_name = "Unknown Project".to_string();
-        let mut customer_name = "Unknown Customer".to_string();
-
-        if let Some(task_rel) = &stat.relationships.task {
-            if let Some(task_res) = &task_rel.data {
-                let task_id = &task_res.id;
+/// Sort and print a statistic
+fn print_table(mut table: StatisticTable, options: &StatisticsOptions) -> Result<()> {
+    table.sort(options.sort_by);
+    print_list(options.output, &table.records(), &table.table())
+}
 
-                // Find task in included data
-                if let Some(task) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "tasks" && inc.id == *task_id)
-                {
-                    if let Some(name) = task.attributes.get("name").and_then(|n| n.as_str()) {
-                        task_name = name.to_string();
-                    }
-
-                    // Find project
-                    if let Some(project_id) = task
-                        .relationships
-                        .as_ref()
-                        .and_then(|r| r.get("project"))
-                        .and_then(|p| p.get("data"))
-                        .and_then(|d| d.get("id"))
-                        .and_then(|id| id.as_str())
-                    {
-                        if let Some(project) = included
-                            .iter()
-                            .find(|inc| inc.type_name == "projects" && inc.id == project_id)
-                        {
-                            if let Some(name) =
-                                project.attributes.get("name").and_then(|n| n.as_str())
-                            {
-                                project_name = name.to_string();
-                            }
-
-                            // Find customer
-                            if let Some(customer_id) = project
-                                .relationships
-                                .as_ref()
-                                .and_then(|r| r.get("customer"))
-                                .and_then(|p| p.get("data"))
-                                .and_then(|d| d.get("id"))
-                                .and_then(|id| id.as_str())
-                            {
-                                if let Some(customer) = included.iter().find(|inc| {
-                                    inc.type_name == "customers" && inc.id == customer_id
-                                }) {
-                                    if let Some(name) =
-                                        customer.attributes.get("name").and_then(|n| n.as_str())
-                                    {
-                                        customer_name = name.to_string();
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Name of the user a statistic belongs to
+fn username<T>(response: &ResourcesResponse<T>, user_id: Option<&str>) -> String {
+    user_id
+        .and_then(|id| resolve_username(response, id))
+        .unwrap_or_else(|| "Unknown User".to_string())
+}
 
-        // Get user info
-        let mut user_name = "Unknown User".to_string();
-        if let Some(user_rel) = &stat.relationships.user {
-            if let Some(user_res) = &user_rel.data {
-                let user_id = &user_res.id;
+/// Name of an included customer, project or task
+fn included_name<T>(
+    response: &ResourcesResponse<T>,
+    type_name: &str,
+    id: Option<&str>,
+) -> Option<String> {
+    id.and_then(|id| response.find_included(type_name, id))
+        .and_then(|resource| resource.attribute_str("name"))
+        .map(String::from)
+}
 
-                // Find user in included data
-                if let Some(user) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                {
-                    if let Some(username) = user.attributes.get("username").and_then(|n| n.as_str())
-                    {
-                        user_name = username.to_string();
-                    }
-                }
-            }
-        }
+/// Get year statistics for the current user or a specific user
+pub async fn get_year_statistics(
+    client: &TimedClient,
+    year: Option<i32>,
+    options: &StatisticsOptions,
+) -> Result<()> {
+    let mut filter = options.filter(client).await?;
 
-        println!(
-            "User: {user_name} | Customer: {customer_name} | Project: {project_name} | Task: {task_name} | Duration: {duration}"
-        );
+    // Use the current year unless a year or date range is given
+    let year = year.or_else(|| (!options.has_date_range()).then(|| Local::now().year()));
+    if let Some(year) = year {
+        filter.custom.insert("year".to_string(), year.to_string());
     }
+    filter.include = Some("user".to_string());
 
-    Ok(())
+    let response = client.list_all::<YearStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| StatisticRow {
+            labels: vec![
+                username(
+                    &response,
+                    stat.relationships.user.as_ref().and_then(|u| u.id()),
+                ),
+                stat.attributes.year.to_string(),
+            ],
+            duration: stat.attributes.duration.into(),
+            total_attendance: Some(stat.attributes.total_attendance.into()),
+        })
+        .collect();
+
+    let title = match year {
+        Some(year) => format!("Year Statistics for {year}"),
+        None => "Year Statistics".to_string(),
+    };
+    print_table(
+        StatisticTable {
+            title,
+            columns: vec!["user", "year"],
+            rows,
+        },
+        options,
+    )
 }
 
-/// Get user statistics
-#[allow(dead_code)]
-pub async fn get_user_statistics(
+/// Get month statistics for the current user or a specific user
+pub async fn get_month_statistics(
     client: &TimedClient,
-    from_date: Option<&str>,
-    to_date: Option<&str>,
-    user_id: Option<&str>,
+    year: Option<i32>,
+    month: Option<u32>,
+    options: &StatisticsOptions,
 ) -> Result<()> {
-    let mut filter = FilterParams::default();
-
-    // Add date range if specified
-    if let Some(from) = from_date {
-        filter.from_date = Some(from.to_string());
-    }
+    let mut filter = options.filter(client).await?;
 
-    if let Some(to) = to_date {
-        filter.to_date = Some(to.to_string());
+    // Use the current month unless a month or date range is given
+    let now = Local::now();
+    let default = !options.has_date_range();
+    let year = year.or_else(|| default.then(|| now.year()));
+    let month = month.or_else(|| (default && year == Some(now.year())).then(|| now.month()));
+    if let Some(year) = year {
+        filter.custom.insert("year".to_string(), year.to_string());
     }
-
-    // Add user filter if specified
-    if let Some(id) = user_id {
-        filter.user = Some(id.to_string());
+    if let Some(month) = month {
+        filter.custom.insert("month".to_string(), month.to_string());
     }
-
-    // Include user data
     filter.include = Some("user".to_string());
 
-    let response = client
-        .get::<ResourcesResponse<UserStatistic>>("user-statistics", Some(&filter))
-        .await?;
-
-    if response.data.is_empty() {
-        println!("No user statistics found for the specified criteria");
-        return Ok(());
-    }
-
-    println!("User Statistics");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
+    let response = client.list_all::<MonthStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| StatisticRow {
+            labels: vec![
+                username(
+                    &response,
+                    stat.relationships.user.as_ref().and_then(|u| u.id()),
+                ),
+                format!("{}-{:02}", stat.attributes.year, stat.attributes.month),
+            ],
+            duration: stat.attributes.duration.into(),
+            total_attendance: Some(stat.attributes.total_attendance.into()),
+        })
+        .collect();
+
+    let title = match (year, month) {
+        (Some(year), Some(month)) => format!("Month Statistics for {year}/{month}"),
+        (Some(year), None) => format!("Month Statistics for {year}"),
+        _ => "Month Statistics".to_string(),
+    };
+    print_table(
+        StatisticTable {
+            title,
+            columns: vec!["user", "month"],
+            rows,
+        },
+        options,
+    )
+}
 
-        // Get user info
-        let mut user_name = "Unknown User".to_string();
-        if let Some(user_rel) = &stat.relationships.user {
-            if let Some(user_res) = &user_rel.data {
-                let user_id = &user_res.id;
+/// Get task statistics for a user and date range
+pub async fn get_task_statistics(client: &TimedClient, options: &StatisticsOptions) -> Result<()> {
+    let table = task_statistics(client, options).await?;
+    print_table(table, options)
+}
 
-                // Find user in included data
-                if let Some(user) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "users" && inc.id == *user_id)
-                {
-                    if let Some(username) = user.attributes.get("username").and_then(|n| n.as_str())
-                    {
-                        user_name = username.to_string();
-                    }
-                }
+async fn task_statistics(
+    client: &TimedClient,
+    options: &StatisticsOptions,
+) -> Result<StatisticTable> {
+    let mut filter = options.filter(client).await?;
+    filter.include = Some("task,task.project,task.project.customer".to_string());
+
+    let response = client.list_all::<TaskStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| {
+            let task_id = stat.relationships.task.as_ref().and_then(|t| t.id());
+            let path = TaskPath::resolve(&response, task_id.unwrap_or_default());
+            StatisticRow {
+                labels: vec![path.customer, path.project, path.task],
+                duration: stat.attributes.duration.into(),
+                total_attendance: None,
             }
-        }
+        })
+        .collect();
+
+    Ok(StatisticTable {
+        title: "Task Statistics".to_string(),
+        columns: vec!["customer", "project", "task"],
+        rows,
+    })
+}
 
-        println!("User: {user_name} | Total Time: {duration}");
-    }
+/// Get user statistics
+pub async fn get_user_statistics(client: &TimedClient, options: &StatisticsOptions) -> Result<()> {
+    let mut filter = options.filter(client).await?;
+    filter.include = Some("user".to_string());
 
-    Ok(())
+    let response = client.list_all::<UserStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| StatisticRow {
+            labels: vec![username(
+                &response,
+                stat.relationships.user.as_ref().and_then(|u| u.id()),
+            )],
+            duration: stat.attributes.duration.into(),
+            total_attendance: None,
+        })
+        .collect();
+
+    print_table(
+        StatisticTable {
+            title: "User Statistics".to_string(),
+            columns: vec!["user"],
+            rows,
+        },
+        options,
+    )
 }
 
 /// Get customer statistics
-#[allow(dead_code)]
 pub async fn get_customer_statistics(
     client: &TimedClient,
-    from_date: Option<&str>,
-    to_date: Option<&str>,
-    customer_id: Option<&str>,
+    options: &StatisticsOptions,
 ) -> Result<()> {
-    let mut filter = FilterParams::default();
-
-    // Add date range if specified
-    if let Some(from) = from_date {
-        filter.from_date = Some(from.to_string());
-    }
-
-    if let Some(to) = to_date {
-        filter.to_date = Some(to.to_string());
-    }
-
-    // Add customer filter if specified
-    if let Some(id) = customer_id {
-        filter.customer = Some(id.to_string());
-    }
-
-    // Include customer data
+    let mut filter = options.filter(client).await?;
     filter.include = Some("customer".to_string());
 
-    let response = client
-        .get::<ResourcesResponse<CustomerStatistic>>("customer-statistics", Some(&filter))
-        .await?;
-
-    if response.data.is_empty() {
-        println!("No customer statistics found for the specified criteria");
-        return Ok(());
-    }
-
-    println!("Customer Statistics");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
-
-        // Get customer info
-        let mut customer_name = "Unknown Customer".to_string();
-        if let Some(customer_rel) = &stat.relationships.customer {
-            if let Some(customer_res) = &customer_rel.data {
-                let customer_id = &customer_res.id;
-
-                // Find customer in included data
-                if let Some(customer) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "customers" && inc.id == *customer_id)
-                {
-                    if let Some(name) = customer.attributes.get("name").and_then(|n| n.as_str()) {
-                        customer_name = name.to_string();
-                    }
-                }
+    let response = client.list_all::<CustomerStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| {
+            let customer_id = stat.relationships.customer.as_ref().and_then(|c| c.id());
+            StatisticRow {
+                labels: vec![included_name(&response, "customers", customer_id)
+                    .unwrap_or_else(|| "Unknown Customer".to_string())],
+                duration: stat.attributes.duration.into(),
+                total_attendance: None,
             }
-        }
-
-        println!("Customer: {customer_name} | Total Time: {duration}");
-    }
-
-    Ok(())
+        })
+        .collect();
+
+    print_table(
+        StatisticTable {
+            title: "Customer Statistics".to_string(),
+            columns: vec!["customer"],
+            rows,
+        },
+        options,
+    )
 }
 
 /// Get project statistics
-#[allow(dead_code)]
 pub async fn get_project_statistics(
     client: &TimedClient,
-    from_date: Option<&str>,
-    to_date: Option<&str>,
-    customer_id: Option<&str>,
-    project_id: Option<&str>,
+    options: &StatisticsOptions,
 ) -> Result<()> {
-    let mut filter = FilterParams::default();
-
-    // Add date range if specified
-    if let Some(from) = from_date {
-        filter.from_date = Some(from.to_string());
-    }
-
-    if let Some(to) = to_date {
-        filter.to_date = Some(to.to_string());
-    }
-
-    // Add customer filter if specified
-    if let Some(id) = customer_id {
-        filter.customer = Some(id.to_string());
-    }
+    let mut filter = options.filter(client).await?;
+    filter.include = Some("project,project.customer".to_string());
 
-    // Add project filter if specified
-    if let Some(id) = project_id {
-        filter.project = Some(id.to_string());
-    }
+    let response = client.list_all::<ProjectStatistic>(Some(&filter)).await?;
+
+    let rows = response
+        .data
+        .iter()
+        .map(|stat| {
+            let project_id = stat.relationships.project.as_ref().and_then(|p| p.id());
+            let customer_id = project_id
+                .and_then(|id| response.find_included("projects", id))
+                .and_then(|project| project.relationship_id("customer"));
+            StatisticRow {
+                labels: vec![
+                    included_name(&response, "customers", customer_id)
+                        .unwrap_or_else(|| "Unknown Customer".to_string()),
+                    included_name(&response, "projects", project_id)
+                        .unwrap_or_else(|| "Unknown Project".to_string()),
+                ],
+                duration: stat.attributes.duration.into(),
+                total_attendance: None,
+            }
+        })
+        .collect();
+
+    print_table(
+        StatisticTable {
+            title: "Project Statistics".to_string(),
+            columns: vec!["customer", "project"],
+            rows,
+        },
+        options,
+    )
+}
 
-    // Include project and customer data
-    filter.include = Some("project,project.customer".to_string());
+/// Get work report
+pub async fn get_work_report(client: &TimedClient, options: &StatisticsOptions) -> Result<()> {
+    let filter = options.filter(client).await?;
+    let (Some(from_date), Some(to_date)) = (&filter.from_date, &filter.to_date) else {
+        return Err(anyhow::anyhow!("A work report needs --from and --to"));
+    };
 
-    let response = client
-        .get::<ResourcesResponse<ProjectStatistic>>("project-statistics", Some(&filter))
-        .await?;
+    let response = client.list_all::<WorkReport>(Some(&filter)).await?;
 
     if response.data.is_empty() {
-        println!("No project statistics found for the specified criteria");
+        println!("No work report data found for the specified date range");
         return Ok(());
     }
 
-    println!("Project Statistics");
-    println!("----------------------------------------");
-
-    // Get included data
-    let included = response.included.unwrap_or_default();
-
-    // Display each statistic
-    for stat in response.data {
-        let duration = stat.attributes.duration;
-
-        // Get project info
-        let mut project_name = "Unknown Project".to_string();
-        let mut customer_name = "Unknown Customer".to_string();
-
-        if let Some(project_rel) = &stat.relationships.project {
-            if let Some(project_res) = &project_rel.data {
-                let project_id = &project_res.id;
+    // Work report data is a complex JSON structure, so it is passed through as is
+    let records: Vec<Value> = response
+        .data
+        .into_iter()
+        .map(|report| report.attributes.data)
+        .collect();
+    let text = records
+        .iter()
+        .map(serde_json::to_string_pretty)
+        .collect::<Result<Vec<_>, _>>()?
+        .join("\n");
+
+    match options.output {
+        OutputFormat::Table => {
+            println!("Work Report from {from_date} to {to_date}");
+            println!("----------------------------------------");
+            println!("{text}");
+            Ok(())
+        }
+        output => print_list(output, &records, &Table::default()),
+    }
+}
 
-                // Find project in included data
-                if let Some(project) = included
-                    .iter()
-                    .find(|inc| inc.type_name == "projects" && inc.id == *project_id)
-                {
-                    if let Some(name) = project.attributes.get("name").and_then(|n| n.as_str()) {
-                        project_name = name.to_string();
-                    }
-
-                    // Find customer
-                    if let Some(customer_id) = project
-                        .relationships
-                        .as_ref()
-                        .and_then(|r| r.get("customer"))
-                        .and_then(|p| p.get("data"))
-                        .and_then(|d| d.get("id"))
-                        .and_then(|id| id.as_str())
-                    {
-                        if let Some(customer) = included
-                            .iter()
-                            .find(|inc| inc.type_name == "customers" && inc.id == customer_id)
-                        {
-                            if let Some(name) =
-                                customer.attributes.get("name").and_then(|n| n.as_str())
-                            {
-                                customer_name = name.to_string();
-                            }
-                        }
-                    }
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::render_list;
+    use libtimed::fake::{to_one, FakeTimed};
+    use pretty_assertions::assert_eq;
+
+    fn row(labels: &[&str], minutes: i64) -> StatisticRow {
+        StatisticRow {
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            duration: TimedDuration::minutes(minutes),
+            total_attendance: None,
         }
+    }
 
-        println!("Customer: {customer_name} | Project: {project_name} | Total Time: {duration}");
+    fn table() -> StatisticTable {
+        StatisticTable {
+            title: "Customer Statistics".to_string(),
+            columns: vec!["customer"],
+            rows: vec![
+                row(&["Globex"], 90),
+                row(&["Acme, Inc."], 30),
+                row(&["Initech"], 240),
+            ],
+        }
     }
 
-    Ok(())
-}
+    #[test]
+    fn test_sort_statistics() {
+        let mut table = table();
+        let names = |t: &StatisticTable| -> Vec<String> {
+            t.rows.iter().map(|r| r.labels[0].clone()).collect()
+        };
 
-/// Get work report
-#[allow(dead_code)]
-pub async fn get_work_report(
-    client: &TimedClient,
-    from_date: &str,
-    to_date: &str,
-    user_id: Option<&str>,
-) -> Result<()> {
-    let mut filter = FilterParams {
-        from_date: Some(from_date.to_string()),
-        to_date: Some(to_date.to_string()),
-        ..Default::default()
-    };
+        table.sort(StatisticsSort::Name);
+        assert_eq!(names(&table), vec!["Acme, Inc.", "Globex", "Initech"]);
 
-    // Add user filter if specified
-    if let Some(id) = user_id {
-        filter.user = Some(id.to_string());
+        table.sort(StatisticsSort::Duration);
+        assert_eq!(names(&table), vec!["Initech", "Globex", "Acme, Inc."]);
+        assert_eq!(table.total(), TimedDuration::minutes(360));
     }
 
-    let response = client
-        .get::<ResourcesResponse<WorkReport>>("work-reports", Some(&filter))
-        .await?;
+    #[test]
+    fn test_render_statistics() {
+        let table = table();
+
+        let text = table.table().render();
+        assert!(text.contains("Globex     | 1:30"));
+        assert!(text.ends_with("Total: 6:00 (6.00 hours)"));
+
+        let csv = render_list(OutputFormat::Csv, &table.records(), &table.table()).unwrap();
+        assert_eq!(
+            csv.lines().take(3).collect::<Vec<_>>(),
+            vec![
+                "customer,duration,hours",
+                "Globex,01:30:00,1.5",
+                "\"Acme, Inc.\",00:30:00,0.5"
+            ]
+        );
 
-    if response.data.is_empty() {
-        println!("No work report data found for the specified date range");
-        return Ok(());
+        assert_eq!(
+            Value::Object(table.records()[2].clone()),
+            json!({ "customer": "Initech", "duration": "04:00:00", "hours": 4.0 })
+        );
     }
 
-    println!("Work Report from {from_date} to {to_date}");
-    println!("----------------------------------------");
+    #[tokio::test]
+    async fn test_task_statistics_with_filters() {
+        let server = FakeTimed::start().await.unwrap();
+        let development = server.add_task("Acme", "Website", "Development");
+        let meetings = server.add_task("Acme", "Website", "Meetings");
+        let tickets = server.add_task("Globex", "Support", "Tickets");
+        for (date, duration, task) in [
+            ("2024-03-01", "01:00:00", &development),
+            ("2024-03-02", "02:30:00", &development),
+            ("2024-03-02", "00:30:00", &meetings),
+            ("2024-03-03", "04:00:00", &tickets),
+            ("2024-04-01", "08:00:00", &development),
+        ] {
+            server.insert(
+                "reports",
+                json!({ "date": date, "duration": duration, "comment": "" }),
+                json!({ "task": to_one("tasks", task) }),
+            );
+        }
+        let client = server.client();
+
+        let options = StatisticsOptions {
+            from_date: Some("2024-03-01".to_string()),
+            to_date: Some("2024-03-31".to_string()),
+            customer: Some("acme".to_string()),
+            ..Default::default()
+        };
+        let mut table = task_statistics(&client, &options).await.unwrap();
+        table.sort(StatisticsSort::Duration);
+
+        assert_eq!(
+            table.rows,
+            vec![
+                row(&["Acme", "Website", "Development"], 210),
+                row(&["Acme", "Website", "Meetings"], 30),
+            ]
+        );
 
-    // Display report data
-    for report in response.data {
-        // Work report data is a complex JSON structure
-        // Here we just print it as formatted JSON
-        let data_str = serde_json::to_string_pretty(&report.attributes.data)?;
-        println!("{data_str}");
+        // Names that match nothing are reported before any statistics are fetched
+        let options = StatisticsOptions {
+            project: Some("Intranet".to_string()),
+            ..options
+        };
+        assert!(task_statistics(&client, &options).await.is_err());
     }
-
-    Ok(())
 }
@@ -3,18 +3,33 @@
 //! This library provides a type-safe interface to interact with the Timed API.
 //! It handles serialization/deserialization of models, API calls, and data transformation.
 
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use reqwest::{header, Client, ClientBuilder};
-use serde::{Deserialize, Serialize};
+use futures_util::{stream, Stream, TryStreamExt};
+use reqwest::{
+    header, Certificate, Client, ClientBuilder, Identity, Method, Proxy, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use thiserror::Error;
-use tracing::{debug, error};
+use tracing::{debug, error, info, warn};
 
+pub mod cache;
+#[cfg(feature = "fake-server")]
+pub mod fake;
 pub mod models;
+pub mod retry;
+pub mod token;
 pub mod transforms;
 
-use models::FilterParams;
+use cache::ResponseCache;
+use models::{
+    ApiErrorObject, ErrorResponse, FilterParams, Resource, ResourceResponse, ResourcesResponse,
+};
+use retry::RetryPolicy;
+use token::TokenProvider;
 
 /// Error types for the libtimed library
 #[derive(Error, Debug)]
@@ -34,50 +49,152 @@ pub enum TimedError {
     #[error("Authentication required")]
     AuthenticationRequired,
 
+    #[error("Permission denied: {0}")]
+    Forbidden(String),
+
+    #[error("Request rejected (HTTP {status}):{}", list_errors(.errors))]
+    Api {
+        status: u16,
+        errors: Vec<ApiErrorObject>,
+    },
+
+    #[error("Failed to obtain token: {0}")]
+    Token(String),
+
+    #[error("Invalid client configuration: {0}")]
+    ClientConfig(String),
+
     #[error("Operation not permitted: {0}")]
     OperationNotPermitted(String),
 }
 
+/// Render error objects inline, or as a list if there are several
+fn list_errors(errors: &[ApiErrorObject]) -> String {
+    match errors {
+        [error] => format!(" {error}"),
+        _ => errors.iter().map(|e| format!("\n  - {e}")).collect(),
+    }
+}
+
+fn join_errors(errors: &[ApiErrorObject]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Result type for libtimed operations
 pub type Result<T> = std::result::Result<T, TimedError>;
 
+/// API namespace of the Timed backend
+pub const DEFAULT_API_NAMESPACE: &str = "api/v1";
+
+/// Timeout of a single request
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `User-Agent` sent unless a custom one is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("timedctl/", env!("CARGO_PKG_VERSION"));
+
+/// Number of resources requested per page when paginating
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+const PAGE_NUMBER_PARAM: &str = "page[number]";
+const PAGE_SIZE_PARAM: &str = "page[size]";
+
 /// Client for interacting with the Timed API
 pub struct TimedClient {
     http_client: Client,
     base_url: String,
-    token: Option<String>,
+    token: RwLock<Option<String>>,
+    token_provider: Option<Arc<dyn TokenProvider>>,
+    retry_policy: RetryPolicy,
+    cache: Option<ResponseCache>,
 }
 
 impl TimedClient {
-    /// Create a new Timed API client
-    pub fn new(base_url: &str, api_namespace: &str, token: Option<String>) -> Self {
-        let http_client = ClientBuilder::new()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to build HTTP client");
-
-        let base_url = format!("{base_url}/{api_namespace}/");
-
-        Self {
-            http_client,
-            base_url,
-            token,
+    /// Create a new Timed API client with default HTTP settings
+    ///
+    /// Fails if the HTTP client can't be set up, e.g. with a broken TLS backend.
+    pub fn new(base_url: &str, api_namespace: &str, token: Option<String>) -> Result<Self> {
+        let mut builder = Self::builder(base_url).api_namespace(api_namespace);
+        if let Some(token) = token {
+            builder = builder.token(token);
         }
+        builder.build()
+    }
+
+    /// Start building a client with custom HTTP settings
+    pub fn builder(base_url: &str) -> TimedClientBuilder {
+        TimedClientBuilder::new(base_url)
     }
 
     /// Set the authentication token
     pub fn set_token(&mut self, token: String) {
-        self.token = Some(token);
+        *self.token.get_mut().unwrap() = Some(token);
+    }
+
+    /// Set the provider used to obtain and refresh tokens
+    ///
+    /// When the API answers with 401, the client asks the provider for a
+    /// new token once and replays the request.
+    pub fn set_token_provider(&mut self, provider: Arc<dyn TokenProvider>) {
+        self.token_provider = Some(provider);
+    }
+
+    /// Set the policy used to retry failed requests
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Get the policy used to retry failed requests
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Cache responses of read-mostly resources
+    pub fn set_cache(&mut self, cache: ResponseCache) {
+        self.cache = Some(cache);
+    }
+
+    /// Get the response cache, if caching is enabled
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
     }
 
-    /// Check if the client has an authentication token
+    /// Check if the client has an authentication token or a way to get one
     pub fn has_token(&self) -> bool {
-        self.token.is_some()
+        self.token.read().unwrap().is_some() || self.token_provider.is_some()
+    }
+
+    /// Get the current authentication token
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    /// Get the current token, asking the token provider if there is none yet
+    async fn current_token(&self) -> Result<String> {
+        if let Some(token) = self.token() {
+            return Ok(token);
+        }
+        let provider = self
+            .token_provider
+            .as_ref()
+            .ok_or(TimedError::AuthenticationRequired)?;
+        let token = provider.token().await?;
+        *self.token.write().unwrap() = Some(token.clone());
+        Ok(token)
     }
 
-    /// Get the authentication token
-    pub fn token(&self) -> Option<&str> {
-        self.token.as_deref()
+    /// Replace a rejected token with a fresh one from the token provider
+    async fn refresh_token(&self) -> Result<String> {
+        let provider = self
+            .token_provider
+            .as_ref()
+            .ok_or(TimedError::AuthenticationRequired)?;
+        let token = provider.refresh().await?;
+        *self.token.write().unwrap() = Some(token.clone());
+        Ok(token)
     }
 
     /// Get a reference to the underlying HTTP client
@@ -96,26 +213,7 @@ impl TimedClient {
         endpoint: &str,
         params: Option<&FilterParams>,
     ) -> Result<T> {
-        if self.token.is_none() {
-            return Err(TimedError::AuthenticationRequired);
-        }
-
-        let mut req = self
-            .http_client
-            .get(format!("{}{}", self.base_url, endpoint));
-
-        // Add authentication header
-        req = req.header(
-            header::AUTHORIZATION,
-            format!("Bearer {}", self.token.as_ref().unwrap()),
-        );
-
-        // Add content type header
-        req = req.header(header::CONTENT_TYPE, "application/vnd.api+json");
-
-        // Add query parameters if provided
         if let Some(p) = params {
-            req = req.query(&p);
             debug!(
                 "Making GET request to {} with params: {:?}",
                 endpoint,
@@ -125,30 +223,38 @@ impl TimedClient {
             debug!("Making GET request to {}", endpoint);
         }
 
-        let response = req.send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await?;
-            error!("API error ({}): {}", status, text);
+        let cache = match &self.cache {
+            Some(cache) if self.has_token() && cache.is_cached(endpoint) => {
+                let key = cache_key(&self.base_url, endpoint, params);
+                if let Some(value) = cache.get(endpoint, &key) {
+                    return Ok(serde_json::from_value(value)?);
+                }
+                Some((cache, key))
+            }
+            _ => None,
+        };
 
-            return match status.as_u16() {
-                404 => Err(TimedError::NotFound(endpoint.to_string())),
-                401 | 403 => Err(TimedError::AuthenticationRequired),
-                _ => Err(TimedError::InvalidResponse(format!(
-                    "HTTP {status}: {text}"
-                ))),
-            };
+        let response = self.send(Method::GET, endpoint, params, None).await?;
+        match cache {
+            Some((cache, key)) => {
+                let value: serde_json::Value = parse_json(response).await?;
+                cache.insert(endpoint, &key, &value);
+                Ok(serde_json::from_value(value)?)
+            }
+            None => parse_json(response).await,
         }
+    }
 
-        let data = response.json::<T>().await.map_err(|e| {
-            TimedError::Serialization(serde_json::Error::io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                e,
-            )))
-        })?;
-
-        Ok(data)
+    /// Make a GET request to the API and return the raw response
+    ///
+    /// Useful for endpoints that don't return JSON, like report exports.
+    pub async fn get_response(
+        &self,
+        endpoint: &str,
+        params: Option<&FilterParams>,
+    ) -> Result<Response> {
+        debug!("Making raw GET request to {}", endpoint);
+        self.send(Method::GET, endpoint, params, None).await
     }
 
     /// Make a POST request to the API
@@ -157,49 +263,29 @@ impl TimedClient {
         endpoint: &str,
         data: &T,
     ) -> Result<R> {
-        if self.token.is_none() {
-            return Err(TimedError::AuthenticationRequired);
-        }
+        let body = serde_json::to_value(data)?;
+        debug!("Making POST request to {} with data: {}", endpoint, body);
 
-        let req = self
-            .http_client
-            .post(format!("{}{}", self.base_url, endpoint))
-            .header(
-                header::AUTHORIZATION,
-                format!("Bearer {}", self.token.as_ref().unwrap()),
-            )
-            .header(header::CONTENT_TYPE, "application/vnd.api+json")
-            .json(data);
+        let response = self.send(Method::POST, endpoint, None, Some(&body)).await?;
+        parse_json(response).await
+    }
 
+    /// Make a POST request with query parameters and return the raw response
+    ///
+    /// Useful for endpoints that act on a filtered set, like bulk report updates.
+    pub async fn post_response<T: Serialize + std::fmt::Debug>(
+        &self,
+        endpoint: &str,
+        params: Option<&FilterParams>,
+        data: &T,
+    ) -> Result<Response> {
+        let body = serde_json::to_value(data)?;
         debug!(
-            "Making POST request to {} with data: {}",
-            endpoint,
-            serde_json::to_string(data).unwrap_or_else(|_| format!("{data:?}"))
+            "Making raw POST request to {} with data: {}",
+            endpoint, body
         );
-        let response = req.send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await?;
-            error!("API error ({}): {}", status, text);
-
-            return match status.as_u16() {
-                404 => Err(TimedError::NotFound(endpoint.to_string())),
-                401 | 403 => Err(TimedError::AuthenticationRequired),
-                _ => Err(TimedError::InvalidResponse(format!(
-                    "HTTP {status}: {text}"
-                ))),
-            };
-        }
 
-        let result = response.json::<R>().await.map_err(|e| {
-            TimedError::Serialization(serde_json::Error::io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                e,
-            )))
-        })?;
-
-        Ok(result)
+        self.send(Method::POST, endpoint, params, Some(&body)).await
     }
 
     /// Make a PATCH request to the API
@@ -208,86 +294,458 @@ impl TimedClient {
         endpoint: &str,
         data: &T,
     ) -> Result<R> {
-        if self.token.is_none() {
-            return Err(TimedError::AuthenticationRequired);
+        let body = serde_json::to_value(data)?;
+        debug!("Making PATCH request to {} with data: {}", endpoint, body);
+
+        let response = self
+            .send(Method::PATCH, endpoint, None, Some(&body))
+            .await?;
+        parse_json(response).await
+    }
+
+    /// Make a DELETE request to the API
+    pub async fn delete(&self, endpoint: &str) -> Result<()> {
+        debug!("Making DELETE request to {}", endpoint);
+
+        self.send(Method::DELETE, endpoint, None, None).await?;
+        Ok(())
+    }
+
+    /// Send a request, retrying transient failures according to the retry policy
+    async fn send(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: Option<&FilterParams>,
+        body: Option<&serde_json::Value>,
+    ) -> Result<Response> {
+        let mut token = self.current_token().await?;
+
+        // Any write may change what a cached listing returns
+        if method != Method::GET {
+            if let Some(cache) = &self.cache {
+                cache.invalidate(endpoint);
+            }
         }
 
-        let req = self
-            .http_client
-            .patch(format!("{}{}", self.base_url, endpoint))
-            .header(
-                header::AUTHORIZATION,
-                format!("Bearer {}", self.token.as_ref().unwrap()),
-            )
-            .header(header::CONTENT_TYPE, "application/vnd.api+json")
-            .json(data);
+        let url = format!("{}{}", self.base_url, endpoint);
+        let policy = &self.retry_policy;
+        let mut retry = 0;
+        let mut refreshed = false;
 
-        debug!(
-            "Making PATCH request to {} with data: {}",
-            endpoint,
-            serde_json::to_string(data).unwrap_or_else(|_| format!("{data:?}"))
-        );
-        let response = req.send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await?;
-            error!("API error ({}): {}", status, text);
-
-            return match status.as_u16() {
-                404 => Err(TimedError::NotFound(endpoint.to_string())),
-                401 | 403 => Err(TimedError::AuthenticationRequired),
-                _ => Err(TimedError::InvalidResponse(format!(
-                    "HTTP {status}: {text}"
-                ))),
+        loop {
+            let mut req = self
+                .http_client
+                .request(method.clone(), &url)
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .header(header::CONTENT_TYPE, "application/vnd.api+json");
+            if let Some(p) = params {
+                req = req.query(p);
+            }
+            if let Some(b) = body {
+                req = req.json(b);
+            }
+
+            let outcome = req.send().await;
+
+            // An expired token is replaced once, without counting as a retry
+            if let Ok(response) = &outcome {
+                if response.status() == StatusCode::UNAUTHORIZED
+                    && self.token_provider.is_some()
+                    && !refreshed
+                {
+                    info!("Token rejected by the API, refreshing it");
+                    token = self.refresh_token().await?;
+                    refreshed = true;
+                    continue;
+                }
+            }
+
+            let (reason, retry_after) = match &outcome {
+                Ok(response) if RetryPolicy::is_retryable_status(response.status()) => (
+                    format!("HTTP {}", response.status()),
+                    retry::retry_after(response.headers()),
+                ),
+                Err(e) if RetryPolicy::is_retryable_error(e) => (e.to_string(), None),
+                _ => return check_status(outcome?, endpoint).await,
             };
+
+            if retry >= policy.max_retries || !policy.allows_method(&method) {
+                return check_status(outcome?, endpoint).await;
+            }
+
+            let delay = policy.delay(retry, retry_after);
+            retry += 1;
+            warn!(
+                "{} {} failed ({}), retry {}/{} in {:?}",
+                method, endpoint, reason, retry, policy.max_retries, delay
+            );
+            tokio::time::sleep(delay).await;
         }
+    }
+
+    /// Stream all pages of a collection endpoint
+    ///
+    /// Requests `page[size]` resources per page (unless already set in
+    /// `params`) and keeps following `links.next` (or `meta.pagination`)
+    /// until the last page has been returned.
+    pub fn pages<'a, T: DeserializeOwned + 'a>(
+        &'a self,
+        endpoint: &'a str,
+        params: Option<&FilterParams>,
+    ) -> impl Stream<Item = Result<ResourcesResponse<T>>> + 'a {
+        let mut first = params.cloned().unwrap_or_default();
+        first
+            .custom
+            .entry(PAGE_SIZE_PARAM.to_string())
+            .or_insert_with(|| DEFAULT_PAGE_SIZE.to_string());
+        first
+            .custom
+            .entry(PAGE_NUMBER_PARAM.to_string())
+            .or_insert_with(|| "1".to_string());
 
-        let result = response.json::<R>().await.map_err(|e| {
-            TimedError::Serialization(serde_json::Error::io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                e,
-            )))
+        stream::try_unfold(Some(first), move |params| async move {
+            let Some(params) = params else {
+                return Ok(None);
+            };
+            let page: ResourcesResponse<T> = self.get(endpoint, Some(&params)).await?;
+            let next = next_page_params(&params, &page);
+            Ok(Some((page, next)))
+        })
+    }
+
+    /// Fetch every page of a collection endpoint and merge them into one response
+    ///
+    /// Included resources are merged across pages, with duplicates removed.
+    pub async fn get_all<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: Option<&FilterParams>,
+    ) -> Result<ResourcesResponse<T>> {
+        let pages = self.pages::<T>(endpoint, params);
+        futures_util::pin_mut!(pages);
+
+        let mut merged = ResourcesResponse {
+            data: Vec::new(),
+            included: None,
+            links: None,
+            meta: None,
+        };
+        let mut seen = HashSet::new();
+
+        while let Some(page) = pages.try_next().await? {
+            merged.data.extend(page.data);
+            for inc in page.included.unwrap_or_default() {
+                if seen.insert((inc.type_name.clone(), inc.id.clone())) {
+                    merged.included.get_or_insert_with(Vec::new).push(inc);
+                }
+            }
+            merged.meta = page.meta;
+        }
+
+        Ok(merged)
+    }
+
+    /// Stream all resources of type `T` across every page
+    pub fn stream<T: Resource + 'static>(
+        &self,
+        params: Option<&FilterParams>,
+    ) -> impl Stream<Item = Result<T>> + '_ {
+        self.pages::<T>(T::RESOURCE.as_path(), params)
+            .map_ok(|page| stream::iter(page.data.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// List all resources of type `T` across every page
+    pub async fn list_all<T: Resource>(
+        &self,
+        params: Option<&FilterParams>,
+    ) -> Result<ResourcesResponse<T>> {
+        self.get_all(T::RESOURCE.as_path(), params).await
+    }
+
+    /// List resources of type `T`
+    pub async fn list<T: Resource>(
+        &self,
+        params: Option<&FilterParams>,
+    ) -> Result<ResourcesResponse<T>> {
+        self.get(T::RESOURCE.as_path(), params).await
+    }
+
+    /// Fetch a single resource of type `T` by its ID
+    ///
+    /// Special IDs like `me` for users are passed through as-is.
+    pub async fn fetch<T: Resource>(
+        &self,
+        id: &str,
+        params: Option<&FilterParams>,
+    ) -> Result<ResourceResponse<T>> {
+        self.get(&format!("{}/{id}", T::RESOURCE.as_path()), params)
+            .await
+    }
+
+    /// Create a new resource and return it as stored by the API
+    pub async fn create<T: Resource>(&self, resource: &T) -> Result<ResourceResponse<T>> {
+        let body = serde_json::json!({ "data": resource });
+        self.post(T::RESOURCE.as_path(), &body).await
+    }
+
+    /// Update an existing resource and return it as stored by the API
+    pub async fn update<T: Resource>(&self, resource: &T) -> Result<ResourceResponse<T>> {
+        let id = resource.id().ok_or_else(|| {
+            TimedError::OperationNotPermitted(format!(
+                "cannot update {} without an ID",
+                T::RESOURCE.as_path()
+            ))
         })?;
+        let body = serde_json::json!({ "data": resource });
+        self.patch(&format!("{}/{id}", T::RESOURCE.as_path()), &body)
+            .await
+    }
 
-        Ok(result)
+    /// Delete a resource of type `T` by its ID
+    pub async fn destroy<T: Resource>(&self, id: &str) -> Result<()> {
+        self.delete(&format!("{}/{id}", T::RESOURCE.as_path()))
+            .await
     }
+}
 
-    /// Make a DELETE request to the API
-    pub async fn delete(&self, endpoint: &str) -> Result<()> {
-        if self.token.is_none() {
-            return Err(TimedError::AuthenticationRequired);
+/// Builder for a [`TimedClient`] with custom HTTP settings
+#[derive(Debug, Clone)]
+pub struct TimedClientBuilder {
+    base_url: String,
+    api_namespace: String,
+    token: Option<String>,
+    timeout: Duration,
+    user_agent: String,
+    root_certificates: Vec<Vec<u8>>,
+    identity: Option<Vec<u8>>,
+    proxy: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl TimedClientBuilder {
+    /// Start with the default settings for the given Timed instance
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_namespace: DEFAULT_API_NAMESPACE.to_string(),
+            token: None,
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            root_certificates: Vec::new(),
+            identity: None,
+            proxy: None,
+            retry_policy: RetryPolicy::default(),
         }
+    }
 
-        let req = self
-            .http_client
-            .delete(format!("{}{}", self.base_url, endpoint))
-            .header(
-                header::AUTHORIZATION,
-                format!("Bearer {}", self.token.as_ref().unwrap()),
-            );
+    /// Set the API namespace, `api/v1` by default
+    pub fn api_namespace(mut self, namespace: &str) -> Self {
+        self.api_namespace = namespace.trim_matches('/').to_string();
+        self
+    }
 
-        debug!("Making DELETE request to {}", endpoint);
-        let response = req.send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await?;
-            error!("API error ({}): {}", status, text);
-
-            return match status.as_u16() {
-                404 => Err(TimedError::NotFound(endpoint.to_string())),
-                401 | 403 => Err(TimedError::AuthenticationRequired),
-                _ => Err(TimedError::InvalidResponse(format!(
-                    "HTTP {status}: {text}"
-                ))),
-            };
+    /// Set the authentication token
+    pub fn token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Set the timeout of a single request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the `User-Agent` header sent with every request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Trust the root certificates of a PEM bundle in addition to the system ones
+    pub fn add_root_certificates_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Authenticate with a client certificate and private key, both PEM encoded
+    pub fn identity_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some(pem.into());
+        self
+    }
+
+    /// Send all requests through a proxy
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Set the policy used to retry failed requests
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Build the underlying HTTP client with the configured settings
+    ///
+    /// Also useful for talking to related services, like the SSO provider,
+    /// through the same proxy and certificates.
+    pub fn build_http_client(&self) -> Result<Client> {
+        let mut builder = ClientBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent);
+
+        for pem in &self.root_certificates {
+            let certificates = Certificate::from_pem_bundle(pem)
+                .map_err(|e| TimedError::ClientConfig(format!("invalid root certificate: {e}")))?;
+            if certificates.is_empty() {
+                return Err(TimedError::ClientConfig(
+                    "no root certificate found in PEM data".to_string(),
+                ));
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
         }
 
-        Ok(())
+        if let Some(pem) = &self.identity {
+            let identity = Identity::from_pem(pem)
+                .map_err(|e| TimedError::ClientConfig(format!("invalid client identity: {e}")))?;
+            builder = builder.identity(identity);
+        }
+
+        if let Some(url) = &self.proxy {
+            let proxy = Proxy::all(url)
+                .map_err(|e| TimedError::ClientConfig(format!("invalid proxy {url}: {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        builder
+            .build()
+            .map_err(|e| TimedError::ClientConfig(e.to_string()))
+    }
+
+    /// Build the client
+    pub fn build(self) -> Result<TimedClient> {
+        let http_client = self.build_http_client()?;
+
+        Ok(TimedClient {
+            http_client,
+            base_url: format!("{}/{}/", self.base_url, self.api_namespace),
+            token: RwLock::new(self.token),
+            token_provider: None,
+            retry_policy: self.retry_policy,
+            cache: None,
+        })
     }
 }
 
+/// Work out the parameters for the page after `page`, if there is one
+fn next_page_params<T>(
+    current: &FilterParams,
+    page: &ResourcesResponse<T>,
+) -> Option<FilterParams> {
+    if page.data.is_empty() {
+        return None;
+    }
+
+    let current_number = current
+        .custom
+        .get(PAGE_NUMBER_PARAM)
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(1);
+
+    let next_number = if let Some(next) = page.links.as_ref().and_then(|l| l.next.as_deref()) {
+        // Only take the page number from the link, so we never send the
+        // token to whatever host the server thinks it is reachable at
+        Url::parse(next)
+            .ok()
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == PAGE_NUMBER_PARAM)
+                    .and_then(|(_, value)| value.parse::<u64>().ok())
+            })
+            .unwrap_or(current_number + 1)
+    } else {
+        let pagination = page.meta.as_ref()?.get("pagination")?;
+        let number = pagination.get("page")?.as_u64()?;
+        let pages = pagination.get("pages")?.as_u64()?;
+        if number >= pages {
+            return None;
+        }
+        number + 1
+    };
+
+    if next_number <= current_number {
+        return None;
+    }
+
+    let mut next = current.clone();
+    next.custom
+        .insert(PAGE_NUMBER_PARAM.to_string(), next_number.to_string());
+    Some(next)
+}
+
+/// Build the cache key of a GET request
+fn cache_key(base_url: &str, endpoint: &str, params: Option<&FilterParams>) -> String {
+    // Sort the parameters, `custom` is a `HashMap` with an arbitrary order
+    let query = params
+        .and_then(|p| serde_json::to_value(p).ok())
+        .and_then(|v| match v {
+            serde_json::Value::Object(fields) => {
+                let sorted: BTreeMap<String, serde_json::Value> = fields.into_iter().collect();
+                serde_json::to_string(&sorted).ok()
+            }
+            other => Some(other.to_string()),
+        })
+        .unwrap_or_default();
+    format!("{base_url}{endpoint}?{query}")
+}
+
+/// Turn an unsuccessful response into the matching error
+async fn check_status(response: Response, endpoint: &str) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let text = response.text().await?;
+    error!("API error ({}): {}", status, text);
+
+    let errors = serde_json::from_str::<ErrorResponse>(&text)
+        .map(|r| r.errors)
+        .unwrap_or_default();
+
+    match status.as_u16() {
+        404 => Err(TimedError::NotFound(endpoint.to_string())),
+        401 => Err(TimedError::AuthenticationRequired),
+        403 => Err(TimedError::Forbidden(if errors.is_empty() {
+            format!("not allowed to access {endpoint}")
+        } else {
+            join_errors(&errors)
+        })),
+        _ if !errors.is_empty() => Err(TimedError::Api {
+            status: status.as_u16(),
+            errors,
+        }),
+        _ => Err(TimedError::InvalidResponse(format!(
+            "HTTP {status}: {text}"
+        ))),
+    }
+}
+
+/// Deserialize a JSON response body
+async fn parse_json<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T> {
+    response.json::<T>().await.map_err(|e| {
+        TimedError::Serialization(serde_json::Error::io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            e,
+        )))
+    })
+}
+
 /// API resource endpoints
 #[derive(Debug, Clone, Copy)]
 pub enum ApiResource {
@@ -341,7 +799,7 @@ mod tests {
 
     #[test]
     fn test_client_creation() {
-        let client = TimedClient::new("https://example.com", "api/v1", None);
+        let client = TimedClient::new("https://example.com", "api/v1", None).unwrap();
         assert_eq!(client.base_url, "https://example.com/api/v1/");
         assert!(!client.has_token());
     }
@@ -352,10 +810,59 @@ mod tests {
             "https://example.com",
             "api/v1",
             Some("token123".to_string()),
-        );
+        )
+        .unwrap();
         assert!(client.has_token());
     }
 
+    #[test]
+    fn test_builder_settings() {
+        let client = TimedClient::builder("https://example.com/")
+            .api_namespace("/api/v2/")
+            .timeout(Duration::from_secs(120))
+            .token("token123".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "https://example.com/api/v2/");
+        assert!(client.has_token());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_settings() {
+        let result = TimedClient::builder("https://example.com")
+            .add_root_certificates_pem("not a certificate")
+            .build();
+        assert!(matches!(result, Err(TimedError::ClientConfig(_))));
+
+        let result = TimedClient::builder("https://example.com")
+            .proxy("::not a url::")
+            .build();
+        assert!(matches!(result, Err(TimedError::ClientConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_builder_sends_user_agent() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/v1/users/me")
+            .match_header(
+                "user-agent",
+                mockito::Matcher::Regex(r"^acme timedctl/\d+\.\d+\.\d+".to_string()),
+            )
+            .with_body(r#"{"data": {}}"#)
+            .create_async()
+            .await;
+
+        let client = TimedClient::builder(&server.url())
+            .token("token".to_string())
+            .user_agent(format!("acme {DEFAULT_USER_AGENT}"))
+            .build()
+            .unwrap();
+        let _: serde_json::Value = client.get("users/me", None).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
     #[test]
     fn test_api_resource_paths() {
         assert_eq!(ApiResource::Users.as_path(), "users");
@@ -385,14 +892,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_unauthorized() {
-        let client = TimedClient::new("https://example.com", "api/v1", None);
+        let client = TimedClient::new("https://example.com", "api/v1", None).unwrap();
         let result: Result<serde_json::Value> = client.get("test", None).await;
         assert!(matches!(result, Err(TimedError::AuthenticationRequired)));
     }
 
     #[tokio::test]
     async fn test_post_unauthorized() {
-        let client = TimedClient::new("https://example.com", "api/v1", None);
+        let client = TimedClient::new("https://example.com", "api/v1", None).unwrap();
         let data = serde_json::json!({});
         let result: Result<serde_json::Value> = client.post("test", &data).await;
         assert!(matches!(result, Err(TimedError::AuthenticationRequired)));
@@ -400,11 +907,389 @@ mod tests {
 
     #[tokio::test]
     async fn test_delete_unauthorized() {
-        let client = TimedClient::new("https://example.com", "api/v1", None);
+        let client = TimedClient::new("https://example.com", "api/v1", None).unwrap();
         let result = client.delete("test").await;
         assert!(matches!(result, Err(TimedError::AuthenticationRequired)));
     }
 
+    #[tokio::test]
+    async fn test_typed_list_unauthorized() {
+        let client = TimedClient::new("https://example.com", "api/v1", None).unwrap();
+        let result = client.list::<models::Report>(None).await;
+        assert!(matches!(result, Err(TimedError::AuthenticationRequired)));
+    }
+
+    #[tokio::test]
+    async fn test_update_requires_id() {
+        let client =
+            TimedClient::new("https://example.com", "api/v1", Some("token".into())).unwrap();
+        let customer = models::Customer {
+            id: None,
+            type_name: "customers".to_string(),
+            attributes: models::CustomerAttributes {
+                name: "Acme".to_string(),
+                archived: false,
+            },
+            relationships: None,
+        };
+        let result = client.update(&customer).await;
+        assert!(matches!(result, Err(TimedError::OperationNotPermitted(_))));
+    }
+
+    fn report_json(id: &str, task_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "type": "reports",
+            "attributes": {"comment": "", "date": "2025-01-06", "duration": "01:00:00",
+                           "review": false, "not-billable": false},
+            "relationships": {"task": {"data": {"type": "tasks", "id": task_id}}}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_all_follows_next_links() {
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let task = serde_json::json!({"type": "tasks", "id": "7", "attributes": {"name": "Dev"}});
+
+        let first = server
+            .mock("GET", "/api/v1/reports")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("page[number]".into(), "1".into()),
+                Matcher::UrlEncoded("page[size]".into(), DEFAULT_PAGE_SIZE.to_string()),
+            ]))
+            .with_body(
+                serde_json::json!({
+                    "data": [report_json("1", "7"), report_json("2", "7")],
+                    "included": [task],
+                    "links": {"next": "https://elsewhere.example/api/v1/reports?page%5Bnumber%5D=2"}
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", "/api/v1/reports")
+            .match_query(Matcher::UrlEncoded("page[number]".into(), "2".into()))
+            .with_body(
+                serde_json::json!({
+                    "data": [report_json("3", "7")],
+                    "included": [task],
+                    "links": {"next": null}
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let client = TimedClient::new(&server.url(), "api/v1", Some("token".to_string())).unwrap();
+        let response = client.list_all::<models::Report>(None).await.unwrap();
+
+        first.assert_async().await;
+        second.assert_async().await;
+        assert_eq!(response.data.len(), 3);
+        assert_eq!(response.included.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_uses_meta_pagination() {
+        use futures_util::TryStreamExt;
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        for page in 1..=2 {
+            server
+                .mock("GET", "/api/v1/reports")
+                .match_query(Matcher::UrlEncoded("page[number]".into(), page.to_string()))
+                .with_body(
+                    serde_json::json!({
+                        "data": [report_json(&page.to_string(), "7")],
+                        "meta": {"pagination": {"page": page, "pages": 2, "count": 2}}
+                    })
+                    .to_string(),
+                )
+                .expect(1)
+                .create_async()
+                .await;
+        }
+
+        let client = TimedClient::new(&server.url(), "api/v1", Some("token".to_string())).unwrap();
+        let reports: Vec<models::Report> = client.stream(None).try_collect().await.unwrap();
+
+        let ids: Vec<_> = reports.iter().filter_map(|r| r.id.as_deref()).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+
+    fn retrying_client(url: &str) -> TimedClient {
+        let mut client = TimedClient::new(url, "api/v1", Some("token".to_string())).unwrap();
+        client.set_retry_policy(RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            retry_non_idempotent: false,
+        });
+        client
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_after_service_unavailable() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/api/v1/users/me")
+            .with_status(503)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", "/api/v1/users/me")
+            .with_body(r#"{"data": {"id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = retrying_client(&server.url());
+        let result: serde_json::Value = client.get("users/me", None).await.unwrap();
+
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(result["data"]["id"], "1");
+    }
+
+    #[tokio::test]
+    async fn test_get_gives_up_after_max_retries() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/v1/reports")
+            .with_status(502)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = retrying_client(&server.url());
+        let result: Result<serde_json::Value> = client.get("reports", None).await;
+
+        mock.assert_async().await;
+        assert!(matches!(result, Err(TimedError::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried_by_default() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/v1/reports")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = retrying_client(&server.url());
+        let result: Result<serde_json::Value> =
+            client.post("reports", &serde_json::json!({})).await;
+
+        mock.assert_async().await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cache_key_ignores_parameter_order() {
+        let build = |pairs: &[(&str, &str)]| {
+            let mut params = FilterParams {
+                user: Some("7".to_string()),
+                ..Default::default()
+            };
+            for (key, value) in pairs {
+                params.custom.insert(key.to_string(), value.to_string());
+            }
+            cache_key("https://example.com/api/v1/", "tasks", Some(&params))
+        };
+
+        let pairs = [
+            ("page[number]", "2"),
+            ("page[size]", "100"),
+            ("archived", "0"),
+            ("project", "3"),
+            ("ordering", "name"),
+        ];
+        let mut reversed = pairs;
+        reversed.reverse();
+
+        let key = build(&pairs);
+        for _ in 0..10 {
+            assert_eq!(build(&reversed), key);
+        }
+        assert_ne!(build(&pairs[1..]), key);
+    }
+
+    #[tokio::test]
+    async fn test_cached_get_skips_second_request() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/v1/users/me")
+            .with_body(r#"{"data": {"id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client =
+            TimedClient::new(&server.url(), "api/v1", Some("token".to_string())).unwrap();
+        client.set_cache(ResponseCache::in_memory());
+        for _ in 0..2 {
+            let result: serde_json::Value = client.get("users/me", None).await.unwrap();
+            assert_eq!(result["data"]["id"], "1");
+        }
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors_are_parsed() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/v1/reports")
+            .with_status(400)
+            .with_body(
+                serde_json::json!({"errors": [{
+                    "status": "400",
+                    "code": "invalid",
+                    "detail": "Report overlaps with an existing report",
+                    "source": {"pointer": "/data/attributes/duration"}
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let client = TimedClient::new(&server.url(), "api/v1", Some("token".to_string())).unwrap();
+        let err = client
+            .post::<_, serde_json::Value>("reports", &serde_json::json!({}))
+            .await
+            .unwrap_err();
+
+        match &err {
+            TimedError::Api { status, errors } => {
+                assert_eq!(*status, 400);
+                assert_eq!(errors[0].code.as_deref(), Some("invalid"));
+                assert_eq!(errors[0].field(), Some("duration"));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert_eq!(
+            err.to_string(),
+            "Request rejected (HTTP 400): duration: Report overlaps with an existing report (invalid)"
+        );
+    }
+
+    #[test]
+    fn test_multiple_api_errors_render_as_list() {
+        let err = TimedError::Api {
+            status: 400,
+            errors: vec![
+                models::ApiErrorObject {
+                    detail: Some("This field is required.".to_string()),
+                    source: Some(models::ApiErrorSource {
+                        pointer: Some("/data/relationships/task".to_string()),
+                        parameter: None,
+                    }),
+                    ..Default::default()
+                },
+                models::ApiErrorObject {
+                    title: Some("Invalid date".to_string()),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(
+            err.to_string(),
+            "Request rejected (HTTP 400):\n  - task: This field is required.\n  - Invalid date"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_is_not_authentication() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("PATCH", "/api/v1/reports/1")
+            .with_status(403)
+            .with_body(r#"{"errors": [{"detail": "Report is verified"}]}"#)
+            .create_async()
+            .await;
+
+        let client = TimedClient::new(&server.url(), "api/v1", Some("token".to_string())).unwrap();
+        let result = client
+            .patch::<_, serde_json::Value>("reports/1", &serde_json::json!({}))
+            .await;
+
+        assert!(matches!(result, Err(TimedError::Forbidden(msg)) if msg == "Report is verified"));
+    }
+
+    struct RotatingToken(std::sync::atomic::AtomicUsize);
+
+    impl TokenProvider for RotatingToken {
+        fn token(&self) -> futures_util::future::BoxFuture<'_, Result<String>> {
+            Box::pin(async { Ok("old".to_string()) })
+        }
+
+        fn refresh(&self) -> futures_util::future::BoxFuture<'_, Result<String>> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Box::pin(async { Ok("new".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_refreshes_token_and_replays() {
+        let mut server = mockito::Server::new_async().await;
+        let rejected = server
+            .mock("GET", "/api/v1/users/me")
+            .match_header("authorization", "Bearer old")
+            .with_status(401)
+            .expect(1)
+            .create_async()
+            .await;
+        let accepted = server
+            .mock("GET", "/api/v1/users/me")
+            .match_header("authorization", "Bearer new")
+            .with_body(r#"{"data": {"id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = Arc::new(RotatingToken(Default::default()));
+        let mut client = TimedClient::new(&server.url(), "api/v1", None).unwrap();
+        client.set_token_provider(provider.clone());
+
+        let result: serde_json::Value = client.get("users/me", None).await.unwrap();
+
+        rejected.assert_async().await;
+        accepted.assert_async().await;
+        assert_eq!(result["data"]["id"], "1");
+        assert_eq!(client.token().as_deref(), Some("new"));
+        assert_eq!(provider.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_token_is_refreshed_only_once() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("DELETE", "/api/v1/reports/1")
+            .with_status(401)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let provider = Arc::new(RotatingToken(Default::default()));
+        let mut client =
+            TimedClient::new(&server.url(), "api/v1", Some("old".to_string())).unwrap();
+        client.set_token_provider(provider.clone());
+
+        let result = client.delete("reports/1").await;
+
+        mock.assert_async().await;
+        assert!(matches!(result, Err(TimedError::AuthenticationRequired)));
+        assert_eq!(provider.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
     // Simplified test that doesn't require mockito
     #[test]
     fn test_get_request_url_formation() {
@@ -412,7 +1297,8 @@ mod tests {
             "https://example.com",
             "api/v1",
             Some("token123".to_string()),
-        );
+        )
+        .unwrap();
         let url = format!("{}{}", client.base_url(), "users");
         assert_eq!(url, "https://example.com/api/v1/users");
     }
@@ -424,7 +1310,8 @@ mod tests {
             "https://example.com",
             "api/v1",
             Some("token123".to_string()),
-        );
+        )
+        .unwrap();
         let url = format!("{}{}", client.base_url(), "reports");
         assert_eq!(url, "https://example.com/api/v1/reports");
     }
@@ -1,18 +1,23 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use tracing::{debug, error, info};
+use clap::{builder::TypedValueParser, Args, Parser, Subcommand};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 mod auth;
 mod config;
 mod handlers;
+mod output;
 
 use auth::AuthClient;
-use config::{get_default_config_path, TimedConfig};
-use handlers::{activity, config as config_handlers, data, get_overtime, report};
-use libtimed::TimedClient;
+use config::{get_default_config_path, RoundingConfig, TimedConfig};
+use handlers::{
+    absence, activity, attendance, cache as cache_handlers, config as config_handlers, data,
+    dates::DateRange, report, show_overtime, statistics,
+};
+use libtimed::cache::{default_cache_dir, user_cache_dir, ResponseCache};
 
 #[derive(Parser)]
 #[command(
@@ -30,14 +35,39 @@ struct Cli {
     #[arg(long)]
     no_renew_token: bool,
 
+    /// Don't use cached customers, projects, tasks and users
+    #[arg(long)]
+    no_cache: bool,
+
     /// Verbose output
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
 
+    /// Output format of read commands
+    #[arg(
+        short = 'o',
+        long,
+        global = true,
+        value_name = "FORMAT",
+        value_parser = clap::builder::EnumValueParser::<output::OutputFormat>::new().map(OutputArg::Format),
+        default_value = "table"
+    )]
+    output: OutputArg,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Value of `-o/--output`
+///
+/// `export reports` takes a file there instead of a format. Both share the
+/// argument, as clap hands global values down to subcommands by ID.
+#[derive(Debug, Clone)]
+enum OutputArg {
+    Format(output::OutputFormat),
+    File(PathBuf),
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Force renew token
@@ -70,9 +100,46 @@ enum Commands {
     #[command(name = "activity", alias = "ac")]
     Activity(ActivityCommand),
 
+    /// Clock in and out and manage attendances
+    #[command(name = "attendance", alias = "att")]
+    Attendance(AttendanceCommand),
+
+    /// Manage absences like vacation or sick days
+    #[command(name = "absence", alias = "abs")]
+    Absence(AbsenceCommand),
+
+    /// Export reports to a file
+    #[command(name = "export", alias = "x")]
+    Export(ExportCommand),
+
+    /// Show time statistics
+    #[command(name = "stats", alias = "statistics", alias = "st")]
+    Stats(StatsCommand),
+
     /// Manage configuration
     #[command(name = "config", alias = "cfg", alias = "conf")]
     Config(ConfigCommand),
+
+    /// Manage the local response cache
+    #[command(name = "cache")]
+    Cache(CacheCommand),
+}
+
+#[derive(Parser)]
+struct CacheCommand {
+    #[command(subcommand)]
+    command: CacheCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommands {
+    /// Remove all cached responses
+    #[command(name = "clear")]
+    Clear,
+
+    /// Show cache directory path
+    #[command(name = "path")]
+    Path,
 }
 
 #[derive(Parser)]
@@ -145,9 +212,61 @@ enum GetCommands {
         #[arg(long)]
         to: Option<String>,
 
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["date", "from", "to"])]
+        range: Option<DateRange>,
+
         /// Use interactive mode to prompt for date selection
         #[arg(short = 'i', long)]
         interactive: bool,
+
+        /// Customer name (or a unique part of it) or ID
+        #[arg(long)]
+        customer: Option<String>,
+
+        /// Project name (or a unique part of it) or ID
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Task name (or a unique part of it) or ID
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Only reports that need review (or, with false, that don't)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        review: Option<bool>,
+
+        /// Only reports that are not billable (or, with false, that are)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        not_billable: Option<bool>,
+
+        /// Only verified reports (or, with false, unverified ones)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        verified: Option<bool>,
+
+        /// Only rejected reports (or, with false, ones that aren't)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        rejected: Option<bool>,
+
+        /// Only billed reports (or, with false, unbilled ones)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        billed: Option<bool>,
+
+        /// Only reports whose comment contains this text (case-insensitive)
+        #[arg(long, value_name = "TEXT")]
+        comment_contains: Option<String>,
+
+        /// Group by day, week, month, customer, project, task or user, nestable like customer,project
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            conflicts_with_all = ["columns", "template"]
+        )]
+        group_by: Vec<report::ReportGroup>,
+
+        #[command(flatten)]
+        list: ListArgs,
     },
 
     /// Get activities
@@ -165,9 +284,16 @@ enum GetCommands {
         #[arg(long)]
         to: Option<String>,
 
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["date", "from", "to"])]
+        range: Option<DateRange>,
+
         /// Include activities from all users, not just current user
         #[arg(short = 'A', long)]
         all_users: bool,
+
+        #[command(flatten)]
+        list: ListArgs,
     },
 
     /// Get raw data for building custom scripts
@@ -181,15 +307,35 @@ struct DataCommand {
     command: DataCommands,
 }
 
+#[derive(Debug, Args)]
+struct ListArgs {
+    /// Fields to show, comma-separated (e.g. date,task,duration,comment)
+    #[arg(long, value_delimiter = ',', conflicts_with = "template")]
+    columns: Option<Vec<String>>,
+
+    /// Order of the rows
+    #[arg(long, value_enum, default_value_t)]
+    sort_by: handlers::ListSort,
+
+    /// Print one line per entry instead, like '{date} {duration} {task}'
+    #[arg(long)]
+    template: Option<String>,
+}
+
+impl ListArgs {
+    fn view(&self) -> output::ListView {
+        output::ListView {
+            columns: self.columns.clone(),
+            template: self.template.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum DataCommands {
     /// Get customers
     #[command(name = "customers")]
-    Customers {
-        /// Output format
-        #[arg(long, default_value = "json")]
-        format: String,
-    },
+    Customers,
 
     /// Get projects
     #[command(name = "projects")]
@@ -205,10 +351,6 @@ enum DataCommands {
         /// Include archived projects
         #[arg(long)]
         archived: bool,
-
-        /// Output format
-        #[arg(long, default_value = "json")]
-        format: String,
     },
 
     /// Get tasks
@@ -233,10 +375,6 @@ enum DataCommands {
         /// Include archived tasks
         #[arg(long)]
         archived: bool,
-
-        /// Output format
-        #[arg(long, default_value = "json")]
-        format: String,
     },
 }
 
@@ -300,7 +438,7 @@ enum AddCommands {
         #[arg(long)]
         description: Option<String>,
 
-        /// Duration (format: HH:MM or decimal hours)
+        /// Duration, like 1h30m, 90m, 1.25, 1:30, 09:00-12:30 or 1h+20m
         #[arg(long)]
         duration: Option<String>,
 
@@ -320,6 +458,10 @@ enum AddCommands {
         #[arg(long)]
         not_billable: bool,
 
+        /// Keep the exact duration instead of rounding it
+        #[arg(long)]
+        no_round: bool,
+
         /// Use non-interactive mode (for scripting)
         #[arg(short = 'n', long)]
         non_interactive: bool,
@@ -334,8 +476,8 @@ struct EditCommand {
 
 #[derive(Debug, Subcommand)]
 enum EditCommands {
-    /// Edit report(s)
-    #[command(name = "report", alias = "r")]
+    /// Edit report(s), or all reports matching a filter with --bulk
+    #[command(name = "report", alias = "reports", alias = "r")]
     Report {
         /// Date to edit reports for
         #[arg(long)]
@@ -344,9 +486,83 @@ enum EditCommands {
         /// Use non-interactive mode (for scripting)
         #[arg(short = 'n', long)]
         non_interactive: bool,
+
+        /// Keep the exact duration instead of rounding it
+        #[arg(long, conflicts_with = "bulk")]
+        no_round: bool,
+
+        /// Update all reports matching the filters at once
+        #[arg(long)]
+        bulk: bool,
+
+        #[command(flatten)]
+        bulk_args: BulkEditArgs,
     },
 }
 
+#[derive(Debug, Args)]
+struct BulkEditArgs {
+    /// Start date of the reports to update (format: YYYY-MM-DD)
+    #[arg(long, requires = "bulk")]
+    from: Option<String>,
+
+    /// End date of the reports to update (format: YYYY-MM-DD)
+    #[arg(long, requires = "bulk")]
+    to: Option<String>,
+
+    /// Only update reports of this user (username or ID)
+    #[arg(long, requires = "bulk", conflicts_with = "all_users")]
+    user: Option<String>,
+
+    /// Update reports of all users, not just your own
+    #[arg(short = 'A', long, requires = "bulk")]
+    all_users: bool,
+
+    /// Only update reports of this customer (name or ID)
+    #[arg(long, requires = "bulk")]
+    customer: Option<String>,
+
+    /// Only update reports of this project (name or ID)
+    #[arg(long, requires = "bulk")]
+    project: Option<String>,
+
+    /// Only update reports of this task (name or ID)
+    #[arg(long, requires = "bulk")]
+    task: Option<String>,
+
+    /// Only update reports whose review flag is set (true) or not set (false)
+    #[arg(long, requires = "bulk", value_name = "BOOL")]
+    in_review: Option<bool>,
+
+    /// Move the reports to this task, as task, project/task or customer/project/task
+    #[arg(long, requires = "bulk")]
+    set_task: Option<String>,
+
+    /// Set or clear the review flag
+    #[arg(long, requires = "bulk", value_name = "BOOL")]
+    review: Option<bool>,
+
+    /// Set or clear the not billable flag
+    #[arg(long, requires = "bulk", value_name = "BOOL")]
+    not_billable: Option<bool>,
+
+    /// Verify the reports, or remove the verification
+    #[arg(long, requires = "bulk", value_name = "BOOL")]
+    verified: Option<bool>,
+
+    /// Replace the comment of the reports
+    #[arg(long, requires = "bulk")]
+    comment: Option<String>,
+
+    /// Don't ask for confirmation
+    #[arg(short = 'y', long, requires = "bulk")]
+    yes: bool,
+
+    /// Only list the reports that would be updated
+    #[arg(long, requires = "bulk")]
+    dry_run: bool,
+}
+
 #[derive(Parser)]
 struct ActivityCommand {
     #[command(subcommand)]
@@ -378,7 +594,7 @@ enum ActivityCommands {
         #[arg(long)]
         show_archived: bool,
 
-        /// Start time for the activity (format: HH:MM)
+        /// Start time for the activity (format: HH:MM or just the hour)
         #[arg(long)]
         start_time: Option<String>,
 
@@ -391,6 +607,54 @@ enum ActivityCommands {
     #[command(name = "stop", alias = "end", alias = "finish")]
     Stop,
 
+    /// Stop the current activity and start another at the same minute
+    #[command(name = "switch", alias = "sw")]
+    Switch {
+        /// Task alias from the config, or task as task, project/task or customer/project/task
+        /// matched by partial names
+        task: Option<String>,
+
+        /// Comment for the new activity (asked for in interactive mode)
+        #[arg(short = 'm', long)]
+        comment: Option<String>,
+
+        /// Include archived customers, projects and tasks
+        #[arg(long)]
+        show_archived: bool,
+
+        /// Use non-interactive mode (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+
+    /// Change the start time, comment, task or flags of the current activity
+    #[command(name = "amend", alias = "fix")]
+    Amend {
+        /// New start time (format: HH:MM or just the hour)
+        #[arg(long)]
+        start: Option<String>,
+
+        /// New comment
+        #[arg(short = 'm', long)]
+        comment: Option<String>,
+
+        /// New task as task, project/task or customer/project/task
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Include archived customers, projects and tasks
+        #[arg(long)]
+        show_archived: bool,
+
+        /// Mark for review (or, with false, unmark)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        review: Option<bool>,
+
+        /// Mark as not billable (or, with false, as billable)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        not_billable: Option<bool>,
+    },
+
     /// Show current activity
     #[command(name = "show", alias = "s", alias = "get", alias = "info")]
     Show {
@@ -415,9 +679,386 @@ enum ActivityCommands {
         date: Option<String>,
     },
 
-    /// Generate the timesheet of the current activities
+    /// Book finished activities as reports, grouped by task and comment
     #[command(name = "generate-timesheet", alias = "gts", alias = "ts")]
-    GenerateTimesheet,
+    GenerateTimesheet {
+        /// Date of the activities (defaults to today)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Start date of the activities (format: YYYY-MM-DD)
+        #[arg(long, conflicts_with = "date")]
+        from: Option<String>,
+
+        /// End date of the activities (format: YYYY-MM-DD)
+        #[arg(long, conflicts_with = "date")]
+        to: Option<String>,
+
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["date", "from", "to"])]
+        range: Option<DateRange>,
+
+        /// Only show the reports that would be created
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Keep the exact durations instead of rounding them
+        #[arg(long)]
+        no_round: bool,
+
+        /// Book all entries without asking (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+}
+
+#[derive(Parser)]
+struct AttendanceCommand {
+    #[command(subcommand)]
+    command: AttendanceCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum AttendanceCommands {
+    /// Clock in by opening a new attendance
+    #[command(name = "in")]
+    In {
+        /// Start time (format: HH:MM, defaults to now), or a span like 8-12 to add a finished attendance
+        #[arg(long)]
+        time: Option<String>,
+
+        /// Date of the attendance (format: YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Clock out by closing the open attendance
+    #[command(name = "out")]
+    Out {
+        /// End time (format: HH:MM, defaults to now)
+        #[arg(long)]
+        time: Option<String>,
+
+        /// Date of the attendance (format: YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// List attendances
+    #[command(name = "list", alias = "ls", alias = "get")]
+    List {
+        /// Date to list attendances for
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Start date for attendances (format: YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End date for attendances (format: YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["date", "from", "to"])]
+        range: Option<DateRange>,
+
+        /// Include attendances from all users, not just current user
+        #[arg(short = 'A', long)]
+        all_users: bool,
+    },
+
+    /// Edit an attendance
+    #[command(name = "edit", alias = "e")]
+    Edit {
+        /// ID of the attendance to edit
+        id: Option<String>,
+
+        /// Date to pick the attendance from, or the new date when an ID is given
+        #[arg(long)]
+        date: Option<String>,
+
+        /// New start time (format: HH:MM), or a span like 8-12 for both times
+        #[arg(long)]
+        from_time: Option<String>,
+
+        /// New end time (format: HH:MM)
+        #[arg(long)]
+        to_time: Option<String>,
+
+        /// Use non-interactive mode (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+
+    /// Delete an attendance
+    #[command(name = "rm", alias = "delete", alias = "d", alias = "remove")]
+    Rm {
+        /// ID of the attendance to delete
+        id: Option<String>,
+
+        /// Date to pick the attendance from (defaults to today)
+        #[arg(long, conflicts_with = "id")]
+        date: Option<String>,
+
+        /// Use non-interactive mode (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+}
+
+#[derive(Parser)]
+struct AbsenceCommand {
+    #[command(subcommand)]
+    command: AbsenceCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum AbsenceCommands {
+    /// List absences
+    #[command(name = "list", alias = "ls", alias = "get")]
+    List {
+        /// Date to list absences for
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Start date for absences (format: YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End date for absences (format: YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["date", "from", "to"])]
+        range: Option<DateRange>,
+
+        /// Include absences from all users, not just current user
+        #[arg(short = 'A', long)]
+        all_users: bool,
+    },
+
+    /// Add an absence, or one per working day with --from and --to
+    #[command(name = "add", alias = "a", alias = "create")]
+    Add {
+        /// Absence type name (or a unique part of it) or ID
+        #[arg(long = "type", short = 't')]
+        absence_type: Option<String>,
+
+        /// Date of the absence (format: YYYY-MM-DD, defaults to today)
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        date: Option<String>,
+
+        /// First day of the absence (format: YYYY-MM-DD)
+        #[arg(long, requires = "to")]
+        from: Option<String>,
+
+        /// Last day of the absence (format: YYYY-MM-DD)
+        #[arg(long, requires = "from")]
+        to: Option<String>,
+
+        /// Comment for the absence
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// Don't ask for confirmation when adding several days
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Use non-interactive mode (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+
+    /// Delete an absence
+    #[command(name = "delete", alias = "d", alias = "rm", alias = "remove")]
+    Delete {
+        /// ID of the absence to delete
+        id: Option<String>,
+
+        /// Date to delete the absence for (defaults to today)
+        #[arg(long, conflicts_with = "id")]
+        date: Option<String>,
+
+        /// Use non-interactive mode (for scripting)
+        #[arg(short = 'n', long)]
+        non_interactive: bool,
+    },
+
+    /// List available absence types
+    #[command(name = "types", alias = "t")]
+    Types,
+}
+
+#[derive(Parser)]
+struct ExportCommand {
+    #[command(subcommand)]
+    command: ExportCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum ExportCommands {
+    /// Export reports as a CSV, XLSX or ODS file
+    #[command(name = "reports", alias = "report", alias = "r")]
+    Reports {
+        /// Start date (format: YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End date (format: YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+        #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["from", "to"])]
+        range: Option<DateRange>,
+
+        /// Username or user ID
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Customer name (or a unique part of it) or ID
+        #[arg(long)]
+        customer: Option<String>,
+
+        /// Project name (or a unique part of it) or ID
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Task name (or a unique part of it) or ID
+        #[arg(long)]
+        task: Option<String>,
+
+        /// File format
+        #[arg(long, default_value = "xlsx", value_parser = ["csv", "xlsx", "ods"])]
+        format: String,
+
+        /// File or directory to write to (defaults to the file name sent by the server)
+        #[arg(
+            id = "output",
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            value_parser = clap::builder::PathBufValueParser::new().map(OutputArg::File)
+        )]
+        file: Option<OutputArg>,
+    },
+}
+
+#[derive(Parser)]
+struct StatsCommand {
+    #[command(subcommand)]
+    command: StatsCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum StatsCommands {
+    /// Time per user and year
+    #[command(name = "year", alias = "y")]
+    Year {
+        /// Year to show (defaults to the current year)
+        #[arg(long)]
+        year: Option<i32>,
+
+        #[command(flatten)]
+        args: StatsArgs,
+    },
+
+    /// Time per user and month
+    #[command(name = "month", alias = "m")]
+    Month {
+        /// Year to show (defaults to the current year)
+        #[arg(long)]
+        year: Option<i32>,
+
+        /// Month to show, 1 to 12 (defaults to the current month)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=12))]
+        month: Option<u32>,
+
+        #[command(flatten)]
+        args: StatsArgs,
+    },
+
+    /// Time per task
+    #[command(name = "task", alias = "tasks")]
+    Task(StatsArgs),
+
+    /// Time per user
+    #[command(name = "user", alias = "users")]
+    User(StatsArgs),
+
+    /// Time per project
+    #[command(name = "project", alias = "projects")]
+    Project(StatsArgs),
+
+    /// Time per customer
+    #[command(name = "customer", alias = "customers")]
+    Customer(StatsArgs),
+
+    /// Work report for a date range
+    #[command(name = "work-report", alias = "wr")]
+    WorkReport(StatsArgs),
+}
+
+#[derive(Debug, Args)]
+struct StatsArgs {
+    /// Start date (format: YYYY-MM-DD)
+    #[arg(long)]
+    from: Option<String>,
+
+    /// End date (format: YYYY-MM-DD)
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Date range: this-week, last-week, this-month, last-month, ytd, 2025-W07, 2025-03, ...
+    #[arg(long, value_parser = DateRange::parse_arg, conflicts_with_all = ["from", "to"])]
+    range: Option<DateRange>,
+
+    /// Username or user ID
+    #[arg(long)]
+    user: Option<String>,
+
+    /// Customer name (or a unique part of it) or ID
+    #[arg(long)]
+    customer: Option<String>,
+
+    /// Project name (or a unique part of it) or ID
+    #[arg(long)]
+    project: Option<String>,
+
+    /// Task name (or a unique part of it) or ID
+    #[arg(long)]
+    task: Option<String>,
+
+    /// Order of the rows
+    #[arg(long, value_enum, default_value_t)]
+    sort_by: statistics::StatisticsSort,
+}
+
+impl From<StatsArgs> for statistics::StatisticsOptions {
+    fn from(args: StatsArgs) -> Self {
+        let (from_date, to_date) = DateRange::or(args.range, args.from, args.to);
+        Self {
+            from_date,
+            to_date,
+            user: args.user,
+            customer: args.customer,
+            project: args.project,
+            task: args.task,
+            sort_by: args.sort_by,
+            output: Default::default(),
+        }
+    }
+}
+
+/// The configured rounding, or none at all with --no-round
+fn rounding(config: &TimedConfig, no_round: bool) -> RoundingConfig {
+    if no_round {
+        RoundingConfig::disabled()
+    } else {
+        config.rounding.clone()
+    }
 }
 
 /// Initialize logging with the appropriate verbosity level
@@ -501,6 +1142,23 @@ async fn main() -> Result<()> {
         }
     }
 
+    // The cache doesn't need configuration or authentication either
+    if let Commands::Cache(cmd) = &cli.command {
+        match &cmd.command {
+            CacheCommands::Clear => {
+                if let Err(e) = cache_handlers::clear_cache() {
+                    error!("Failed to clear cache: {}", e);
+                }
+            }
+            CacheCommands::Path => {
+                if let Err(e) = cache_handlers::cache_path() {
+                    error!("Failed to get cache path: {}", e);
+                }
+            }
+        }
+        return Ok(());
+    }
+
     // Only load configuration if we're not handling a config command (which was handled above)
     let config = match TimedConfig::load(Some(&config_path)) {
         Ok(config) => config,
@@ -511,7 +1169,13 @@ async fn main() -> Result<()> {
     };
 
     // Create auth client
-    let auth_client = AuthClient::new(config.clone());
+    let auth_client = match AuthClient::new(config.clone()) {
+        Ok(auth_client) => auth_client,
+        Err(e) => {
+            error!("Invalid HTTP client configuration: {}", e);
+            return Err(anyhow::anyhow!("Configuration error: {}", e));
+        }
+    };
 
     // Ensure we have a valid token unless we're doing a force-renew
     let token_result = if !matches!(cli.command, Commands::ForceRenew) && !cli.no_renew_token {
@@ -527,10 +1191,22 @@ async fn main() -> Result<()> {
     };
 
     // Create API client if we have a token
-    let _api_client = token_result.as_ref().map(|t| {
-        info!("Using existing token");
-        TimedClient::new(&config.timed_url, "api/v1", Some(t.clone()))
-    });
+    // HTTP settings shared by every client we create
+    let client_builder = match config.client_builder() {
+        Ok(builder) => builder,
+        Err(e) => {
+            error!("Invalid HTTP client configuration: {}", e);
+            return Err(anyhow::anyhow!("Configuration error: {}", e));
+        }
+    };
+
+    let _api_client = token_result
+        .as_ref()
+        .map(|t| {
+            info!("Using existing token");
+            client_builder.clone().token(t.clone()).build()
+        })
+        .transpose()?;
 
     // Create API client if we don't already have it for force-renew command
     let api_client = if token_result.is_none() && matches!(cli.command, Commands::ForceRenew) {
@@ -538,7 +1214,13 @@ async fn main() -> Result<()> {
         match auth_client.force_renew_token().await {
             Ok(token) => {
                 info!("Token renewed successfully");
-                Some(TimedClient::new(&config.timed_url, "api/v1", Some(token)))
+                // The new login may belong to another account
+                if let Some(dir) = default_cache_dir() {
+                    if let Err(e) = ResponseCache::persistent(dir).clear() {
+                        warn!("Failed to clear cache: {}", e);
+                    }
+                }
+                Some(client_builder.clone().token(token).build()?)
             }
             Err(e) => {
                 error!("Failed to renew token: {}", e);
@@ -552,7 +1234,7 @@ async fn main() -> Result<()> {
     // All config commands were already handled above
 
     // Ensure we have a client for commands that need it
-    let client = match (api_client, &cli.command) {
+    let mut client = match (api_client, &cli.command) {
         (Some(client), _) => client,
         (None, Commands::ForceRenew) => {
             // ForceRenew was already handled above
@@ -564,33 +1246,77 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Cached responses belong to the configured user and SSO account
+    let subject = token_result
+        .as_deref()
+        .and_then(|token| auth_client.token_subject(token))
+        .unwrap_or_default();
+
+    // Let the client refresh the token on its own once it expires
+    client.set_token_provider(Arc::new(auth_client));
+
+    if !cli.no_cache {
+        if let Some(dir) = user_cache_dir(&format!("{}-{subject}", config.username)) {
+            client.set_cache(ResponseCache::persistent(dir));
+        }
+    }
+
     // Handle commands
+    let output = match cli.output {
+        OutputArg::Format(format) => format,
+        OutputArg::File(_) => output::OutputFormat::default(),
+    };
     match cli.command {
         Commands::ForceRenew => {
             // Already handled above
         }
         Commands::Get(cmd) => match cmd.command {
-            GetCommands::Overtime { date } => match get_overtime(&client, date.as_deref()).await {
-                Ok(balance) => println!("Overtime: {balance}"),
-                Err(e) => error!("Failed to get overtime: {}", e),
-            },
+            GetCommands::Overtime { date } => {
+                if let Err(e) = show_overtime(&client, date.as_deref(), output).await {
+                    error!("Failed to get overtime: {}", e);
+                }
+            }
             GetCommands::Reports {
                 date,
                 from,
                 to,
+                range,
                 all_users,
                 interactive,
+                customer,
+                project,
+                task,
+                review,
+                not_billable,
+                verified,
+                rejected,
+                billed,
+                comment_contains,
+                group_by,
+                list,
             } => {
-                if let Err(e) = report::get_reports(
-                    &client,
-                    date.as_deref(),
-                    from.as_deref(),
-                    to.as_deref(),
+                let (from, to) = DateRange::or(range, from, to);
+                let options = report::GetReportsOptions {
+                    date,
+                    from_date: from,
+                    to_date: to,
                     all_users,
                     interactive,
-                )
-                .await
-                {
+                    customer,
+                    project,
+                    task,
+                    review,
+                    not_billable,
+                    verified,
+                    rejected,
+                    billed,
+                    comment_contains,
+                    output,
+                    sort_by: list.sort_by,
+                    view: list.view(),
+                    group_by,
+                };
+                if let Err(e) = report::get_reports(&client, &options).await {
                     error!("Failed to get reports: {}", e);
                 }
             }
@@ -598,24 +1324,27 @@ async fn main() -> Result<()> {
                 date,
                 from,
                 to,
+                range,
                 all_users,
+                list,
             } => {
-                if let Err(e) = activity::show_activity(
-                    &client,
-                    false,
-                    date.as_deref(),
-                    from.as_deref(),
-                    to.as_deref(),
+                let (from, to) = DateRange::or(range, from, to);
+                let options = activity::GetActivitiesOptions {
+                    date,
+                    from_date: from,
+                    to_date: to,
                     all_users,
-                )
-                .await
-                {
+                    output,
+                    sort_by: list.sort_by,
+                    view: list.view(),
+                };
+                if let Err(e) = activity::show_activity(&client, &options).await {
                     error!("Failed to get activities: {}", e);
                 }
             }
             GetCommands::Data(data_cmd) => match data_cmd.command {
-                DataCommands::Customers { format } => {
-                    if let Err(e) = data::get_customers(&client, &format).await {
+                DataCommands::Customers => {
+                    if let Err(e) = data::get_customers(&client, output).await {
                         error!("Failed to get customers: {}", e);
                     }
                 }
@@ -623,14 +1352,13 @@ async fn main() -> Result<()> {
                     customer_id,
                     customer_name,
                     archived,
-                    format,
                 } => {
                     if let Err(e) = data::get_projects(
                         &client,
                         customer_id,
                         customer_name.as_deref(),
                         archived,
-                        &format,
+                        output,
                     )
                     .await
                     {
@@ -643,7 +1371,6 @@ async fn main() -> Result<()> {
                     project_id,
                     project_name,
                     archived,
-                    format,
                 } => {
                     if let Err(e) = data::get_tasks(
                         &client,
@@ -652,7 +1379,7 @@ async fn main() -> Result<()> {
                         project_id,
                         project_name.as_deref(),
                         archived,
-                        &format,
+                        output,
                     )
                     .await
                     {
@@ -691,6 +1418,7 @@ async fn main() -> Result<()> {
                 show_archived,
                 review,
                 not_billable,
+                no_round,
                 non_interactive,
             } => {
                 if let Err(e) = report::add_report(
@@ -705,6 +1433,7 @@ async fn main() -> Result<()> {
                     review,
                     not_billable,
                     !non_interactive,
+                    &rounding(&config, no_round),
                 )
                 .await
                 {
@@ -713,12 +1442,48 @@ async fn main() -> Result<()> {
             }
         },
         Commands::Edit(cmd) => match cmd.command {
+            EditCommands::Report {
+                date,
+                non_interactive: _,
+                no_round: _,
+                bulk: true,
+                bulk_args: args,
+            } => {
+                let options = report::BulkEditOptions {
+                    date,
+                    from_date: args.from,
+                    to_date: args.to,
+                    user: args.user,
+                    all_users: args.all_users,
+                    customer: args.customer,
+                    project: args.project,
+                    task: args.task,
+                    in_review: args.in_review,
+                    set_task: args.set_task,
+                    review: args.review,
+                    not_billable: args.not_billable,
+                    verified: args.verified,
+                    comment: args.comment,
+                    yes: args.yes,
+                    dry_run: args.dry_run,
+                };
+                if let Err(e) = report::bulk_update_reports(&client, &options).await {
+                    error!("Failed to update reports: {}", e);
+                }
+            }
             EditCommands::Report {
                 date,
                 non_interactive,
+                no_round,
+                ..
             } => {
-                if let Err(e) =
-                    report::edit_report(&client, date.as_deref(), !non_interactive).await
+                if let Err(e) = report::edit_report(
+                    &client,
+                    date.as_deref(),
+                    !non_interactive,
+                    &rounding(&config, no_round),
+                )
+                .await
                 {
                     error!("Failed to edit report: {}", e);
                 }
@@ -754,8 +1519,28 @@ async fn main() -> Result<()> {
                     error!("Failed to stop activity: {}", e);
                 }
             }
+            ActivityCommands::Amend {
+                start,
+                comment,
+                task,
+                show_archived,
+                review,
+                not_billable,
+            } => {
+                let options = activity::AmendActivityOptions {
+                    start,
+                    comment,
+                    task,
+                    show_archived,
+                    review,
+                    not_billable,
+                };
+                if let Err(e) = activity::amend_activity(&client, &options).await {
+                    error!("Failed to amend activity: {}", e);
+                }
+            }
             ActivityCommands::Show { short } => {
-                if let Err(e) = activity::get_active_activity(&client, short).await {
+                if let Err(e) = activity::get_active_activity(&client, short, output).await {
                     error!("Failed to show activity: {}", e);
                 }
             }
@@ -769,13 +1554,297 @@ async fn main() -> Result<()> {
                     error!("Failed to delete activity: {}", e);
                 }
             }
-            ActivityCommands::GenerateTimesheet => {
-                if let Err(e) = activity::generate_timesheet(&client).await {
+            ActivityCommands::Switch {
+                task,
+                comment,
+                show_archived,
+                non_interactive,
+            } => {
+                let options = activity::SwitchActivityOptions {
+                    task: task.map(|task| config.task_alias(&task).to_string()),
+                    comment,
+                    show_archived,
+                    interactive: !non_interactive,
+                };
+                if let Err(e) = activity::switch_activity(&client, &options).await {
+                    error!("Failed to switch activity: {}", e);
+                }
+            }
+            ActivityCommands::GenerateTimesheet {
+                date,
+                from,
+                to,
+                range,
+                dry_run,
+                no_round,
+                non_interactive,
+            } => {
+                let (from_date, to_date) = DateRange::or(range, from, to);
+                let options = activity::GenerateTimesheetOptions {
+                    date,
+                    from_date,
+                    to_date,
+                    rounding: rounding(&config, no_round),
+                    dry_run,
+                    interactive: !non_interactive,
+                };
+                if let Err(e) = activity::generate_timesheet(&client, &options).await {
                     error!("Failed to generate timesheet: {}", e);
                 }
             }
         },
-        Commands::Config(_) => {
+        Commands::Attendance(cmd) => match cmd.command {
+            AttendanceCommands::In { time, date } => {
+                if let Err(e) =
+                    attendance::clock_in(&client, date.as_deref(), time.as_deref()).await
+                {
+                    error!("Failed to clock in: {}", e);
+                }
+            }
+            AttendanceCommands::Out { time, date } => {
+                if let Err(e) =
+                    attendance::clock_out(&client, date.as_deref(), time.as_deref()).await
+                {
+                    error!("Failed to clock out: {}", e);
+                }
+            }
+            AttendanceCommands::List {
+                date,
+                from,
+                to,
+                range,
+                all_users,
+            } => {
+                let (from, to) = DateRange::or(range, from, to);
+                if let Err(e) = attendance::list_attendances(
+                    &client,
+                    date.as_deref(),
+                    from.as_deref(),
+                    to.as_deref(),
+                    all_users,
+                    output,
+                )
+                .await
+                {
+                    error!("Failed to list attendances: {}", e);
+                }
+            }
+            AttendanceCommands::Edit {
+                id,
+                date,
+                from_time,
+                to_time,
+                non_interactive,
+            } => {
+                if let Err(e) = attendance::edit_attendance(
+                    &client,
+                    id.as_deref(),
+                    date.as_deref(),
+                    from_time.as_deref(),
+                    to_time.as_deref(),
+                    !non_interactive,
+                )
+                .await
+                {
+                    error!("Failed to edit attendance: {}", e);
+                }
+            }
+            AttendanceCommands::Rm {
+                id,
+                date,
+                non_interactive,
+            } => {
+                if let Err(e) = attendance::remove_attendance(
+                    &client,
+                    id.as_deref(),
+                    date.as_deref(),
+                    !non_interactive,
+                )
+                .await
+                {
+                    error!("Failed to delete attendance: {}", e);
+                }
+            }
+        },
+        Commands::Absence(cmd) => match cmd.command {
+            AbsenceCommands::List {
+                date,
+                from,
+                to,
+                range,
+                all_users,
+            } => {
+                let (from, to) = DateRange::or(range, from, to);
+                if let Err(e) = absence::list_absences(
+                    &client,
+                    date.as_deref(),
+                    from.as_deref(),
+                    to.as_deref(),
+                    all_users,
+                    output,
+                )
+                .await
+                {
+                    error!("Failed to list absences: {}", e);
+                }
+            }
+            AbsenceCommands::Add {
+                absence_type,
+                date,
+                from,
+                to,
+                comment,
+                yes,
+                non_interactive,
+            } => {
+                let options = absence::AddAbsenceOptions {
+                    date,
+                    from_date: from,
+                    to_date: to,
+                    absence_type,
+                    comment,
+                    yes,
+                    interactive: !non_interactive,
+                };
+                if let Err(e) = absence::add_absences(&client, options).await {
+                    error!("Failed to add absence: {}", e);
+                }
+            }
+            AbsenceCommands::Delete {
+                id,
+                date,
+                non_interactive,
+            } => {
+                if let Err(e) = absence::delete_absences(
+                    &client,
+                    id.as_deref(),
+                    date.as_deref(),
+                    !non_interactive,
+                )
+                .await
+                {
+                    error!("Failed to delete absence: {}", e);
+                }
+            }
+            AbsenceCommands::Types => {
+                if let Err(e) = absence::list_absence_types(&client, output).await {
+                    error!("Failed to list absence types: {}", e);
+                }
+            }
+        },
+        Commands::Export(cmd) => match cmd.command {
+            ExportCommands::Reports {
+                from,
+                to,
+                range,
+                user,
+                customer,
+                project,
+                task,
+                format,
+                file,
+            } => {
+                let (from, to) = DateRange::or(range, from, to);
+                let options = report::ExportOptions {
+                    from_date: from,
+                    to_date: to,
+                    user,
+                    customer,
+                    project,
+                    task,
+                    file_type: format,
+                    // A format given before the subcommand is not a file
+                    output: match file {
+                        Some(OutputArg::File(file)) => Some(file),
+                        _ => None,
+                    },
+                };
+                if let Err(e) = report::export_reports(&client, &options).await {
+                    error!("Failed to export reports: {}", e);
+                }
+            }
+        },
+        Commands::Stats(cmd) => {
+            let result = match cmd.command {
+                StatsCommands::Year { year, args } => {
+                    statistics::get_year_statistics(
+                        &client,
+                        year,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::Month { year, month, args } => {
+                    statistics::get_month_statistics(
+                        &client,
+                        year,
+                        month,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::Task(args) => {
+                    statistics::get_task_statistics(
+                        &client,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::User(args) => {
+                    statistics::get_user_statistics(
+                        &client,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::Project(args) => {
+                    statistics::get_project_statistics(
+                        &client,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::Customer(args) => {
+                    statistics::get_customer_statistics(
+                        &client,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+                StatsCommands::WorkReport(args) => {
+                    statistics::get_work_report(
+                        &client,
+                        &statistics::StatisticsOptions {
+                            output,
+                            ..args.into()
+                        },
+                    )
+                    .await
+                }
+            };
+            if let Err(e) = result {
+                error!("Failed to get statistics: {}", e);
+            }
+        }
+        Commands::Config(_) | Commands::Cache(_) => {
             // Already handled above
         }
     }
@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 
-use crate::TimedClient;
+use crate::transforms::{date_format, duration_format, optional_time_format, time_format};
+use crate::{ApiResource, TimedClient};
 use serde_json::Value;
 
 /// Base trait for all API models
@@ -15,6 +18,18 @@ pub trait Model {
     }
 }
 
+/// Links a model to the API resource it is served from
+///
+/// This is what the typed `list`, `fetch`, `create`, `update` and `destroy`
+/// methods on [`TimedClient`] use to find the endpoint for a model.
+pub trait Resource: Serialize + DeserializeOwned {
+    /// The API resource this model belongs to
+    const RESOURCE: ApiResource;
+
+    /// Get the ID of this resource, if it has one
+    fn id(&self) -> Option<&str>;
+}
+
 /// Common parameters for API filtering
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct FilterParams {
@@ -50,6 +65,22 @@ pub struct FilterParams {
     #[serde(rename = "not_billable", skip_serializing_if = "Option::is_none")]
     pub not_billable: Option<i32>,
 
+    /// Filter by verification status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<i32>,
+
+    /// Filter by rejection status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<i32>,
+
+    /// Filter by billed status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billed: Option<i32>,
+
+    /// Filter by a part of the comment (case-insensitive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
     /// Filter by task ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub task: Option<String>,
@@ -83,6 +114,29 @@ pub struct ResourceResponse<T> {
 pub struct ResourcesResponse<T> {
     pub data: Vec<T>,
     pub included: Option<Vec<IncludedResource>>,
+    #[serde(default)]
+    pub links: Option<PaginationLinks>,
+    #[serde(default)]
+    pub meta: Option<Value>,
+}
+
+impl<T> ResourcesResponse<T> {
+    /// Find an included resource by type and ID
+    pub fn find_included(&self, type_name: &str, id: &str) -> Option<&IncludedResource> {
+        self.included
+            .as_ref()?
+            .iter()
+            .find(|inc| inc.type_name == type_name && inc.id == id)
+    }
+}
+
+/// Pagination links of a collection response
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PaginationLinks {
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub next: Option<String>,
+    pub prev: Option<String>,
 }
 
 /// A generic included resource in an API response
@@ -95,6 +149,79 @@ pub struct IncludedResource {
     pub relationships: Option<HashMap<String, serde_json::Value>>,
 }
 
+impl IncludedResource {
+    /// Get a string attribute by name
+    pub fn attribute_str(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).and_then(|v| v.as_str())
+    }
+
+    /// Get the ID of a to-one relationship by name
+    pub fn relationship_id(&self, name: &str) -> Option<&str> {
+        self.relationships
+            .as_ref()?
+            .get(name)?
+            .get("data")?
+            .get("id")?
+            .as_str()
+    }
+}
+
+/// Error document returned by the API for rejected requests
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    pub errors: Vec<ApiErrorObject>,
+}
+
+/// A single JSON:API error object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiErrorObject {
+    pub status: Option<String>,
+    pub code: Option<String>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    pub source: Option<ApiErrorSource>,
+}
+
+/// Part of the request an error object refers to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiErrorSource {
+    pub pointer: Option<String>,
+    pub parameter: Option<String>,
+}
+
+impl ApiErrorObject {
+    /// Name of the field or parameter the error refers to, if any
+    pub fn field(&self) -> Option<&str> {
+        let source = self.source.as_ref()?;
+        if let Some(pointer) = source.pointer.as_deref() {
+            let field = pointer
+                .trim_start_matches("/data/attributes/")
+                .trim_start_matches("/data/relationships/")
+                .trim_start_matches('/');
+            return Some(field).filter(|f| !f.is_empty() && *f != "data");
+        }
+        source.parameter.as_deref()
+    }
+}
+
+impl fmt::Display for ApiErrorObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = self.field() {
+            write!(f, "{field}: ")?;
+        }
+        let message = self
+            .detail
+            .as_deref()
+            .or(self.title.as_deref())
+            .unwrap_or("unknown error");
+        write!(f, "{message}")?;
+        if let Some(code) = &self.code {
+            write!(f, " ({code})")?;
+        }
+        Ok(())
+    }
+}
+
 /// User model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -121,6 +248,14 @@ impl Model for User {
     }
 }
 
+impl Resource for User {
+    const RESOURCE: ApiResource = ApiResource::Users;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Customer model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Customer {
@@ -143,6 +278,14 @@ impl Model for Customer {
     }
 }
 
+impl Resource for Customer {
+    const RESOURCE: ApiResource = ApiResource::Customers;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Project model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -170,6 +313,14 @@ impl Model for Project {
     }
 }
 
+impl Resource for Project {
+    const RESOURCE: ApiResource = ApiResource::Projects;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Task model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -197,6 +348,14 @@ impl Model for Task {
     }
 }
 
+impl Resource for Task {
+    const RESOURCE: ApiResource = ApiResource::Tasks;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Activity model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Activity {
@@ -210,14 +369,18 @@ pub struct Activity {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivityAttributes {
     pub comment: String,
-    pub date: String,
-    #[serde(rename = "from-time")]
-    pub from_time: String,
-    #[serde(rename = "to-time")]
-    pub to_time: Option<String>,
+    #[serde(with = "date_format")]
+    pub date: NaiveDate,
+    #[serde(rename = "from-time", with = "time_format")]
+    pub from_time: NaiveTime,
+    #[serde(rename = "to-time", with = "optional_time_format", default)]
+    pub to_time: Option<NaiveTime>,
     pub review: bool,
     #[serde(rename = "not-billable")]
     pub not_billable: bool,
+    /// Whether the activity was already booked as a report
+    #[serde(default)]
+    pub transferred: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -232,6 +395,14 @@ impl Model for Activity {
     }
 }
 
+impl Resource for Activity {
+    const RESOURCE: ApiResource = ApiResource::Activities;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Report model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Report {
@@ -245,8 +416,10 @@ pub struct Report {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReportAttributes {
     pub comment: String,
-    pub date: String,
-    pub duration: String,
+    #[serde(with = "date_format")]
+    pub date: NaiveDate,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
     pub review: bool,
     #[serde(rename = "not-billable")]
     pub not_billable: bool,
@@ -272,6 +445,14 @@ impl Model for Report {
     }
 }
 
+impl Resource for Report {
+    const RESOURCE: ApiResource = ApiResource::Reports;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// WorktimeBalance model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorktimeBalance {
@@ -284,8 +465,10 @@ pub struct WorktimeBalance {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorktimeBalanceAttributes {
-    pub date: String,
-    pub balance: String,
+    #[serde(with = "date_format")]
+    pub date: NaiveDate,
+    #[serde(with = "duration_format")]
+    pub balance: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -299,12 +482,27 @@ impl Model for WorktimeBalance {
     }
 }
 
+impl Resource for WorktimeBalance {
+    const RESOURCE: ApiResource = ApiResource::WorktimeBalances;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Common structure for relationship data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelationshipData {
     pub data: Option<RelationshipResource>,
 }
 
+impl RelationshipData {
+    /// Get the ID of the related resource, if any
+    pub fn id(&self) -> Option<&str> {
+        self.data.as_ref().map(|d| d.id.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelationshipResource {
     #[serde(rename = "type")]
@@ -324,11 +522,12 @@ pub struct Attendance {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttendanceAttributes {
-    pub date: String,
-    #[serde(rename = "from-time")]
-    pub from_time: String,
-    #[serde(rename = "to-time")]
-    pub to_time: Option<String>,
+    #[serde(with = "date_format")]
+    pub date: NaiveDate,
+    #[serde(rename = "from-time", with = "time_format")]
+    pub from_time: NaiveTime,
+    #[serde(rename = "to-time", with = "optional_time_format", default)]
+    pub to_time: Option<NaiveTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -342,6 +541,14 @@ impl Model for Attendance {
     }
 }
 
+impl Resource for Attendance {
+    const RESOURCE: ApiResource = ApiResource::Attendances;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// Absence model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Absence {
@@ -354,7 +561,8 @@ pub struct Absence {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AbsenceAttributes {
-    pub date: String,
+    #[serde(with = "date_format")]
+    pub date: NaiveDate,
     pub comment: Option<String>,
 }
 
@@ -371,6 +579,14 @@ impl Model for Absence {
     }
 }
 
+impl Resource for Absence {
+    const RESOURCE: ApiResource = ApiResource::Absences;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// AbsenceType model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AbsenceType {
@@ -393,6 +609,14 @@ impl Model for AbsenceType {
     }
 }
 
+impl Resource for AbsenceType {
+    const RESOURCE: ApiResource = ApiResource::AbsenceTypes;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// YearStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YearStatistic {
@@ -406,9 +630,10 @@ pub struct YearStatistic {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YearStatisticAttributes {
     pub year: i32,
-    pub duration: String,
-    #[serde(rename = "total-attendance")]
-    pub total_attendance: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
+    #[serde(rename = "total-attendance", with = "duration_format")]
+    pub total_attendance: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -422,6 +647,14 @@ impl Model for YearStatistic {
     }
 }
 
+impl Resource for YearStatistic {
+    const RESOURCE: ApiResource = ApiResource::YearStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// MonthStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonthStatistic {
@@ -436,9 +669,10 @@ pub struct MonthStatistic {
 pub struct MonthStatisticAttributes {
     pub year: i32,
     pub month: i32,
-    pub duration: String,
-    #[serde(rename = "total-attendance")]
-    pub total_attendance: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
+    #[serde(rename = "total-attendance", with = "duration_format")]
+    pub total_attendance: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -452,6 +686,14 @@ impl Model for MonthStatistic {
     }
 }
 
+impl Resource for MonthStatistic {
+    const RESOURCE: ApiResource = ApiResource::MonthStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// TaskStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskStatistic {
@@ -464,7 +706,8 @@ pub struct TaskStatistic {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskStatisticAttributes {
-    pub duration: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -479,6 +722,14 @@ impl Model for TaskStatistic {
     }
 }
 
+impl Resource for TaskStatistic {
+    const RESOURCE: ApiResource = ApiResource::TaskStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// UserStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserStatistic {
@@ -491,7 +742,8 @@ pub struct UserStatistic {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserStatisticAttributes {
-    pub duration: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -505,6 +757,14 @@ impl Model for UserStatistic {
     }
 }
 
+impl Resource for UserStatistic {
+    const RESOURCE: ApiResource = ApiResource::UserStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// ProjectStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectStatistic {
@@ -517,7 +777,8 @@ pub struct ProjectStatistic {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectStatisticAttributes {
-    pub duration: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -531,6 +792,14 @@ impl Model for ProjectStatistic {
     }
 }
 
+impl Resource for ProjectStatistic {
+    const RESOURCE: ApiResource = ApiResource::ProjectStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// CustomerStatistic model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomerStatistic {
@@ -543,7 +812,8 @@ pub struct CustomerStatistic {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomerStatisticAttributes {
-    pub duration: String,
+    #[serde(with = "duration_format")]
+    pub duration: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -557,6 +827,14 @@ impl Model for CustomerStatistic {
     }
 }
 
+impl Resource for CustomerStatistic {
+    const RESOURCE: ApiResource = ApiResource::CustomerStatistics;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 /// WorkReport model for Timed API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkReport {
@@ -577,6 +855,14 @@ impl Model for WorkReport {
     }
 }
 
+impl Resource for WorkReport {
+    const RESOURCE: ApiResource = ApiResource::WorkReports;
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -602,6 +888,26 @@ mod tests {
         assert_eq!(WorkReport::resource_name(), "work-reports");
     }
 
+    #[test]
+    fn test_resource_paths_match_model_names() {
+        assert_eq!(User::RESOURCE.as_path(), User::resource_name());
+        assert_eq!(Report::RESOURCE.as_path(), Report::resource_name());
+        assert_eq!(Activity::RESOURCE.as_path(), Activity::resource_name());
+        assert_eq!(
+            WorktimeBalance::RESOURCE.as_path(),
+            WorktimeBalance::resource_name()
+        );
+        assert_eq!(
+            AbsenceType::RESOURCE.as_path(),
+            AbsenceType::resource_name()
+        );
+        assert_eq!(
+            CustomerStatistic::RESOURCE.as_path(),
+            CustomerStatistic::resource_name()
+        );
+        assert_eq!(WorkReport::RESOURCE.as_path(), WorkReport::resource_name());
+    }
+
     #[test]
     fn test_serialize_activity() {
         let activity = Activity {
@@ -609,11 +915,12 @@ mod tests {
             type_name: "activities".to_string(),
             attributes: ActivityAttributes {
                 comment: "Working on something".to_string(),
-                date: "2023-07-15".to_string(),
-                from_time: "09:00:00".to_string(),
-                to_time: Some("17:00:00".to_string()),
+                date: NaiveDate::from_ymd_opt(2023, 7, 15).unwrap(),
+                from_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                to_time: NaiveTime::from_hms_opt(17, 0, 0),
                 review: false,
                 not_billable: false,
+                transferred: false,
             },
             relationships: ActivityRelationships {
                 user: Some(RelationshipData {
@@ -635,4 +942,63 @@ mod tests {
         assert!(json.contains("activities"));
         assert!(json.contains("Working on something"));
     }
+
+    #[test]
+    fn test_deserialize_typed_report() {
+        let json = serde_json::json!({
+            "id": "1",
+            "type": "reports",
+            "attributes": {
+                "comment": "Meeting",
+                "date": "2023-07-15",
+                "duration": "01:30:00",
+                "review": false,
+                "not-billable": false,
+                "verified": false
+            },
+            "relationships": {}
+        });
+
+        let report: Report = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            report.attributes.date,
+            NaiveDate::from_ymd_opt(2023, 7, 15).unwrap()
+        );
+        assert_eq!(report.attributes.duration, Duration::minutes(90));
+
+        let serialized = serde_json::to_value(&report).unwrap();
+        assert_eq!(serialized["attributes"]["date"], "2023-07-15");
+        assert_eq!(serialized["attributes"]["duration"], "01:30:00");
+    }
+
+    #[test]
+    fn test_reject_malformed_payloads() {
+        let activity = serde_json::json!({
+            "type": "activities",
+            "attributes": {
+                "comment": "",
+                "date": "2023-07-15",
+                "from-time": "nine",
+                "review": false,
+                "not-billable": false
+            },
+            "relationships": {}
+        });
+        let error = serde_json::from_value::<Activity>(activity).unwrap_err();
+        assert!(error.to_string().contains("nine"));
+
+        let report = serde_json::json!({
+            "type": "reports",
+            "attributes": {
+                "comment": "",
+                "date": "15.07.2023",
+                "duration": "01:00:00",
+                "review": false,
+                "not-billable": false,
+                "verified": false
+            },
+            "relationships": {}
+        });
+        assert!(serde_json::from_value::<Report>(report).is_err());
+    }
 }
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::models::*;
+    use chrono::{Duration, NaiveDate, NaiveTime};
     use serde_json::json;
 
     #[test]
@@ -9,9 +10,9 @@ mod tests {
             id: Some("123".to_string()),
             type_name: "attendances".to_string(),
             attributes: AttendanceAttributes {
-                date: "2023-08-15".to_string(),
-                from_time: "09:00:00".to_string(),
-                to_time: Some("17:00:00".to_string()),
+                date: NaiveDate::from_ymd_opt(2023, 8, 15).unwrap(),
+                from_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                to_time: NaiveTime::from_hms_opt(17, 0, 0),
             },
             relationships: AttendanceRelationships {
                 user: Some(RelationshipData {
@@ -38,7 +39,7 @@ mod tests {
             id: Some("123".to_string()),
             type_name: "absences".to_string(),
             attributes: AbsenceAttributes {
-                date: "2023-08-15".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 8, 15).unwrap(),
                 comment: Some("Vacation".to_string()),
             },
             relationships: AbsenceRelationships {
@@ -91,8 +92,8 @@ mod tests {
             type_name: "year-statistics".to_string(),
             attributes: YearStatisticAttributes {
                 year: 2023,
-                duration: "2000:00:00".to_string(),
-                total_attendance: "2100:00:00".to_string(),
+                duration: Duration::hours(2000),
+                total_attendance: Duration::hours(2100),
             },
             relationships: YearStatisticRelationships {
                 user: Some(RelationshipData {
@@ -121,8 +122,8 @@ mod tests {
             attributes: MonthStatisticAttributes {
                 year: 2023,
                 month: 8,
-                duration: "160:00:00".to_string(),
-                total_attendance: "168:00:00".to_string(),
+                duration: Duration::hours(160),
+                total_attendance: Duration::hours(168),
             },
             relationships: MonthStatisticRelationships {
                 user: Some(RelationshipData {
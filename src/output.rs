@@ -0,0 +1,532 @@
+//! Output formats shared by all read commands
+//!
+//! Every read command turns its results into serializable records and a
+//! human-readable [`Table`]. The format selected with the global `--output`
+//! flag decides which of the two is printed.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Format of the output of read commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable table
+    #[default]
+    Table,
+    /// Pretty-printed JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+const SEPARATOR: &str = "----------------------------------------";
+
+/// Human-readable rendering of a command's output
+///
+/// Cells are aligned in columns, with an optional title above and footer
+/// lines like totals below.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    title: Option<String>,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    footer: Vec<String>,
+    empty: Option<String>,
+}
+
+impl Table {
+    /// Create a table with the given column headers
+    pub fn new<S: Into<String>>(header: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            header: header.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the title printed above the table
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the message printed instead of an empty table
+    pub fn empty(mut self, message: impl Into<String>) -> Self {
+        self.empty = Some(message.into());
+        self
+    }
+
+    /// Add a row of cells
+    pub fn row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    /// Add a line below the table, like a total
+    pub fn footer(&mut self, line: impl Into<String>) {
+        self.footer.push(line.into());
+    }
+
+    /// Render the table as aligned text
+    pub fn render(&self) -> String {
+        if self.rows.is_empty() {
+            if let Some(message) = &self.empty {
+                return message.clone();
+            }
+        }
+
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(self.header.len()))
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .chain(std::iter::once(&self.header))
+                    .filter_map(|cells| cells.get(i))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut out = Vec::new();
+        if let Some(title) = &self.title {
+            out.push(title.clone());
+            out.push(SEPARATOR.to_string());
+        }
+        if !self.header.is_empty() {
+            out.push(line(&self.header));
+        }
+        out.extend(self.rows.iter().map(|cells| line(cells)));
+        if !self.footer.is_empty() {
+            out.push(SEPARATOR.to_string());
+            out.extend(self.footer.iter().cloned());
+        }
+        out.join("\n")
+    }
+}
+
+/// Render a list of records, or the table for [`OutputFormat::Table`]
+pub fn render_list<T: Serialize>(
+    format: OutputFormat,
+    records: &[T],
+    table: &Table,
+) -> Result<String> {
+    match format {
+        OutputFormat::Table => Ok(table.render()),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(records)?),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(records)?.trim_end().to_string()),
+        OutputFormat::Ndjson => {
+            let lines = records
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Csv => delimited(records, b','),
+        OutputFormat::Tsv => delimited(records, b'\t'),
+    }
+}
+
+/// Render a single record, or the text for [`OutputFormat::Table`]
+pub fn render_item<T: Serialize>(format: OutputFormat, record: &T, text: &str) -> Result<String> {
+    match format {
+        OutputFormat::Table => Ok(text.to_string()),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(record)?),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(record)?.trim_end().to_string()),
+        _ => render_list(format, std::slice::from_ref(record), &Table::default()),
+    }
+}
+
+/// Print a list of records in the given format
+pub fn print_list<T: Serialize>(format: OutputFormat, records: &[T], table: &Table) -> Result<()> {
+    let output = render_list(format, records, table)?;
+    if !output.is_empty() {
+        println!("{output}");
+    }
+    Ok(())
+}
+
+/// Print a single record in the given format
+pub fn print_item<T: Serialize>(format: OutputFormat, record: &T, text: &str) -> Result<()> {
+    println!("{}", render_item(format, record, text)?);
+    Ok(())
+}
+
+/// Column selection and templating for list commands
+#[derive(Debug, Clone, Default)]
+pub struct ListView {
+    /// Fields to show, in this order
+    pub columns: Option<Vec<String>>,
+    /// Line printed per record, with `{field}` placeholders
+    pub template: Option<String>,
+}
+
+impl ListView {
+    /// Check that the columns and the template only use the given fields
+    pub fn validate(&self, fields: &[&str]) -> Result<()> {
+        let template_fields = match &self.template {
+            Some(template) => segments(template)?
+                .into_iter()
+                .filter_map(|segment| match segment {
+                    Segment::Field(field) => Some(field),
+                    Segment::Text(_) => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let unknown: Vec<&str> = self
+            .columns
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(template_fields)
+            .filter(|field| !fields.contains(field))
+            .collect();
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Unknown field(s): {} (available: {})",
+                unknown.join(", "),
+                fields.join(", ")
+            ))
+        }
+    }
+}
+
+/// Render a list of records, applying the columns and template of a view
+///
+/// A template takes precedence over the output format. Selected columns apply
+/// to every format, including the table, which keeps its title and footer.
+pub fn render_view<T: Serialize>(
+    format: OutputFormat,
+    records: &[T],
+    table: &Table,
+    view: &ListView,
+) -> Result<String> {
+    if view.columns.is_none() && view.template.is_none() {
+        return render_list(format, records, table);
+    }
+
+    let objects = records
+        .iter()
+        .map(|record| match serde_json::to_value(record)? {
+            Value::Object(fields) => Ok(fields),
+            _ => Err(anyhow::anyhow!("Only records can be selected from")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(template) = &view.template {
+        let lines = objects
+            .iter()
+            .map(|record| fill_template(template, record))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(lines.join("\n"));
+    }
+
+    let columns = view.columns.as_deref().unwrap_or_default();
+    let selected: Vec<Map<String, Value>> = objects
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .map(|column| {
+                    let value = record.get(column).cloned().unwrap_or(Value::Null);
+                    (column.clone(), value)
+                })
+                .collect()
+        })
+        .collect();
+
+    let table = Table {
+        header: columns.iter().map(|c| heading(c)).collect(),
+        rows: selected
+            .iter()
+            .map(|record| record.values().map(cell).collect())
+            .collect(),
+        ..table.clone()
+    };
+    render_list(format, &selected, &table)
+}
+
+/// Print a list of records, applying the columns and template of a view
+pub fn print_view<T: Serialize>(
+    format: OutputFormat,
+    records: &[T],
+    table: &Table,
+    view: &ListView,
+) -> Result<()> {
+    let output = render_view(format, records, table, view)?;
+    if !output.is_empty() {
+        println!("{output}");
+    }
+    Ok(())
+}
+
+/// Part of a template
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Field(&'a str),
+}
+
+/// Split a template into text and `{field}` placeholders
+///
+/// `{{` and `}}` stand for literal braces.
+fn segments(template: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        segments.push(Segment::Text(&rest[..start]));
+        let tail = &rest[start..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            segments.push(Segment::Text(&tail[..1]));
+            rest = &tail[2..];
+        } else if tail.starts_with('}') {
+            return Err(anyhow::anyhow!("Unmatched '}}' in template: {}", template));
+        } else {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("Unclosed '{{' in template: {}", template))?;
+            segments.push(Segment::Field(tail[1..end].trim()));
+            rest = &tail[end + 1..];
+        }
+    }
+    segments.push(Segment::Text(rest));
+    Ok(segments)
+}
+
+/// Replace the placeholders of a template with the fields of a record
+fn fill_template(template: &str, record: &Map<String, Value>) -> Result<String> {
+    segments(template)?
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => Ok(text.to_string()),
+            Segment::Field(field) => record
+                .get(field)
+                .map(cell)
+                .ok_or_else(|| anyhow::anyhow!("Unknown field in template: {}", field)),
+        })
+        .collect()
+}
+
+/// Turn a field name like `not_billable` into a column heading like `Not billable`
+fn heading(field: &str) -> String {
+    let text = field.replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Write records as CSV or TSV, quoting fields where needed
+///
+/// The header is taken from the field names of the first record.
+fn delimited<T: Serialize>(records: &[T], delimiter: u8) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+
+    for (i, record) in records.iter().enumerate() {
+        let Value::Object(fields) = serde_json::to_value(record)? else {
+            return Err(anyhow::anyhow!("Only records can be written as CSV"));
+        };
+        if i == 0 {
+            writer.write_record(fields.keys())?;
+        }
+        writer.write_record(fields.values().map(cell))?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8(bytes)?.trim_end_matches('\n').to_string())
+}
+
+/// Turn a JSON value into the text of a CSV cell
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Serialize)]
+    struct Record {
+        id: &'static str,
+        comment: &'static str,
+        hours: f64,
+        billed: Option<bool>,
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                id: "1",
+                comment: "Fix login, again",
+                hours: 1.5,
+                billed: Some(true),
+            },
+            Record {
+                id: "2",
+                comment: "Said \"hi\"\tthen left",
+                hours: 0.25,
+                billed: None,
+            },
+        ]
+    }
+
+    fn table() -> Table {
+        let mut table = Table::new(["ID", "Comment"]).title("Reports");
+        table.row(["1", "Fix login, again"]);
+        table.row(["22", "Meeting"]);
+        table.footer("Total: 2 reports");
+        table
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            table().render(),
+            [
+                "Reports",
+                SEPARATOR,
+                "ID | Comment",
+                "1  | Fix login, again",
+                "22 | Meeting",
+                SEPARATOR,
+                "Total: 2 reports",
+            ]
+            .join("\n")
+        );
+
+        let empty = Table::new(["ID"]).empty("No reports found");
+        assert_eq!(empty.render(), "No reports found");
+    }
+
+    #[test]
+    fn test_csv_and_tsv_quoting() {
+        let csv = render_list(OutputFormat::Csv, &records(), &table()).unwrap();
+        assert_eq!(
+            csv,
+            "id,comment,hours,billed\n1,\"Fix login, again\",1.5,true\n2,\"Said \"\"hi\"\"\tthen left\",0.25,"
+        );
+
+        let tsv = render_list(OutputFormat::Tsv, &records(), &table()).unwrap();
+        assert_eq!(
+            tsv,
+            "id\tcomment\thours\tbilled\n1\tFix login, again\t1.5\ttrue\n2\t\"Said \"\"hi\"\"\tthen left\"\t0.25\t"
+        );
+    }
+
+    #[test]
+    fn test_structured_formats() {
+        let records = records();
+
+        let json: Value =
+            serde_json::from_str(&render_list(OutputFormat::Json, &records, &table()).unwrap())
+                .unwrap();
+        assert_eq!(json[0]["comment"], "Fix login, again");
+        assert_eq!(json[1]["billed"], Value::Null);
+
+        let ndjson = render_list(OutputFormat::Ndjson, &records, &table()).unwrap();
+        assert_eq!(ndjson.lines().count(), 2);
+        assert!(ndjson.starts_with(r#"{"id":"1","comment":"Fix login, again""#));
+
+        let yaml = render_list(OutputFormat::Yaml, &records, &table()).unwrap();
+        assert!(yaml.starts_with("- id: '1'\n  comment: Fix login, again\n"));
+
+        let item = render_item(OutputFormat::Csv, &records[0], "text").unwrap();
+        assert_eq!(
+            item,
+            "id,comment,hours,billed\n1,\"Fix login, again\",1.5,true"
+        );
+        assert_eq!(
+            render_item(OutputFormat::Table, &records[0], "text").unwrap(),
+            "text"
+        );
+    }
+
+    #[test]
+    fn test_view_columns() {
+        let view = ListView {
+            columns: Some(vec!["hours".to_string(), "id".to_string()]),
+            template: None,
+        };
+
+        let csv = render_view(OutputFormat::Csv, &records(), &table(), &view).unwrap();
+        assert_eq!(csv, "hours,id\n1.5,1\n0.25,2");
+
+        assert_eq!(
+            render_view(OutputFormat::Table, &records(), &table(), &view).unwrap(),
+            [
+                "Reports",
+                SEPARATOR,
+                "Hours | Id",
+                "1.5   | 1",
+                "0.25  | 2",
+                SEPARATOR,
+                "Total: 2 reports",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_view_template() {
+        let view = ListView {
+            columns: None,
+            template: Some("{{{id}}} {hours}h: { comment }{billed}".to_string()),
+        };
+        assert_eq!(
+            render_view(OutputFormat::Json, &records(), &table(), &view).unwrap(),
+            "{1} 1.5h: Fix login, againtrue\n{2} 0.25h: Said \"hi\"\tthen left"
+        );
+
+        let fields = ["id", "comment", "hours", "billed"];
+        assert!(view.validate(&fields).is_ok());
+
+        let invalid = ListView {
+            columns: Some(vec!["id".to_string(), "customer".to_string()]),
+            template: Some("{date}".to_string()),
+        };
+        let error = invalid.validate(&fields).unwrap_err().to_string();
+        assert!(error.starts_with("Unknown field(s): customer, date"));
+
+        for template in ["{id", "id}"] {
+            let view = ListView {
+                columns: None,
+                template: Some(template.to_string()),
+            };
+            assert!(view.validate(&fields).is_err());
+        }
+    }
+}
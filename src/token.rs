@@ -0,0 +1,27 @@
+//! Access token sources for the Timed API
+
+use futures_util::future::BoxFuture;
+
+use crate::{Result, TimedError};
+
+/// Source of access tokens that the client can ask for a fresh token
+pub trait TokenProvider: Send + Sync {
+    /// Get a token that is believed to be valid
+    fn token(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Get a new token after the API rejected the current one
+    fn refresh(&self) -> BoxFuture<'_, Result<String>>;
+}
+
+/// A fixed token that cannot be refreshed
+pub struct StaticToken(pub String);
+
+impl TokenProvider for StaticToken {
+    fn token(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move { Ok(self.0.clone()) })
+    }
+
+    fn refresh(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async { Err(TimedError::AuthenticationRequired) })
+    }
+}
@@ -1,5 +1,10 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub};
+use std::str::FromStr;
+
 use chrono::{Duration, NaiveDate, NaiveTime};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 /// Transform trait for converting between Rust types and API representation
 pub trait Transform<T, U> {
@@ -41,24 +46,265 @@ pub struct DurationTransform;
 
 impl Transform<Option<Duration>, Option<String>> for DurationTransform {
     fn serialize(&self, value: Option<Duration>) -> Option<String> {
-        value.map(|duration| {
-            let total_seconds = duration.num_seconds();
-            let hours = total_seconds / 3600;
-            let minutes = (total_seconds % 3600) / 60;
-            format!("{hours:02}:{minutes:02}:00")
-        })
+        value.map(|duration| TimedDuration::from(duration).to_string())
     }
 
     fn deserialize(&self, value: Option<String>) -> Option<Duration> {
-        value.and_then(|s| {
-            let parts: Vec<&str> = s.split(':').collect();
-            if parts.len() >= 2 {
-                if let (Ok(hours), Ok(minutes)) = (parts[0].parse::<i64>(), parts[1].parse::<i64>())
-                {
-                    return Some(Duration::hours(hours) + Duration::minutes(minutes));
-                }
+        value
+            .and_then(|s| s.parse::<TimedDuration>().ok())
+            .map(Duration::from)
+    }
+}
+
+/// Error returned when a duration can't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDurationError(String);
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid duration: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
+
+/// A signed duration in the format Timed uses, which is that of a Python `timedelta`
+///
+/// Accepts `08:30:00`, `-01:30:00`, `3 days, 01:15:00`, `-1 day, 22:30:00`
+/// and Django's `3 01:15:00`, with optional fractional seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimedDuration(Duration);
+
+impl TimedDuration {
+    /// A duration of zero
+    pub const ZERO: Self = Self(Duration::zero());
+
+    /// Create a duration from minutes
+    pub fn minutes(minutes: i64) -> Self {
+        Self(Duration::minutes(minutes))
+    }
+
+    /// Create a duration from seconds
+    pub fn seconds(seconds: i64) -> Self {
+        Self(Duration::seconds(seconds))
+    }
+
+    /// Create a duration from decimal hours, rounded to the second
+    ///
+    /// Returns `None` if the hours are not finite or out of range.
+    pub fn from_hours(hours: f64) -> Option<Self> {
+        let seconds = (hours * 3600.0).round();
+        if !seconds.is_finite() {
+            return None;
+        }
+        // Out of range values saturate, which `try_seconds` rejects
+        Duration::try_seconds(seconds as i64).map(Self)
+    }
+
+    /// Get the whole number of seconds
+    pub fn num_seconds(&self) -> i64 {
+        self.0.num_seconds()
+    }
+
+    /// Get the whole number of minutes
+    pub fn num_minutes(&self) -> i64 {
+        self.0.num_minutes()
+    }
+
+    /// Get the duration in decimal hours
+    pub fn as_hours(&self) -> f64 {
+        self.0.num_seconds() as f64 / 3600.0
+    }
+
+    /// Check whether the duration is below zero
+    pub fn is_negative(&self) -> bool {
+        self.0 < Duration::zero()
+    }
+
+    /// Sign, whole hours and remaining minutes of the duration
+    fn parts(&self) -> (&'static str, i64, i64) {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let minutes = self.0.num_minutes().abs();
+        (sign, minutes / 60, minutes % 60)
+    }
+
+    /// Format with an explicit sign, like `+12h30m` or `-1h30m`
+    pub fn format_signed(&self) -> String {
+        let (sign, hours, minutes) = self.parts();
+        let sign = if sign.is_empty() { "+" } else { sign };
+        format!("{sign}{hours}h{minutes:02}m")
+    }
+
+    /// Format as hours and minutes, like `12:30` or `-1:30`
+    pub fn format_clock(&self) -> String {
+        let (sign, hours, minutes) = self.parts();
+        format!("{sign}{hours}:{minutes:02}")
+    }
+
+    /// Format as decimal hours, like `12.50` or `-1.50`
+    pub fn format_decimal(&self) -> String {
+        format!("{:.2}", self.as_hours())
+    }
+
+    /// Format like Python's `str(timedelta)`, e.g. `-1 day, 22:30:00`
+    pub fn to_timedelta_string(&self) -> String {
+        let total = self.0.num_seconds();
+        let days = total.div_euclid(86_400);
+        let seconds = total.rem_euclid(86_400);
+        let time = format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        );
+        match days {
+            0 => time,
+            1 | -1 => format!("{days} day, {time}"),
+            _ => format!("{days} days, {time}"),
+        }
+    }
+}
+
+impl fmt::Display for TimedDuration {
+    /// Format as `HH:MM:SS`, with a leading `-` when negative and hours past 24
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let total = self.0.num_seconds().abs();
+        write!(
+            f,
+            "{sign}{:02}:{:02}:{:02}",
+            total / 3600,
+            total % 3600 / 60,
+            total % 60
+        )
+    }
+}
+
+impl FromStr for TimedDuration {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDurationError(s.to_string());
+        let s = s.trim();
+
+        // Step 1: Split off the day count of `3 days, 01:15:00` or `3 01:15:00`
+        let (days, time) = match s.rsplit_once(' ') {
+            Some((days, time)) => {
+                let days = days.trim_end_matches(',');
+                let days = days
+                    .strip_suffix("days")
+                    .or_else(|| days.strip_suffix("day"))
+                    .unwrap_or(days);
+                (days.trim().parse::<i64>().map_err(|_| error())?, time)
+            }
+            None => (0, s),
+        };
+
+        // Step 2: Parse the signed `[H]H:MM[:SS[.ffffff]]` part
+        let (negative, time) = match time.strip_prefix('-') {
+            Some(time) => (true, time),
+            None => (false, time),
+        };
+        let parts: Vec<&str> = time.split(':').collect();
+        if !(2..=3).contains(&parts.len()) {
+            return Err(error());
+        }
+        let (seconds, fraction) = match parts.get(2) {
+            Some(seconds) => seconds.split_once('.').unwrap_or((seconds, "")),
+            None => ("0", ""),
+        };
+        let number = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(error());
             }
-            None
+            part.parse::<i64>().map_err(|_| error())
+        };
+        let hours = number(parts[0])?;
+        let minutes = number(parts[1])?;
+        let seconds = number(seconds)?;
+        if minutes >= 60 || seconds >= 60 {
+            return Err(error());
+        }
+        let micros = if fraction.is_empty() {
+            0
+        } else {
+            let digits: String = fraction.chars().chain("00000".chars()).take(6).collect();
+            number(&digits)?
+        };
+
+        let time = Duration::try_hours(hours)
+            .and_then(|time| time.checked_add(&Duration::minutes(minutes)))
+            .and_then(|time| time.checked_add(&Duration::seconds(seconds)))
+            .and_then(|time| time.checked_add(&Duration::microseconds(micros)))
+            .ok_or_else(error)?;
+        let time = if negative { -time } else { time };
+
+        Duration::try_days(days)
+            .and_then(|days| days.checked_add(&time))
+            .map(Self)
+            .ok_or_else(error)
+    }
+}
+
+impl From<Duration> for TimedDuration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl From<TimedDuration> for Duration {
+    fn from(duration: TimedDuration) -> Self {
+        duration.0
+    }
+}
+
+impl Add for TimedDuration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for TimedDuration {
+    fn add_assign(&mut self, other: Self) {
+        self.0 = self.0 + other.0;
+    }
+}
+
+impl Sub for TimedDuration {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl Neg for TimedDuration {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Sum for TimedDuration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl Serialize for TimedDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimedDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&raw), &"a duration like 01:30:00")
         })
     }
 }
@@ -93,6 +339,108 @@ impl Transform<Option<String>, Option<String>> for IdTransform {
     }
 }
 
+/// Serialize a value through a transform
+fn serialize_with<T, X, S>(transform: &X, value: T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    X: Transform<Option<T>, Option<String>>,
+    S: Serializer,
+{
+    transform.serialize(Some(value)).serialize(serializer)
+}
+
+/// Deserialize a value through a transform, failing on values it can't parse
+fn deserialize_with<'de, T, X, D>(
+    transform: &X,
+    deserializer: D,
+    expected: &'static str,
+) -> Result<Option<T>, D::Error>
+where
+    X: Transform<Option<T>, Option<String>>,
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(raw) => transform
+            .deserialize(Some(raw.clone()))
+            .map(Some)
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(&raw), &expected)),
+        None => Ok(None),
+    }
+}
+
+/// Require a value that was deserialized through a transform
+fn required<'de, T, D: Deserializer<'de>>(value: Option<T>) -> Result<T, D::Error> {
+    value.ok_or_else(|| de::Error::custom("unexpected null value"))
+}
+
+/// Serde adapter for dates, for use with `#[serde(with = "...")]`
+pub mod date_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_with(&DateTransform, *value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        required::<_, D>(deserialize_with(
+            &DateTransform,
+            deserializer,
+            "a date like 2024-01-31",
+        )?)
+    }
+}
+
+/// Serde adapter for times of day
+pub mod time_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_with(&TimeTransform, *value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        required::<_, D>(deserialize_with(
+            &TimeTransform,
+            deserializer,
+            "a time like 08:30:00",
+        )?)
+    }
+}
+
+/// Serde adapter for optional times of day
+pub mod optional_time_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<NaiveTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        TimeTransform.serialize(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveTime>, D::Error> {
+        deserialize_with(&TimeTransform, deserializer, "a time like 08:30:00")
+    }
+}
+
+/// Serde adapter for durations
+pub mod duration_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_with(&DurationTransform, *value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        required::<_, D>(deserialize_with(
+            &DurationTransform,
+            deserializer,
+            "a duration like 01:30:00",
+        )?)
+    }
+}
+
 /// A relationship reference that can be serialized to/from API format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relationship {
@@ -105,6 +453,7 @@ pub struct Relationship {
 mod tests {
     use super::*;
     use chrono::Duration;
+    use rstest::rstest;
 
     #[test]
     fn test_date_transform() {
@@ -142,6 +491,86 @@ mod tests {
         assert_eq!(deserialized, Some(duration));
     }
 
+    #[rstest]
+    #[case("08:30:00", 8 * 3600 + 30 * 60)]
+    #[case("8:30", 8 * 3600 + 30 * 60)]
+    #[case("2000:00:00", 2000 * 3600)]
+    #[case("00:00:00", 0)]
+    #[case("-01:30:00", -(3600 + 30 * 60))]
+    #[case("-1:30", -(3600 + 30 * 60))]
+    #[case("-1 day, 22:30:00", -(3600 + 30 * 60))]
+    #[case("3 days, 01:15:00", 3 * 86_400 + 3600 + 15 * 60)]
+    #[case("1 day, 00:00:00", 86_400)]
+    #[case("3 01:15:00", 3 * 86_400 + 3600 + 15 * 60)]
+    #[case("-2 21:00:00", -(27 * 3600))]
+    #[case("00:00:01.500000", 1)]
+    fn test_parse_timed_duration(#[case] input: &str, #[case] seconds: i64) {
+        let duration: TimedDuration = input.parse().unwrap();
+        assert_eq!(duration.num_seconds(), seconds);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("abc")]
+    #[case("1:75")]
+    #[case("01:30:75")]
+    #[case("x days, 01:00:00")]
+    #[case("01:-30:00")]
+    #[case("1:2:3:4")]
+    #[case("99999999999999999:00:00")]
+    #[case("9999999999999 days, 00:00:00")]
+    #[case("-106751991167 days, -23:59:59")]
+    fn test_reject_invalid_timed_duration(#[case] input: &str) {
+        assert!(input.parse::<TimedDuration>().is_err());
+    }
+
+    #[rstest]
+    #[case(0, "00:00:00", "0:00:00", "+0h00m", "0:00", "0.00")]
+    #[case(45, "00:45:00", "0:45:00", "+0h45m", "0:45", "0.75")]
+    #[case(750, "12:30:00", "12:30:00", "+12h30m", "12:30", "12.50")]
+    #[case(-90, "-01:30:00", "-1 day, 22:30:00", "-1h30m", "-1:30", "-1.50")]
+    #[case(4395, "73:15:00", "3 days, 1:15:00", "+73h15m", "73:15", "73.25")]
+    #[case(-2880, "-48:00:00", "-2 days, 0:00:00", "-48h00m", "-48:00", "-48.00")]
+    fn test_format_timed_duration(
+        #[case] minutes: i64,
+        #[case] api: &str,
+        #[case] timedelta: &str,
+        #[case] signed: &str,
+        #[case] clock: &str,
+        #[case] decimal: &str,
+    ) {
+        let duration = TimedDuration::minutes(minutes);
+        assert_eq!(duration.to_string(), api);
+        assert_eq!(duration.to_timedelta_string(), timedelta);
+        assert_eq!(duration.format_signed(), signed);
+        assert_eq!(duration.format_clock(), clock);
+        assert_eq!(duration.format_decimal(), decimal);
+
+        // Both textual forms parse back to the same duration
+        assert_eq!(api.parse::<TimedDuration>().unwrap(), duration);
+        assert_eq!(timedelta.parse::<TimedDuration>().unwrap(), duration);
+    }
+
+    #[test]
+    fn test_timed_duration_serde() {
+        let balance: TimedDuration = serde_json::from_str("\"-1 day, 22:30:00\"").unwrap();
+        assert_eq!(balance, TimedDuration::minutes(-90));
+        assert_eq!(serde_json::to_string(&balance).unwrap(), "\"-01:30:00\"");
+        assert!(serde_json::from_str::<TimedDuration>("\"soon\"").is_err());
+    }
+
+    #[test]
+    fn test_timed_duration_from_hours_and_sum() {
+        assert_eq!(
+            TimedDuration::from_hours(1.25),
+            Some(TimedDuration::minutes(75))
+        );
+        assert_eq!(TimedDuration::from_hours(1e13), None);
+        assert_eq!(TimedDuration::from_hours(f64::NAN), None);
+        let total: TimedDuration = [90, -30, 15].into_iter().map(TimedDuration::minutes).sum();
+        assert_eq!(total, TimedDuration::minutes(75));
+    }
+
     #[test]
     fn test_boolean_transform() {
         let transform = BooleanTransform;
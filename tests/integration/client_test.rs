@@ -1,9 +1,11 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
 use libtimed::{models::*, Result, TimedClient};
 use mockito::{self, Server};
 use serde_json::json;
 
+#[test]
 fn test_client_get_users() -> Result<()> {
-    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let rt = tokio::runtime::Runtime::new().unwrap();
     let mut server = Server::new();
 
     // Mock the users endpoint
@@ -28,7 +30,7 @@ fn test_client_get_users() -> Result<()> {
         .create();
 
     // Create client with the mock server URL
-    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string()));
+    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string())).unwrap();
 
     // Call the API
     let response = rt.block_on(client.get::<serde_json::Value>("users/me", None))?;
@@ -43,8 +45,9 @@ fn test_client_get_users() -> Result<()> {
     Ok(())
 }
 
+#[test]
 fn test_client_get_activities() -> Result<()> {
-    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let rt = tokio::runtime::Runtime::new().unwrap();
     let mut server = Server::new();
 
     // Mock the activities endpoint
@@ -88,12 +91,14 @@ fn test_client_get_activities() -> Result<()> {
         .create();
 
     // Create client with the mock server URL
-    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string()));
+    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string())).unwrap();
 
     // Create filter params
-    let mut filter = FilterParams::default();
-    filter.date = Some("2023-07-15".to_string());
-    filter.include = Some("task,user".to_string());
+    let filter = FilterParams {
+        date: Some("2023-07-15".to_string()),
+        include: Some("task,user".to_string()),
+        ..Default::default()
+    };
 
     // Call the API
     let response =
@@ -103,7 +108,10 @@ fn test_client_get_activities() -> Result<()> {
     assert_eq!(response.data.len(), 1);
     assert_eq!(response.data[0].id, Some("123".to_string()));
     assert_eq!(response.data[0].attributes.comment, "Working on something");
-    assert_eq!(response.data[0].attributes.date, "2023-07-15");
+    assert_eq!(
+        response.data[0].attributes.date,
+        NaiveDate::from_ymd_opt(2023, 7, 15).unwrap()
+    );
 
     // Verify the mock was called
     mock.assert();
@@ -111,8 +119,9 @@ fn test_client_get_activities() -> Result<()> {
     Ok(())
 }
 
+#[test]
 fn test_client_post_activity() -> Result<()> {
-    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let rt = tokio::runtime::Runtime::new().unwrap();
     let mut server = Server::new();
 
     // Mock the activities endpoint for POST
@@ -153,7 +162,7 @@ fn test_client_post_activity() -> Result<()> {
         .create();
 
     // Create client with the mock server URL
-    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string()));
+    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string())).unwrap();
 
     // Create new activity
     let activity = Activity {
@@ -161,11 +170,12 @@ fn test_client_post_activity() -> Result<()> {
         type_name: "activities".to_string(),
         attributes: ActivityAttributes {
             comment: "New activity".to_string(),
-            date: "2023-07-16".to_string(),
-            from_time: "10:00:00".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 7, 16).unwrap(),
+            from_time: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
             to_time: None,
             review: false,
             not_billable: false,
+            transferred: false,
         },
         relationships: ActivityRelationships {
             user: Some(RelationshipData {
@@ -201,8 +211,9 @@ fn test_client_post_activity() -> Result<()> {
     Ok(())
 }
 
+#[test]
 fn test_client_worktime_balance() -> Result<()> {
-    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let rt = tokio::runtime::Runtime::new().unwrap();
     let mut server = Server::new();
 
     // Mock the worktime-balances endpoint
@@ -236,11 +247,13 @@ fn test_client_worktime_balance() -> Result<()> {
         .create();
 
     // Create client with the mock server URL
-    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string()));
+    let client = TimedClient::new(&server.url(), "api/v1", Some("mock-token".to_string())).unwrap();
 
     // Create filter params
-    let mut filter = FilterParams::default();
-    filter.date = Some("2023-07-15".to_string());
+    let filter = FilterParams {
+        date: Some("2023-07-15".to_string()),
+        ..Default::default()
+    };
 
     // Call the API
     let response = rt.block_on(
@@ -250,8 +263,11 @@ fn test_client_worktime_balance() -> Result<()> {
     // Verify the response
     assert_eq!(response.data.len(), 1);
     assert_eq!(response.data[0].id, Some("123".to_string()));
-    assert_eq!(response.data[0].attributes.date, "2023-07-15");
-    assert_eq!(response.data[0].attributes.balance, "08:30:00");
+    assert_eq!(
+        response.data[0].attributes.date,
+        NaiveDate::from_ymd_opt(2023, 7, 15).unwrap()
+    );
+    assert_eq!(response.data[0].attributes.balance, Duration::minutes(510));
 
     // Verify the mock was called
     mock.assert();
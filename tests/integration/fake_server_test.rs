@@ -0,0 +1,246 @@
+use chrono::{NaiveDate, NaiveTime};
+use libtimed::{
+    fake::{to_one, FakeTimed},
+    models::*,
+    TimedClient, TimedError,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn report(date: &str, duration: &str, task: &str) -> (serde_json::Value, serde_json::Value) {
+    (
+        json!({
+            "comment": format!("Work on {date}"),
+            "date": date,
+            "duration": duration,
+            "review": false,
+            "not-billable": false,
+            "verified": false
+        }),
+        json!({ "task": to_one("tasks", task) }),
+    )
+}
+
+#[tokio::test]
+async fn test_current_user() {
+    let server = FakeTimed::start().await.unwrap();
+    let client = server.client();
+
+    let user = client.fetch::<User>("me", None).await.unwrap();
+    assert_eq!(user.data.id, Some(server.current_user_id()));
+    assert_eq!(user.data.attributes.username, "fake");
+}
+
+#[tokio::test]
+async fn test_activity_crud() {
+    let server = FakeTimed::start().await.unwrap();
+    let client = server.client();
+    let task = server.add_task("Acme", "Website", "Development");
+
+    let activity = Activity {
+        id: None,
+        type_name: "activities".to_string(),
+        attributes: ActivityAttributes {
+            comment: "Pairing".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+            from_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            to_time: None,
+            review: false,
+            not_billable: false,
+            transferred: false,
+        },
+        relationships: ActivityRelationships {
+            user: None,
+            task: Some(RelationshipData {
+                data: Some(RelationshipResource {
+                    type_name: "tasks".to_string(),
+                    id: task.clone(),
+                }),
+            }),
+        },
+    };
+
+    // Create, which assigns an ID and the current user
+    let created = client.create(&activity).await.unwrap().data;
+    let id = created.id.clone().unwrap();
+    assert_eq!(
+        created.relationships.user.as_ref().and_then(|u| u.id()),
+        Some(server.current_user_id().as_str())
+    );
+
+    // Update
+    let mut stopped = created.clone();
+    stopped.attributes.to_time = NaiveTime::from_hms_opt(10, 30, 0);
+    let updated = client.update(&stopped).await.unwrap().data;
+    assert_eq!(
+        updated.attributes.to_time,
+        NaiveTime::from_hms_opt(10, 30, 0)
+    );
+    assert_eq!(
+        server.get("activities", &id).unwrap()["attributes"]["to-time"],
+        "10:30:00"
+    );
+
+    // Delete
+    client.destroy::<Activity>(&id).await.unwrap();
+    assert!(server.get("activities", &id).is_none());
+    assert!(matches!(
+        client.fetch::<Activity>(&id, None).await,
+        Err(TimedError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_filters_and_include() {
+    let server = FakeTimed::start().await.unwrap();
+    let client = server.client();
+    let development = server.add_task("Acme", "Website", "Development");
+    let tickets = server.add_task("Globex", "Support", "Tickets");
+
+    for (date, duration, task) in [
+        ("2024-03-01", "01:00:00", &development),
+        ("2024-03-02", "02:00:00", &tickets),
+        ("2024-03-05", "03:00:00", &development),
+    ] {
+        let (attributes, relationships) = report(date, duration, task);
+        server.insert("reports", attributes, relationships);
+    }
+
+    // Date range
+    let filter = FilterParams {
+        from_date: Some("2024-03-02".to_string()),
+        to_date: Some("2024-03-31".to_string()),
+        ..Default::default()
+    };
+    let reports = client.list_all::<Report>(Some(&filter)).await.unwrap();
+    assert_eq!(reports.data.len(), 2);
+
+    // Customer through task and project, with the whole path included
+    let customer = server.all("customers")[0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let filter = FilterParams {
+        customer: Some(customer),
+        include: Some("task,task.project,task.project.customer".to_string()),
+        ..Default::default()
+    };
+    let reports = client.list_all::<Report>(Some(&filter)).await.unwrap();
+    assert_eq!(reports.data.len(), 2);
+    let included = reports.included.as_ref().unwrap();
+    let types: Vec<&str> = included.iter().map(|r| r.type_name.as_str()).collect();
+    assert_eq!(types, vec!["tasks", "projects", "customers"]);
+
+    // Ordering
+    let mut filter = FilterParams::default();
+    filter
+        .custom
+        .insert("ordering".to_string(), "-date".to_string());
+    let reports = client.list::<Report>(Some(&filter)).await.unwrap();
+    let dates: Vec<String> = reports
+        .data
+        .iter()
+        .map(|r| r.attributes.date.to_string())
+        .collect();
+    assert_eq!(dates, vec!["2024-03-05", "2024-03-02", "2024-03-01"]);
+}
+
+#[tokio::test]
+async fn test_pagination() {
+    let server = FakeTimed::start().await.unwrap();
+    let client = server.client();
+    let task = server.add_task("Acme", "Website", "Development");
+
+    for day in 1..=7 {
+        let (attributes, relationships) = report(&format!("2024-03-{day:02}"), "01:00:00", &task);
+        server.insert("reports", attributes, relationships);
+    }
+
+    let mut filter = FilterParams::default();
+    filter
+        .custom
+        .insert("page[size]".to_string(), "3".to_string());
+    let reports = client.list_all::<Report>(Some(&filter)).await.unwrap();
+    assert_eq!(reports.data.len(), 7);
+
+    let pages = server
+        .requests()
+        .iter()
+        .filter(|r| r.starts_with("GET /api/v1/reports"))
+        .count();
+    assert_eq!(pages, 3);
+}
+
+#[tokio::test]
+async fn test_errors() {
+    let server = FakeTimed::start().await.unwrap();
+
+    // Missing required attributes
+    let result = server
+        .client()
+        .post::<_, serde_json::Value>(
+            "reports",
+            &json!({ "data": { "type": "reports", "attributes": { "comment": "x" } } }),
+        )
+        .await;
+    match result {
+        Err(TimedError::Api { status, errors }) => {
+            assert_eq!(status, 400);
+            let fields: Vec<&str> = errors.iter().filter_map(|e| e.field()).collect();
+            assert_eq!(fields, vec!["date", "duration"]);
+        }
+        other => panic!("Expected validation errors, got {other:?}"),
+    }
+
+    // No token
+    let client = TimedClient::new(&server.url(), "api/v1", None).unwrap();
+    assert!(matches!(
+        client.get::<serde_json::Value>("users/me", None).await,
+        Err(TimedError::AuthenticationRequired)
+    ));
+}
+
+#[tokio::test]
+async fn test_statistics_from_reports() {
+    let server = FakeTimed::start().await.unwrap();
+    let client = server.client();
+    let development = server.add_task("Acme", "Website", "Development");
+    let tickets = server.add_task("Globex", "Support", "Tickets");
+
+    for (date, duration, task) in [
+        ("2023-12-29", "05:00:00", &development),
+        ("2024-03-01", "20:00:00", &development),
+        ("2024-03-02", "06:00:00", &development),
+        ("2024-03-02", "02:00:00", &tickets),
+    ] {
+        let (attributes, relationships) = report(date, duration, task);
+        server.insert("reports", attributes, relationships);
+    }
+
+    let filter = FilterParams {
+        from_date: Some("2024-01-01".to_string()),
+        include: Some("customer".to_string()),
+        ..Default::default()
+    };
+    let stats = client
+        .list_all::<CustomerStatistic>(Some(&filter))
+        .await
+        .unwrap();
+    let durations: Vec<i64> = stats
+        .data
+        .iter()
+        .map(|s| s.attributes.duration.num_hours())
+        .collect();
+    assert_eq!(durations, vec![26, 2]);
+    assert_eq!(stats.included.unwrap().len(), 2);
+
+    let mut filter = FilterParams::default();
+    filter.custom.insert("year".to_string(), "2024".to_string());
+    let stats = client
+        .list_all::<YearStatistic>(Some(&filter))
+        .await
+        .unwrap();
+    assert_eq!(stats.data.len(), 1);
+    assert_eq!(stats.data[0].attributes.year, 2024);
+    assert_eq!(stats.data[0].attributes.duration.num_hours(), 28);
+}
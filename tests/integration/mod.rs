@@ -1,2 +1,3 @@
 mod client_test;
+mod fake_server_test;
 mod models_test;
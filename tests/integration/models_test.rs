@@ -1,3 +1,4 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
 use libtimed::models::*;
 use std::collections::HashMap;
 
@@ -28,9 +29,9 @@ fn test_attendance_serialization() {
         id: Some("123".to_string()),
         type_name: "attendances".to_string(),
         attributes: AttendanceAttributes {
-            date: "2023-08-15".to_string(),
-            from_time: "09:00:00".to_string(),
-            to_time: Some("17:00:00".to_string()),
+            date: NaiveDate::from_ymd_opt(2023, 8, 15).unwrap(),
+            from_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            to_time: NaiveTime::from_hms_opt(17, 0, 0),
         },
         relationships: AttendanceRelationships {
             user: Some(RelationshipData {
@@ -70,7 +71,7 @@ fn test_absence_serialization() {
         id: Some("123".to_string()),
         type_name: "absences".to_string(),
         attributes: AbsenceAttributes {
-            date: "2023-08-15".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 8, 15).unwrap(),
             comment: Some("Vacation".to_string()),
         },
         relationships: AbsenceRelationships {
@@ -112,8 +113,8 @@ fn test_statistics_models() {
         type_name: "year-statistics".to_string(),
         attributes: YearStatisticAttributes {
             year: 2023,
-            duration: "2000:00:00".to_string(),
-            total_attendance: "2100:00:00".to_string(),
+            duration: Duration::hours(2000),
+            total_attendance: Duration::hours(2100),
         },
         relationships: YearStatisticRelationships {
             user: Some(RelationshipData {
@@ -138,8 +139,8 @@ fn test_statistics_models() {
         attributes: MonthStatisticAttributes {
             year: 2023,
             month: 8,
-            duration: "160:00:00".to_string(),
-            total_attendance: "168:00:00".to_string(),
+            duration: Duration::hours(160),
+            total_attendance: Duration::hours(168),
         },
         relationships: MonthStatisticRelationships {
             user: Some(RelationshipData {
@@ -169,6 +170,10 @@ fn test_filter_params() {
         user: Some("123".to_string()),
         review: Some(0),
         not_billable: Some(0),
+        verified: Some(1),
+        rejected: Some(0),
+        billed: Some(1),
+        comment: Some("standup".to_string()),
         task: Some("456".to_string()),
         project: Some("789".to_string()),
         customer: Some("101".to_string()),
@@ -190,6 +195,10 @@ fn test_filter_params() {
     assert_eq!(json["user"], "123");
     assert_eq!(json["review"], 0);
     assert_eq!(json["not_billable"], 0);
+    assert_eq!(json["verified"], 1);
+    assert_eq!(json["rejected"], 0);
+    assert_eq!(json["billed"], 1);
+    assert_eq!(json["comment"], "standup");
     assert_eq!(json["task"], "456");
     assert_eq!(json["project"], "789");
     assert_eq!(json["customer"], "101");